@@ -17,6 +17,7 @@ const MAX_RECTANGLE_ARRAY_SIZE = 31u;
 struct KdTreeNode {
     @align(16) location: vec2<f32>,
     radius: f32,
+    color: vec3<f32>,
 };
 
 struct KdTree {
@@ -54,10 +55,11 @@ fn draw_circle(
     uv: vec2<f32>,
     center: vec2<f32>,
     radius: f32,
+    color: vec3<f32>,
 ) -> vec4<f32> {
     let distance: f32 = distance(uv, center);
     if distance <= (radius + SHAPE_THICKNESS) && radius <= distance {
-        return mix_colors(base, SHAPE_COLOR);
+        return mix_colors(base, vec4<f32>(color, SHAPE_COLOR.a));
     }
     return base;
 }
@@ -143,7 +145,7 @@ fn draw_circles(base: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
         let node = circles.nodes[index];
         let center = node.location;
         let radius = node.radius;
-        output_color = draw_circle(output_color, uv, center, radius);
+        output_color = draw_circle(output_color, uv, center, radius, node.color);
     }
 
     return output_color;
@@ -53,7 +53,7 @@ impl ChaseTargetEvent {
 
 /// Units with this component will chase the target entity.
 #[derive(Component, Deref)]
-struct ChaseTargetComponent(ChaseTarget);
+pub(crate) struct ChaseTargetComponent(ChaseTarget);
 
 impl ChaseTargetComponent {
     fn new(target: ChaseTarget) -> Self {
@@ -0,0 +1,204 @@
+//! This module implements following (escorting) of other units: a following
+//! unit keeps a constant offset to the position of its leader.
+
+use bevy::prelude::*;
+use de_core::gamestate::GameState;
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPathEvent};
+use de_types::projection::ToFlat;
+
+use crate::chase::ChaseTargetComponent;
+
+/// Following units are sent to their position relative to the leader whenever
+/// their path target gets further than this from it.
+const FOLLOW_TOLERANCE: f32 = 2.;
+
+pub(crate) struct FollowPlugin;
+
+impl Plugin for FollowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FollowEvent>()
+            .add_event::<FollowEndedEvent>()
+            .add_systems(
+                PreUpdate,
+                handle_follow_events
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(FollowSet::FollowEvent),
+            )
+            .add_systems(Update, follow.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum FollowSet {
+    FollowEvent,
+}
+
+/// Send this event to start or stop following of a unit.
+#[derive(Event)]
+pub struct FollowEvent {
+    entity: Entity,
+    following: Option<Following>,
+}
+
+impl FollowEvent {
+    /// # Arguments
+    ///
+    /// * `entity` - the following entity.
+    ///
+    /// * `following` - follow order or None if following shall be stopped.
+    pub fn new(entity: Entity, following: Option<Following>) -> Self {
+        Self { entity, following }
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn following(&self) -> Option<Following> {
+        self.following
+    }
+}
+
+/// This event is sent when a unit stops following its leader because the
+/// leader no longer exists.
+#[derive(Event)]
+pub struct FollowEndedEvent(Entity);
+
+impl FollowEndedEvent {
+    /// Returns the entity which was following the leader.
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Order to keep a constant offset to a (moving) leader.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Following {
+    target: Entity,
+    offset: Vec2,
+}
+
+impl Following {
+    /// # Arguments
+    ///
+    /// * `target` - the leader.
+    ///
+    /// * `offset` - position of the follower relative to the leader in map
+    ///   coordinates.
+    pub fn new(target: Entity, offset: Vec2) -> Self {
+        Self { target, offset }
+    }
+
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+
+    /// Returns desired position of the follower given position of the leader.
+    fn position(&self, leader: Vec2) -> Vec2 {
+        leader + self.offset
+    }
+}
+
+fn handle_follow_events(mut commands: Commands, mut events: EventReader<FollowEvent>) {
+    for event in events.iter() {
+        let mut entity_commands = commands.entity(event.entity());
+        match event.following() {
+            Some(following) => entity_commands.insert(following),
+            None => entity_commands.remove::<Following>(),
+        };
+    }
+}
+
+fn follow(
+    mut commands: Commands,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+    mut ended_events: EventWriter<FollowEndedEvent>,
+    // Chasing (e.g. during an attack) takes precedence over following.
+    followers: Query<
+        (Entity, &Transform, &Following, Option<&PathTarget>),
+        Without<ChaseTargetComponent>,
+    >,
+    leaders: Query<&Transform>,
+) {
+    for (entity, transform, following, path_target) in followers.iter() {
+        let Ok(leader) = leaders.get(following.target()) else {
+            commands.entity(entity).remove::<Following>();
+            ended_events.send(FollowEndedEvent(entity));
+            continue;
+        };
+        let position = following.position(leader.translation.to_flat());
+
+        let current = path_target
+            .map(|path_target| path_target.location())
+            .unwrap_or(transform.translation.to_flat());
+        if current.distance(position) <= FOLLOW_TOLERANCE {
+            continue;
+        }
+
+        path_events.send(UpdateEntityPathEvent::new(
+            entity,
+            PathTarget::new(position, PathQueryProps::new(0., f32::INFINITY), false),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_follow() {
+        let mut app = App::new();
+        app.add_event::<UpdateEntityPathEvent>()
+            .add_event::<FollowEndedEvent>()
+            .add_systems(Update, follow);
+
+        let leader = app.world.spawn(Transform::from_xyz(10., 0., -10.)).id();
+        let offset = Vec2::new(-5., 3.);
+        let follower = app
+            .world
+            .spawn((
+                Transform::from_xyz(0., 0., 0.),
+                Following::new(leader, offset),
+            ))
+            .id();
+
+        let mut path_state = SystemState::<EventReader<UpdateEntityPathEvent>>::new(&mut app.world);
+        let mut targets = |world: &mut World| -> Vec<(Entity, Vec2)> {
+            path_state
+                .get_mut(world)
+                .iter()
+                .map(|event| (event.entity(), event.target().location()))
+                .collect()
+        };
+
+        app.update();
+        assert_eq!(
+            targets(&mut app.world),
+            vec![(follower, Vec2::new(5., 13.))]
+        );
+
+        for (x, y) in [(20., 10.), (30., 25.)] {
+            app.world.get_mut::<Transform>(leader).unwrap().translation = Vec3::new(x, 0., -y);
+            app.update();
+            assert_eq!(
+                targets(&mut app.world),
+                vec![(follower, Vec2::new(x, y) + offset)]
+            );
+        }
+
+        app.world.despawn(leader);
+        app.update();
+        assert!(targets(&mut app.world).is_empty());
+        assert!(app.world.get::<Following>(follower).is_none());
+        let mut ended_state = SystemState::<EventReader<FollowEndedEvent>>::new(&mut app.world);
+        let ended: Vec<Entity> = ended_state
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| event.entity())
+            .collect();
+        assert_eq!(ended, vec![follower]);
+    }
+}
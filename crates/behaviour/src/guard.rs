@@ -0,0 +1,188 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use de_core::gamestate::GameState;
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPathEvent};
+use de_types::projection::ToFlat;
+
+use crate::chase::ChaseTargetComponent;
+
+/// Guarding units are sent back to their guard position whenever they get
+/// further than this from it.
+const GUARD_TOLERANCE: f32 = 2.;
+
+pub(crate) struct GuardPlugin;
+
+impl Plugin for GuardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GuardEvent>()
+            .add_systems(
+                PreUpdate,
+                handle_guard_events
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(GuardSet::GuardEvent),
+            )
+            .add_systems(Update, guard.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum GuardSet {
+    GuardEvent,
+}
+
+/// Send this event to start or stop guarding of a point or an entity.
+#[derive(Event)]
+pub struct GuardEvent {
+    entity: Entity,
+    guard: Option<Guard>,
+}
+
+impl GuardEvent {
+    /// # Arguments
+    ///
+    /// * `entity` - the guarding entity.
+    ///
+    /// * `guard` - guard order or None if guarding shall be stopped.
+    pub fn new(entity: Entity, guard: Option<Guard>) -> Self {
+        Self { entity, guard }
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn guard(&self) -> Option<Guard> {
+        self.guard
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GuardTarget {
+    /// A static point on the map.
+    Point(Vec2),
+    /// An entity (movable or static). The guard position follows the entity.
+    Entity(Entity),
+}
+
+/// Order to keep a position on a circle around a guarded target.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Guard {
+    target: GuardTarget,
+    radius: f32,
+    angle: f32,
+}
+
+impl Guard {
+    /// Creates guard orders which distribute `count` units evenly on a circle
+    /// around the target.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - the guarded point or entity.
+    ///
+    /// * `radius` - radius of the guard circle in meters.
+    ///
+    /// * `count` - number of guarding units.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `radius` is not a finite non-negative number.
+    pub fn formation(target: GuardTarget, radius: f32, count: usize) -> impl Iterator<Item = Self> {
+        debug_assert!(radius.is_finite());
+        debug_assert!(radius >= 0.);
+
+        (0..count).map(move |i| Self {
+            target,
+            radius,
+            angle: TAU * i as f32 / count as f32,
+        })
+    }
+
+    pub fn target(&self) -> GuardTarget {
+        self.target
+    }
+
+    /// Returns radius of the guard circle in meters.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Returns guard position of the unit given position of the guarded
+    /// target.
+    fn position(&self, center: Vec2) -> Vec2 {
+        center + self.radius * Vec2::from_angle(self.angle)
+    }
+}
+
+fn handle_guard_events(mut commands: Commands, mut events: EventReader<GuardEvent>) {
+    for event in events.iter() {
+        let mut entity_commands = commands.entity(event.entity());
+        match event.guard() {
+            Some(guard) => entity_commands.insert(guard),
+            None => entity_commands.remove::<Guard>(),
+        };
+    }
+}
+
+fn guard(
+    mut commands: Commands,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+    // Chasing (e.g. during an attack) takes precedence over guarding.
+    guarding: Query<
+        (Entity, &Transform, &Guard, Option<&PathTarget>),
+        Without<ChaseTargetComponent>,
+    >,
+    targets: Query<&Transform>,
+) {
+    for (entity, transform, guard, path_target) in guarding.iter() {
+        let center = match guard.target() {
+            GuardTarget::Point(point) => point,
+            GuardTarget::Entity(target) => match targets.get(target) {
+                Ok(transform) => transform.translation.to_flat(),
+                Err(_) => {
+                    commands.entity(entity).remove::<Guard>();
+                    continue;
+                }
+            },
+        };
+        let position = guard.position(center);
+
+        let (path_target, distance) = path_target
+            .map(|path_target| (path_target.location(), path_target.properties().distance()))
+            .unwrap_or((transform.translation.to_flat(), 0.));
+
+        if (position - path_target).length() + distance <= GUARD_TOLERANCE {
+            continue;
+        }
+
+        path_events.send(UpdateEntityPathEvent::new(
+            entity,
+            PathTarget::new(position, PathQueryProps::new(0., f32::INFINITY), false),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formation() {
+        let center = Vec2::new(10., -20.);
+        let positions: Vec<Vec2> = Guard::formation(GuardTarget::Point(center), 5., 4)
+            .map(|guard| guard.position(center))
+            .collect();
+        assert_eq!(positions.len(), 4);
+
+        for (i, position) in positions.iter().enumerate() {
+            let offset = *position - center;
+            assert!((offset.length() - 5.).abs() < 1e-5);
+
+            let next = positions[(i + 1) % 4] - center;
+            assert!((offset.angle_between(next) - TAU / 4.).abs() < 1e-5);
+        }
+        assert!(positions[0].abs_diff_eq(Vec2::new(15., -20.), 1e-5));
+        assert!(positions[1].abs_diff_eq(Vec2::new(10., -15.), 1e-5));
+    }
+}
@@ -0,0 +1,77 @@
+//! This module implements recording of recently traveled paths of units. The
+//! history is used to retrace the path, see [`crate::CommandQueueEvent::fall_back`].
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use de_core::{
+    gamestate::GameState,
+    objects::{Local, MovableSolid},
+};
+use de_types::projection::ToFlat;
+
+/// Maximum number of recorded positions per unit.
+const HISTORY_LENGTH: usize = 16;
+/// A new position is recorded once a unit gets at least this far (in meters)
+/// from the last recorded position.
+const HISTORY_SPACING: f32 = 10.;
+
+pub(crate) struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (setup_units, record_positions).run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Bounded history of recently visited positions of a unit.
+#[derive(Component, Default)]
+pub struct PositionHistory {
+    positions: VecDeque<Vec2>,
+}
+
+impl PositionHistory {
+    /// Returns recorded positions in map coordinates from the oldest to the
+    /// most recent one.
+    pub fn positions(&self) -> impl DoubleEndedIterator<Item = Vec2> + '_ {
+        self.positions.iter().cloned()
+    }
+
+    /// Records a position if it is far enough from the last recorded
+    /// position. The oldest position is forgotten once the history is full.
+    pub(crate) fn record(&mut self, position: Vec2) {
+        if self
+            .positions
+            .back()
+            .is_some_and(|last| last.distance(position) < HISTORY_SPACING)
+        {
+            return;
+        }
+
+        if self.positions.len() == HISTORY_LENGTH {
+            self.positions.pop_front();
+        }
+        self.positions.push_back(position);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.positions.clear();
+    }
+}
+
+type NewUnits = (With<MovableSolid>, Added<Local>);
+
+fn setup_units(mut commands: Commands, units: Query<Entity, NewUnits>) {
+    for entity in units.iter() {
+        commands.entity(entity).insert(PositionHistory::default());
+    }
+}
+
+fn record_positions(mut units: Query<(&Transform, &mut PositionHistory), Changed<Transform>>) {
+    for (transform, mut history) in units.iter_mut() {
+        history.record(transform.translation.to_flat());
+    }
+}
@@ -3,13 +3,43 @@
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
 use chase::ChasePlugin;
 pub use chase::{ChaseSet, ChaseTarget, ChaseTargetEvent};
+use follow::FollowPlugin;
+pub use follow::{FollowEndedEvent, FollowEvent, FollowSet, Following};
+use guard::GuardPlugin;
+pub use guard::{Guard, GuardEvent, GuardSet, GuardTarget};
+use history::HistoryPlugin;
+pub use history::PositionHistory;
+use queue::QueuePlugin;
+pub use queue::{
+    CommandQueue, CommandQueueEvent, DelayedCommand, GoSignalEvent, Order, QueueSet, StartAt,
+};
+use release::ReleasePlugin;
+use scout::ScoutPlugin;
+pub use scout::{ScoutEvent, ScoutSet};
+use transport::TransportPlugin;
+pub use transport::{CargoCapacity, CargoFullEvent, Carried, LoadEvent, TransportSet, UnloadEvent};
 
 mod chase;
+mod follow;
+mod guard;
+mod history;
+mod queue;
+mod release;
+mod scout;
+mod transport;
 
 pub struct BehaviourPluginGroup;
 
 impl PluginGroup for BehaviourPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(ChasePlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(ChasePlugin)
+            .add(FollowPlugin)
+            .add(GuardPlugin)
+            .add(HistoryPlugin)
+            .add(QueuePlugin)
+            .add(ReleasePlugin)
+            .add(ScoutPlugin)
+            .add(TransportPlugin)
     }
 }
@@ -0,0 +1,468 @@
+//! This module implements command queues: orders given to a unit are executed
+//! one after another.
+//!
+//! A queued order might be delayed, i.e. it does not start before a given
+//! time or before a go signal is given (see [`GoSignalEvent`]).
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{ecs::query::Has, prelude::*};
+use de_core::{
+    gamestate::GameState,
+    objects::{Local, MovableSolid, Playable},
+};
+use de_pathing::{PathQueryProps, PathTarget, ScheduledPath, UpdateEntityPathEvent};
+use de_spawner::WaypointsOnSpawn;
+
+use crate::history::PositionHistory;
+
+pub(crate) struct QueuePlugin;
+
+impl Plugin for QueuePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CommandQueueEvent>()
+            .add_event::<GoSignalEvent>()
+            .add_systems(
+                PreUpdate,
+                (
+                    setup_units,
+                    handle_queue_events
+                        .after(setup_units)
+                        .in_set(QueueSet::QueueEvent),
+                    handle_go_signal
+                        .run_if(on_event::<GoSignalEvent>())
+                        .after(QueueSet::QueueEvent),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, execute_orders.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum QueueSet {
+    QueueEvent,
+}
+
+/// Send this event to modify command queue of a unit.
+#[derive(Event)]
+pub struct CommandQueueEvent {
+    entity: Entity,
+    action: QueueAction,
+}
+
+impl CommandQueueEvent {
+    /// Appends an order to the end of the queue.
+    pub fn append(entity: Entity, order: Order) -> Self {
+        Self::new(
+            entity,
+            QueueAction::Append(DelayedCommand::immediate(order)),
+        )
+    }
+
+    /// Appends a delayed order to the end of the queue.
+    pub fn append_delayed(entity: Entity, command: DelayedCommand) -> Self {
+        Self::new(entity, QueueAction::Append(command))
+    }
+
+    /// Removes all orders from the queue.
+    pub fn clear(entity: Entity) -> Self {
+        Self::new(entity, QueueAction::Clear)
+    }
+
+    /// Removes the most recently appended order, see [`CommandQueue::undo`].
+    pub fn undo(entity: Entity) -> Self {
+        Self::new(entity, QueueAction::Undo)
+    }
+
+    /// Replaces all orders with moves retracing recently traveled path of the
+    /// unit (see [`PositionHistory`]) in the reverse direction.
+    pub fn fall_back(entity: Entity) -> Self {
+        Self::new(entity, QueueAction::FallBack)
+    }
+
+    fn new(entity: Entity, action: QueueAction) -> Self {
+        Self { entity, action }
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn action(&self) -> QueueAction {
+        self.action
+    }
+}
+
+/// Send this event to release all queued orders waiting for the go signal
+/// (see [`StartAt::GoSignal`]) of all playable units.
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct GoSignalEvent;
+
+#[derive(Clone, Copy)]
+enum QueueAction {
+    Append(DelayedCommand),
+    Clear,
+    Undo,
+    FallBack,
+}
+
+/// A single order of a command queue.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Order {
+    /// Move to a point on the map.
+    Move(Vec2),
+}
+
+/// Condition which must be met before a queued order is started.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartAt {
+    /// The order is not started before this time (measured since the app
+    /// start-up).
+    Time(Duration),
+    /// The order is not started before [`GoSignalEvent`] is sent.
+    GoSignal,
+}
+
+/// A queued order which starts only after a condition is met. The condition
+/// is checked only once all preceding orders are finished.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DelayedCommand {
+    command: Order,
+    start_at: Option<StartAt>,
+}
+
+impl DelayedCommand {
+    pub fn new(command: Order, start_at: StartAt) -> Self {
+        Self {
+            command,
+            start_at: Some(start_at),
+        }
+    }
+
+    fn immediate(command: Order) -> Self {
+        Self {
+            command,
+            start_at: None,
+        }
+    }
+
+    pub fn command(&self) -> Order {
+        self.command
+    }
+
+    /// Returns true if the order might be started at time `now`.
+    fn ready(&self, now: Duration) -> bool {
+        match self.start_at {
+            None => true,
+            Some(StartAt::Time(time)) => now >= time,
+            Some(StartAt::GoSignal) => false,
+        }
+    }
+}
+
+/// Orders given to a unit. The first order is the one being executed.
+#[derive(Component, Default)]
+pub struct CommandQueue {
+    orders: VecDeque<DelayedCommand>,
+    /// True if execution of the first order has already started.
+    started: bool,
+}
+
+impl CommandQueue {
+    /// Returns the order being executed.
+    pub fn current(&self) -> Option<Order> {
+        self.orders.front().map(DelayedCommand::command)
+    }
+
+    /// Returns all orders in the order of their execution.
+    pub fn orders(&self) -> impl Iterator<Item = Order> + '_ {
+        self.orders.iter().map(DelayedCommand::command)
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    fn push(&mut self, command: DelayedCommand) {
+        self.orders.push_back(command);
+    }
+
+    fn clear(&mut self) {
+        self.orders.clear();
+        self.started = false;
+    }
+
+    /// Removes the most recently appended order. The order being executed is
+    /// removed only if it is the only order in the queue.
+    fn undo(&mut self) -> Option<Order> {
+        let command = self.orders.pop_back();
+        if self.orders.is_empty() {
+            self.started = false;
+        }
+        command.map(|command| command.command())
+    }
+
+    /// Releases all orders waiting for the go signal.
+    fn go(&mut self) {
+        for command in self.orders.iter_mut() {
+            if command.start_at == Some(StartAt::GoSignal) {
+                command.start_at = None;
+            }
+        }
+    }
+
+    /// Marks the current order as finished.
+    fn finish(&mut self) {
+        if self.started {
+            self.orders.pop_front();
+            self.started = false;
+        }
+    }
+
+    /// Starts and returns the first order if it is not started yet and its
+    /// start condition is met at time `now`.
+    fn start(&mut self, now: Duration) -> Option<Order> {
+        if self.started {
+            return None;
+        }
+
+        let command = self.orders.front().filter(|command| command.ready(now))?;
+        self.started = true;
+        Some(command.command())
+    }
+}
+
+/// Creates a queue of orders which start immediately one after another.
+impl FromIterator<Order> for CommandQueue {
+    fn from_iter<T: IntoIterator<Item = Order>>(iter: T) -> Self {
+        Self {
+            orders: iter.into_iter().map(DelayedCommand::immediate).collect(),
+            started: false,
+        }
+    }
+}
+
+type NewUnits = (With<MovableSolid>, Added<Local>);
+
+/// Inserts a command queue to freshly spawned units and to units taken over
+/// from another computer. Units spawned with [`WaypointsOnSpawn`] start with a
+/// move order to each of the waypoints.
+fn setup_units(
+    mut commands: Commands,
+    units: Query<(Entity, Option<&WaypointsOnSpawn>), NewUnits>,
+) {
+    for (entity, waypoints) in units.iter() {
+        let mut entity_commands = commands.entity(entity);
+        match waypoints {
+            Some(waypoints) => {
+                let queue: CommandQueue = waypoints
+                    .waypoints()
+                    .iter()
+                    .map(|&point| Order::Move(point))
+                    .collect();
+                entity_commands.insert(queue).remove::<WaypointsOnSpawn>();
+            }
+            None => {
+                entity_commands.insert(CommandQueue::default());
+            }
+        }
+    }
+}
+
+fn handle_queue_events(
+    mut commands: Commands,
+    mut queues: Query<(&mut CommandQueue, Option<&mut PositionHistory>)>,
+    mut events: EventReader<CommandQueueEvent>,
+) {
+    for event in events.iter() {
+        let Ok((mut queue, history)) = queues.get_mut(event.entity()) else {
+            continue;
+        };
+
+        match event.action() {
+            QueueAction::Append(command) => queue.push(command),
+            QueueAction::Clear => queue.clear(),
+            QueueAction::Undo => {
+                let started = queue.started;
+                if queue.undo().is_some() && started && queue.is_empty() {
+                    // The unit stops once its only order is undone.
+                    commands
+                        .entity(event.entity())
+                        .remove::<(PathTarget, ScheduledPath)>();
+                }
+            }
+            QueueAction::FallBack => {
+                let Some(mut history) = history else {
+                    continue;
+                };
+
+                queue.clear();
+                for position in history.positions().rev() {
+                    queue.push(DelayedCommand::immediate(Order::Move(position)));
+                }
+                // The retreat path is not retraced by a subsequent fall back.
+                history.clear();
+            }
+        }
+    }
+}
+
+fn handle_go_signal(
+    mut queues: Query<&mut CommandQueue, With<Playable>>,
+    mut events: EventReader<GoSignalEvent>,
+) {
+    events.clear();
+    for mut queue in queues.iter_mut() {
+        queue.go();
+    }
+}
+
+fn execute_orders(
+    time: Res<Time>,
+    mut queues: Query<(Entity, &mut CommandQueue, Has<PathTarget>)>,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+) {
+    for (entity, mut queue, moving) in queues.iter_mut() {
+        if queue.is_empty() || (queue.started && moving) {
+            continue;
+        }
+
+        queue.finish();
+        match queue.start(time.elapsed()) {
+            Some(Order::Move(target)) => {
+                path_events.send(UpdateEntityPathEvent::new(
+                    entity,
+                    PathTarget::new(target, PathQueryProps::exact(), false),
+                ));
+            }
+            None => continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo() {
+        let mut app = App::new();
+        app.add_event::<CommandQueueEvent>()
+            .add_systems(Update, handle_queue_events);
+        let unit = app.world.spawn(CommandQueue::default()).id();
+
+        for x in [1., 2., 3.] {
+            app.world
+                .send_event(CommandQueueEvent::append(unit, Order::Move(Vec2::splat(x))));
+        }
+        app.update();
+        assert_eq!(app.world.get::<CommandQueue>(unit).unwrap().len(), 3);
+
+        app.world.send_event(CommandQueueEvent::undo(unit));
+        app.world.send_event(CommandQueueEvent::undo(unit));
+        app.update();
+        let queue = app.world.get::<CommandQueue>(unit).unwrap();
+        assert_eq!(
+            queue.orders().collect::<Vec<_>>(),
+            vec![Order::Move(Vec2::splat(1.))]
+        );
+
+        let mut queue = CommandQueue::default();
+        queue.push(DelayedCommand::immediate(Order::Move(Vec2::ZERO)));
+        assert_eq!(queue.start(Duration::ZERO), Some(Order::Move(Vec2::ZERO)));
+        queue.push(DelayedCommand::immediate(Order::Move(Vec2::ONE)));
+        // The executed order is kept while there are other orders.
+        assert_eq!(queue.undo(), Some(Order::Move(Vec2::ONE)));
+        assert_eq!(queue.current(), Some(Order::Move(Vec2::ZERO)));
+        assert_eq!(queue.undo(), Some(Order::Move(Vec2::ZERO)));
+        assert!(queue.is_empty());
+        assert_eq!(queue.start(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_fall_back() {
+        let mut app = App::new();
+        app.add_event::<CommandQueueEvent>()
+            .add_systems(Update, handle_queue_events);
+
+        let recorded = [
+            Vec2::new(0., 0.),
+            Vec2::new(12., 0.),
+            Vec2::new(12., 15.),
+            Vec2::new(30., 20.),
+        ];
+        let mut history = PositionHistory::default();
+        for position in recorded {
+            history.record(position);
+            // Positions close to the last recorded position are skipped.
+            history.record(position + Vec2::new(1., 1.));
+        }
+        let mut queue = CommandQueue::default();
+        queue.push(DelayedCommand::immediate(Order::Move(Vec2::splat(100.))));
+        let unit = app.world.spawn((queue, history)).id();
+
+        app.world.send_event(CommandQueueEvent::fall_back(unit));
+        app.update();
+        let expected: Vec<Order> = recorded.iter().rev().map(|&p| Order::Move(p)).collect();
+        assert_eq!(
+            app.world
+                .get::<CommandQueue>(unit)
+                .unwrap()
+                .orders()
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            app.world
+                .get::<PositionHistory>(unit)
+                .unwrap()
+                .positions()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_delayed() {
+        let order = Order::Move(Vec2::new(3., 4.));
+        let start_at = StartAt::Time(Duration::from_secs(5));
+
+        let mut queue = CommandQueue::default();
+        queue.push(DelayedCommand::new(order, start_at));
+        assert_eq!(queue.start(Duration::from_secs(1)), None);
+        assert_eq!(queue.start(Duration::from_millis(4999)), None);
+        assert_eq!(queue.start(Duration::from_secs(5)), Some(order));
+        // The order is started only once.
+        assert_eq!(queue.start(Duration::from_secs(6)), None);
+        queue.finish();
+        assert!(queue.is_empty());
+
+        let mut app = App::new();
+        app.add_event::<GoSignalEvent>()
+            .add_systems(Update, handle_go_signal.run_if(on_event::<GoSignalEvent>()));
+        let delayed = || {
+            let mut queue = CommandQueue::default();
+            queue.push(DelayedCommand::new(order, StartAt::GoSignal));
+            queue
+        };
+        let unit = app.world.spawn((Playable, delayed())).id();
+        let foreign = app.world.spawn(delayed()).id();
+
+        app.update();
+        let mut queue = app.world.get_mut::<CommandQueue>(unit).unwrap();
+        assert_eq!(queue.start(Duration::MAX), None);
+
+        app.world.send_event(GoSignalEvent);
+        app.update();
+        let mut queue = app.world.get_mut::<CommandQueue>(unit).unwrap();
+        assert_eq!(queue.start(Duration::ZERO), Some(order));
+        assert_eq!(queue.start(Duration::ZERO), None);
+        let mut queue = app.world.get_mut::<CommandQueue>(foreign).unwrap();
+        assert_eq!(queue.start(Duration::MAX), None);
+    }
+}
@@ -0,0 +1,47 @@
+//! This module stops behaviors of units which are no longer simulated by this
+//! computer, e.g. after their ownership was transferred to a player simulated
+//! elsewhere. The new simulating computer takes over from scratch.
+
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, objects::Local};
+
+use crate::{
+    chase::ChaseTargetComponent,
+    follow::Following,
+    guard::Guard,
+    history::PositionHistory,
+    queue::CommandQueue,
+    scout::Scouting,
+    transport::{Boarding, UnloadAt},
+};
+
+pub(crate) struct ReleasePlugin;
+
+impl Plugin for ReleasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            release_units.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+type Behaviors = (
+    CommandQueue,
+    PositionHistory,
+    ChaseTargetComponent,
+    Following,
+    Guard,
+    Scouting,
+    Boarding,
+    UnloadAt,
+);
+
+fn release_units(mut commands: Commands, mut released: RemovedComponents<Local>) {
+    for entity in released.iter() {
+        // Despawned entities are reported as well.
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<Behaviors>();
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! This module implements scouting: a scouting unit is repeatedly sent to
+//! the nearest tile not yet explored by its owner (see [`FogMap`]).
+//!
+//! Scouting stops once the unit is given another order (i.e. its path target
+//! changes), once the unit stops before its target is explored (e.g. because
+//! the target is not reachable) or once the whole map is explored.
+
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, player::PlayerComponent};
+use de_index::FogMap;
+use de_map::size::MapBounds;
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPathEvent};
+use de_types::projection::ToFlat;
+
+pub(crate) struct ScoutPlugin;
+
+impl Plugin for ScoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScoutEvent>()
+            .add_systems(
+                PreUpdate,
+                handle_scout_events
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(ScoutSet::ScoutEvent),
+            )
+            .add_systems(Update, scout.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum ScoutSet {
+    ScoutEvent,
+}
+
+/// Send this event to start or stop scouting of a unit.
+#[derive(Event)]
+pub struct ScoutEvent {
+    entity: Entity,
+    scouting: bool,
+}
+
+impl ScoutEvent {
+    /// # Arguments
+    ///
+    /// * `entity` - the scouting entity.
+    ///
+    /// * `scouting` - true if scouting shall be started, false if it shall be
+    ///   stopped.
+    pub fn new(entity: Entity, scouting: bool) -> Self {
+        Self { entity, scouting }
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn scouting(&self) -> bool {
+        self.scouting
+    }
+}
+
+/// Scouting order. It holds the point the unit is currently sent to.
+#[derive(Component, Default)]
+pub(crate) struct Scouting(Option<Vec2>);
+
+impl Scouting {
+    /// Updates the scouting target and returns the action to be taken by the
+    /// unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_target` - location of the current path target of the unit.
+    ///
+    /// * `explored` - returns true if a point lies on an explored tile.
+    ///
+    /// * `nearest` - returns the nearest unexplored point.
+    fn update(
+        &mut self,
+        path_target: Option<Vec2>,
+        explored: impl Fn(Vec2) -> bool,
+        nearest: impl FnOnce() -> Option<Vec2>,
+    ) -> ScoutAction {
+        if let Some(target) = self.0 {
+            match path_target {
+                Some(location) if location != target => return ScoutAction::Stop,
+                Some(_) if !explored(target) => return ScoutAction::Continue,
+                None if !explored(target) => return ScoutAction::Stop,
+                _ => (),
+            }
+        }
+
+        self.0 = nearest();
+        match self.0 {
+            Some(target) => ScoutAction::MoveTo(target),
+            None => ScoutAction::Stop,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScoutAction {
+    /// Keep moving to the current target.
+    Continue,
+    /// Move to a new target.
+    MoveTo(Vec2),
+    /// Stop scouting.
+    Stop,
+}
+
+fn handle_scout_events(mut commands: Commands, mut events: EventReader<ScoutEvent>) {
+    for event in events.iter() {
+        let mut entity_commands = commands.entity(event.entity());
+        if event.scouting() {
+            entity_commands.insert(Scouting::default());
+        } else {
+            entity_commands.remove::<Scouting>();
+        }
+    }
+}
+
+fn scout(
+    mut commands: Commands,
+    fog: Res<FogMap>,
+    bounds: Res<MapBounds>,
+    mut scouts: Query<(
+        Entity,
+        &PlayerComponent,
+        &Transform,
+        &mut Scouting,
+        Option<&PathTarget>,
+    )>,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+) {
+    let aabb = bounds.aabb();
+    for (entity, &player, transform, mut scouting, path_target) in scouts.iter_mut() {
+        let action = scouting.update(
+            path_target.map(|path_target| path_target.location()),
+            |point| fog.is_explored(*player, fog.tile(point)),
+            || fog.nearest_unexplored(*player, transform.translation.to_flat(), &aabb),
+        );
+
+        match action {
+            ScoutAction::Continue => (),
+            ScoutAction::MoveTo(target) => {
+                path_events.send(UpdateEntityPathEvent::new(
+                    entity,
+                    PathTarget::new(target, PathQueryProps::new(0., f32::INFINITY), false),
+                ));
+            }
+            ScoutAction::Stop => {
+                commands.entity(entity).remove::<Scouting>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retarget() {
+        let first = Vec2::new(15., 65.);
+        let second = Vec2::new(65., 15.);
+        let mut explored: Vec<Vec2> = Vec::new();
+        let nearest = |explored: &[Vec2]| {
+            [first, second]
+                .into_iter()
+                .find(|point| !explored.contains(point))
+        };
+
+        let mut scouting = Scouting::default();
+        assert_eq!(
+            scouting.update(None, |p| explored.contains(&p), || nearest(&explored)),
+            ScoutAction::MoveTo(first)
+        );
+        assert_eq!(
+            scouting.update(
+                Some(first),
+                |p| explored.contains(&p),
+                || nearest(&explored)
+            ),
+            ScoutAction::Continue
+        );
+
+        // The unit has reached the first target which got explored.
+        explored.push(first);
+        assert_eq!(
+            scouting.update(None, |p| explored.contains(&p), || nearest(&explored)),
+            ScoutAction::MoveTo(second)
+        );
+        assert_eq!(
+            scouting.update(
+                Some(second),
+                |p| explored.contains(&p),
+                || nearest(&explored)
+            ),
+            ScoutAction::Continue
+        );
+
+        // Another order was given to the unit.
+        assert_eq!(
+            scouting.update(
+                Some(Vec2::ZERO),
+                |p| explored.contains(&p),
+                || nearest(&explored)
+            ),
+            ScoutAction::Stop
+        );
+    }
+}
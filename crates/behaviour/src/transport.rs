@@ -0,0 +1,474 @@
+//! This module implements transportation of units: units board a transport
+//! (an entity with [`CargoCapacity`]), are carried by it and are unloaded at
+//! a point on the map.
+
+use std::f32::consts::TAU;
+
+use bevy::{ecs::query::Has, prelude::*};
+use de_core::{gamestate::GameState, objects::Playable};
+use de_index::Unindexed;
+use de_pathing::{PathQueryProps, PathTarget, ScheduledPath, UpdateEntityPathEvent};
+use de_types::projection::ToFlat;
+
+/// Units board a transport once they get this close (in meters) to it.
+/// Transports unload their cargo once they get this close to the unload
+/// point.
+const LOAD_DISTANCE: f32 = 8.;
+/// Unloaded units are placed on a circle of this radius (in meters) around
+/// the transport.
+const UNLOAD_RADIUS: f32 = 6.;
+
+pub(crate) struct TransportPlugin;
+
+impl Plugin for TransportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadEvent>()
+            .add_event::<UnloadEvent>()
+            .add_event::<CargoFullEvent>()
+            .add_systems(
+                PreUpdate,
+                (handle_load_events, handle_unload_events)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(TransportSet::TransportEvent),
+            )
+            .add_systems(
+                Update,
+                (board, carry.after(board), unload.after(carry))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum TransportSet {
+    TransportEvent,
+}
+
+/// Send this event to order units to board a transport. Units which do not
+/// fit to the transport are not ordered and [`CargoFullEvent`] is sent for
+/// each of them.
+#[derive(Event)]
+pub struct LoadEvent {
+    units: Vec<Entity>,
+    transport: Entity,
+}
+
+impl LoadEvent {
+    pub fn new(units: Vec<Entity>, transport: Entity) -> Self {
+        Self { units, transport }
+    }
+
+    fn units(&self) -> &[Entity] {
+        self.units.as_slice()
+    }
+
+    fn transport(&self) -> Entity {
+        self.transport
+    }
+}
+
+/// Send this event to order a transport to move to a point and unload all
+/// units it carries there.
+#[derive(Event)]
+pub struct UnloadEvent {
+    transport: Entity,
+    point: Vec2,
+}
+
+impl UnloadEvent {
+    pub fn new(transport: Entity, point: Vec2) -> Self {
+        Self { transport, point }
+    }
+
+    fn transport(&self) -> Entity {
+        self.transport
+    }
+
+    fn point(&self) -> Vec2 {
+        self.point
+    }
+}
+
+/// This event is sent when a unit is not loaded because the transport is
+/// full.
+#[derive(Event)]
+pub struct CargoFullEvent {
+    unit: Entity,
+    transport: Entity,
+}
+
+impl CargoFullEvent {
+    pub fn unit(&self) -> Entity {
+        self.unit
+    }
+
+    pub fn transport(&self) -> Entity {
+        self.transport
+    }
+}
+
+/// Entities with this component might transport units.
+#[derive(Component)]
+pub struct CargoCapacity {
+    capacity: usize,
+    /// Units carried by the transport or boarding it.
+    cargo: Vec<Entity>,
+}
+
+impl CargoCapacity {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            cargo: Vec::new(),
+        }
+    }
+
+    /// Maximum number of transported units.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Units carried by the transport or on their way to board it.
+    pub fn cargo(&self) -> &[Entity] {
+        self.cargo.as_slice()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cargo.len() >= self.capacity
+    }
+
+    fn remove(&mut self, unit: Entity) {
+        self.cargo.retain(|&entity| entity != unit);
+    }
+}
+
+/// Units with this component are carried by a transport. Carried units are
+/// not indexed and cannot be played until they are unloaded.
+#[derive(Component)]
+pub struct Carried {
+    transport: Entity,
+    /// True if the unit was playable before it boarded the transport.
+    playable: bool,
+}
+
+impl Carried {
+    pub fn transport(&self) -> Entity {
+        self.transport
+    }
+
+    /// Returns the unit back to the map.
+    fn release(&self, commands: &mut Commands, unit: Entity) {
+        let mut unit = commands.entity(unit);
+        unit.remove::<(Carried, Unindexed)>();
+        if self.playable {
+            unit.insert(Playable);
+        }
+    }
+}
+
+/// Units with this component are on their way to board a transport.
+#[derive(Component)]
+pub(crate) struct Boarding {
+    transport: Entity,
+    /// True once the unit started moving towards the transport, i.e. once its
+    /// path target was set.
+    moving: bool,
+}
+
+impl Boarding {
+    fn new(transport: Entity) -> Self {
+        Self {
+            transport,
+            moving: false,
+        }
+    }
+}
+
+/// Transports with this component unload their cargo at the point.
+#[derive(Component)]
+pub(crate) struct UnloadAt {
+    point: Vec2,
+    /// True once the transport started moving towards the point.
+    moving: bool,
+}
+
+impl UnloadAt {
+    fn new(point: Vec2) -> Self {
+        Self {
+            point,
+            moving: false,
+        }
+    }
+}
+
+fn handle_load_events(
+    mut commands: Commands,
+    mut transports: Query<(&Transform, &mut CargoCapacity)>,
+    mut load_events: EventReader<LoadEvent>,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+    mut full_events: EventWriter<CargoFullEvent>,
+) {
+    for event in load_events.iter() {
+        let transport = event.transport();
+        let Ok((transform, mut capacity)) = transports.get_mut(transport) else {
+            continue;
+        };
+
+        for &unit in event.units() {
+            if unit == transport || capacity.cargo().contains(&unit) {
+                continue;
+            }
+            if capacity.is_full() {
+                full_events.send(CargoFullEvent { unit, transport });
+                continue;
+            }
+
+            capacity.cargo.push(unit);
+            commands.entity(unit).insert(Boarding::new(transport));
+            path_events.send(UpdateEntityPathEvent::new(
+                unit,
+                PathTarget::new(
+                    transform.translation.to_flat(),
+                    PathQueryProps::new(0., 0.5 * LOAD_DISTANCE),
+                    false,
+                ),
+            ));
+        }
+    }
+}
+
+fn handle_unload_events(
+    mut commands: Commands,
+    transports: Query<(), With<CargoCapacity>>,
+    mut unload_events: EventReader<UnloadEvent>,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+) {
+    for event in unload_events.iter() {
+        if !transports.contains(event.transport()) {
+            continue;
+        }
+
+        commands
+            .entity(event.transport())
+            .insert(UnloadAt::new(event.point()));
+        path_events.send(UpdateEntityPathEvent::new(
+            event.transport(),
+            PathTarget::new(event.point(), PathQueryProps::exact(), false),
+        ));
+    }
+}
+
+type BoardingComponents<'a> = (
+    Entity,
+    &'a mut Boarding,
+    &'a Transform,
+    Has<PathTarget>,
+    Has<Playable>,
+);
+
+fn board(
+    mut commands: Commands,
+    mut transports: Query<(&Transform, &mut CargoCapacity)>,
+    mut boarding: Query<BoardingComponents, Without<CargoCapacity>>,
+) {
+    for (unit, mut boarding, transform, has_path, playable) in boarding.iter_mut() {
+        let transport = boarding.transport;
+        let Ok((transport_transform, mut capacity)) = transports.get_mut(transport) else {
+            commands.entity(unit).remove::<Boarding>();
+            continue;
+        };
+
+        let distance = transform
+            .translation
+            .to_flat()
+            .distance(transport_transform.translation.to_flat());
+        if distance <= LOAD_DISTANCE {
+            commands
+                .entity(unit)
+                .remove::<(Boarding, PathTarget, ScheduledPath, Playable)>()
+                .insert((
+                    Carried {
+                        transport,
+                        playable,
+                    },
+                    Unindexed,
+                    Visibility::Hidden,
+                ));
+        } else if has_path {
+            boarding.moving = true;
+        } else if boarding.moving {
+            // The unit stopped (e.g. due to another order) before it reached
+            // the transport.
+            capacity.remove(unit);
+            commands.entity(unit).remove::<Boarding>();
+        }
+    }
+}
+
+/// Carried units are moved together with their transport.
+fn carry(
+    mut commands: Commands,
+    transports: Query<&Transform, (With<CargoCapacity>, Without<Carried>)>,
+    mut carried: Query<(Entity, &Carried, &mut Transform, &mut Visibility)>,
+) {
+    for (unit, carried, mut transform, mut visibility) in carried.iter_mut() {
+        match transports.get(carried.transport()) {
+            Ok(transport) => {
+                if transform.translation != transport.translation {
+                    transform.translation = transport.translation;
+                }
+            }
+            Err(_) => {
+                // The transport no longer exists, the units are released at
+                // its last known position.
+                *visibility = Visibility::Inherited;
+                carried.release(&mut commands, unit);
+            }
+        }
+    }
+}
+
+fn unload(
+    mut commands: Commands,
+    mut transports: Query<(
+        Entity,
+        &Transform,
+        &mut UnloadAt,
+        &mut CargoCapacity,
+        Has<PathTarget>,
+    )>,
+    mut carried: Query<(&Carried, &mut Transform, &mut Visibility), Without<CargoCapacity>>,
+) {
+    for (transport, transform, mut unload_at, mut capacity, has_path) in transports.iter_mut() {
+        let position = transform.translation.to_flat();
+        if position.distance(unload_at.point) > LOAD_DISTANCE {
+            if has_path {
+                unload_at.moving = true;
+                continue;
+            }
+            // The point might be unreachable, thus the cargo is unloaded as
+            // close as possible.
+            if !unload_at.moving {
+                continue;
+            }
+        }
+        commands.entity(transport).remove::<UnloadAt>();
+
+        let units: Vec<Entity> = capacity
+            .cargo()
+            .iter()
+            .cloned()
+            .filter(|&unit| {
+                carried
+                    .get(unit)
+                    .is_ok_and(|(carried, _, _)| carried.transport() == transport)
+            })
+            .collect();
+
+        for (i, &unit) in units.iter().enumerate() {
+            capacity.remove(unit);
+
+            let (unit_carried, mut unit_transform, mut visibility) = carried.get_mut(unit).unwrap();
+            let offset = UNLOAD_RADIUS * Vec2::from_angle(TAU * i as f32 / units.len() as f32);
+            unit_transform.translation = transform.translation + Vec3::new(offset.x, 0., -offset.y);
+            *visibility = Visibility::Inherited;
+            unit_carried.release(&mut commands, unit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let mut app = App::new();
+        app.add_event::<LoadEvent>()
+            .add_event::<UnloadEvent>()
+            .add_event::<UpdateEntityPathEvent>()
+            .add_event::<CargoFullEvent>()
+            .add_systems(
+                Update,
+                (
+                    handle_load_events,
+                    board,
+                    handle_unload_events,
+                    unload.after(handle_unload_events),
+                ),
+            );
+
+        let transport = app
+            .world
+            .spawn((Transform::from_xyz(0., 0., 0.), CargoCapacity::new(2)))
+            .id();
+        let units: Vec<Entity> = [1., 2., 3.]
+            .into_iter()
+            .map(|x| {
+                app.world
+                    .spawn((
+                        Playable,
+                        Transform::from_xyz(x, 0., 0.),
+                        Visibility::Inherited,
+                    ))
+                    .id()
+            })
+            .collect();
+
+        app.world
+            .send_event(LoadEvent::new(units.clone(), transport));
+        app.update();
+        app.update();
+
+        assert_eq!(
+            app.world.get::<CargoCapacity>(transport).unwrap().cargo(),
+            &units[..2]
+        );
+        for &unit in &units[..2] {
+            assert_eq!(
+                app.world.get::<Carried>(unit).unwrap().transport(),
+                transport
+            );
+            assert_eq!(
+                app.world.get::<Visibility>(unit).unwrap(),
+                Visibility::Hidden
+            );
+            assert!(app.world.get::<Unindexed>(unit).is_some());
+            assert!(app.world.get::<Playable>(unit).is_none());
+        }
+        assert!(app.world.get::<Carried>(units[2]).is_none());
+        assert!(app.world.get::<Playable>(units[2]).is_some());
+        assert!(app.world.get::<Boarding>(units[2]).is_none());
+
+        let mut state = SystemState::<EventReader<CargoFullEvent>>::new(&mut app.world);
+        let full: Vec<(Entity, Entity)> = state
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| (event.unit(), event.transport()))
+            .collect();
+        assert_eq!(full, vec![(units[2], transport)]);
+
+        app.world
+            .send_event(UnloadEvent::new(transport, Vec2::ZERO));
+        app.update();
+        app.update();
+
+        assert!(app
+            .world
+            .get::<CargoCapacity>(transport)
+            .unwrap()
+            .cargo()
+            .is_empty());
+        for &unit in &units[..2] {
+            assert!(app.world.get::<Carried>(unit).is_none());
+            assert!(app.world.get::<Unindexed>(unit).is_none());
+            assert!(app.world.get::<Playable>(unit).is_some());
+            assert_eq!(
+                app.world.get::<Visibility>(unit).unwrap(),
+                Visibility::Inherited
+            );
+        }
+    }
+}
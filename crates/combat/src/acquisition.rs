@@ -0,0 +1,333 @@
+//! This module implements automatic target acquisition: idle locally
+//! simulated combat units attack the nearest enemy within their acquisition
+//! range. Units guarding a point intercept enemies anywhere within the guarded
+//! area instead.
+
+use bevy::prelude::*;
+use de_behaviour::{Guard, GuardTarget};
+use de_core::{
+    gamestate::GameState,
+    gconfig::GameConfig,
+    objects::{Active, Local},
+    player::PlayerComponent,
+};
+use de_index::SpatialQuery;
+use de_objects::LaserCannon;
+use de_pathing::{PathTarget, ScheduledPath};
+use de_types::projection::ToFlat;
+
+use crate::{
+    attack::{AttackEvent, Attacking},
+    AttackingSet,
+};
+
+pub(crate) struct AcquisitionPlugin;
+
+impl Plugin for AcquisitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (
+                setup_units,
+                acquire.after(setup_units).before(AttackingSet::Attack),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Maximum distance of enemies automatically attacked by an idle combat unit.
+/// It defaults to the range of the unit's cannon.
+#[derive(Component, Clone, Copy)]
+pub struct AcquisitionRange(f32);
+
+impl AcquisitionRange {
+    /// # Panics
+    ///
+    /// Panics if `range` is not a finite non-negative number.
+    pub fn new(range: f32) -> Self {
+        assert!(range.is_finite() && range >= 0.);
+        Self(range)
+    }
+
+    pub fn range(&self) -> f32 {
+        self.0
+    }
+}
+
+/// Combat behavior of a unit.
+#[derive(Component, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Stance {
+    /// Idle unit automatically attacks enemies within its acquisition range.
+    #[default]
+    Aggressive,
+    /// The unit attacks only when commanded to.
+    HoldFire,
+    /// Idle unit automatically attacks enemies within its acquisition range
+    /// like an aggressive unit but it returns to its original position once
+    /// the attacked enemy is destroyed.
+    StandGround,
+}
+
+type NewUnits = (With<LaserCannon>, Added<Local>);
+
+fn setup_units(mut commands: Commands, units: Query<(Entity, &LaserCannon), NewUnits>) {
+    for (entity, cannon) in units.iter() {
+        commands
+            .entity(entity)
+            .insert((AcquisitionRange::new(cannon.range()), Stance::default()));
+    }
+}
+
+type IdleUnits = (With<Local>, Without<Attacking>, Without<ScheduledPath>);
+type IdleUnitComponents<'a> = (
+    Entity,
+    &'a Transform,
+    &'a PlayerComponent,
+    &'a AcquisitionRange,
+    &'a Stance,
+    Option<&'a PathTarget>,
+    Option<&'a Guard>,
+);
+
+fn acquire(
+    config: Res<GameConfig>,
+    space: SpatialQuery<()>,
+    units: Query<IdleUnitComponents, IdleUnits>,
+    targets: Query<(&Transform, &PlayerComponent), With<Active>>,
+    mut events: EventWriter<AttackEvent>,
+) {
+    for (entity, transform, &player, range, &stance, path, guard) in units.iter() {
+        if stance == Stance::HoldFire {
+            continue;
+        }
+        // Units returning to their pre-combat position are not idle even
+        // before their path is scheduled.
+        if stance == Stance::StandGround && path.is_some_and(|path| !path.permanent()) {
+            continue;
+        }
+
+        let position = transform.translation.to_flat();
+        let (center, radius) = search_circle(position, range, guard);
+        let enemy = space
+            .entities_in_circle(center, radius)
+            .into_iter()
+            .filter_map(|candidate| {
+                targets
+                    .get(candidate)
+                    .ok()
+                    .filter(|(_, &owner)| config.are_enemies(*owner, *player))
+                    .map(|(target, _)| (candidate, target.translation.to_flat()))
+            })
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+
+        if let Some((enemy, _)) = enemy {
+            events.send(AttackEvent::new(entity, enemy));
+        }
+    }
+}
+
+/// Returns center and radius of the circle searched for enemies. Units
+/// guarding a point search the guarded area, which is the guard circle
+/// widened by the acquisition range, so that enemies entering it are
+/// intercepted by any of the guarding units.
+fn search_circle(position: Vec2, range: &AcquisitionRange, guard: Option<&Guard>) -> (Vec2, f32) {
+    match guard.map(|guard| (guard.target(), guard.radius())) {
+        Some((GuardTarget::Point(center), radius)) => (center, radius + range.range()),
+        _ => (position, range.range()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_core::gconfig::LocalPlayers;
+    use de_index::{EntityIndex, LocalCollider};
+    use de_objects::ObjectCollider;
+    use de_pathing::UpdateEntityPathEvent;
+    use de_types::player::Player;
+    use parry3d::{
+        math::{Isometry, Vector},
+        shape::{Cuboid, TriMesh, TriMeshFlags},
+    };
+
+    use super::*;
+    use crate::attack::finish_attacks;
+
+    fn spawn(world: &mut World, x: f32, player: Player) -> Entity {
+        let entity = world
+            .spawn((
+                Active,
+                Transform::from_xyz(x, 0., 0.),
+                PlayerComponent::from(player),
+            ))
+            .id();
+
+        let mut trimesh: TriMesh = Cuboid::new(Vector::new(1., 1., 1.)).into();
+        trimesh.set_flags(TriMeshFlags::ORIENTED).unwrap();
+        let collider = LocalCollider::new(
+            ObjectCollider::from(trimesh),
+            Isometry::translation(x, 0., 0.),
+        );
+        world.resource_mut::<EntityIndex>().insert(entity, collider);
+
+        entity
+    }
+
+    fn config() -> GameConfig {
+        GameConfig::new(
+            "map.tar",
+            false,
+            LocalPlayers::from_max_player(Player::Player1, Player::Player3),
+        )
+        .with_allies(&[Player::Player3])
+    }
+
+    #[test]
+    fn test_acquire() {
+        let mut app = App::new();
+        app.insert_resource(config())
+            .insert_resource(EntityIndex::new())
+            .add_event::<AttackEvent>()
+            .add_systems(Update, acquire);
+
+        let aggressive = spawn(&mut app.world, 0., Player::Player1);
+        app.world.entity_mut(aggressive).insert((
+            Local,
+            AcquisitionRange::new(20.),
+            Stance::Aggressive,
+        ));
+        let hold_fire = spawn(&mut app.world, -30., Player::Player1);
+        app.world.entity_mut(hold_fire).insert((
+            Local,
+            AcquisitionRange::new(20.),
+            Stance::HoldFire,
+        ));
+        spawn(&mut app.world, 3., Player::Player1);
+        // Allied units are never attacked.
+        spawn(&mut app.world, 5., Player::Player3);
+        let enemy = spawn(&mut app.world, -15., Player::Player2);
+        spawn(&mut app.world, 18., Player::Player2);
+        spawn(&mut app.world, 100., Player::Player2);
+
+        app.update();
+
+        let mut state = SystemState::<EventReader<AttackEvent>>::new(&mut app.world);
+        let mut events = state.get_mut(&mut app.world);
+        let events: Vec<(Entity, Entity)> = events
+            .iter()
+            .map(|event| (event.attacker(), event.enemy()))
+            .collect();
+        assert_eq!(events, vec![(aggressive, enemy)]);
+    }
+
+    #[test]
+    fn test_stand_ground() {
+        let mut app = App::new();
+        app.insert_resource(config())
+            .insert_resource(EntityIndex::new())
+            .add_event::<AttackEvent>()
+            .add_event::<UpdateEntityPathEvent>()
+            .add_systems(Update, (finish_attacks, acquire.after(finish_attacks)));
+
+        let first = spawn(&mut app.world, 10., Player::Player2);
+        let second = spawn(&mut app.world, 110., Player::Player2);
+        let next = spawn(&mut app.world, 115., Player::Player2);
+
+        let stand_ground = spawn(&mut app.world, 0., Player::Player1);
+        app.world.entity_mut(stand_ground).insert((
+            Local,
+            AcquisitionRange::new(20.),
+            Stance::StandGround,
+            Attacking::new(first, Vec2::new(-5., 3.)),
+        ));
+        let pursue = spawn(&mut app.world, 100., Player::Player1);
+        app.world.entity_mut(pursue).insert((
+            Local,
+            AcquisitionRange::new(20.),
+            Stance::Aggressive,
+            Attacking::new(second, Vec2::new(90., 0.)),
+        ));
+
+        for target in [first, second] {
+            app.world.despawn(target);
+            app.world
+                .resource_mut::<EntityIndex>()
+                .remove(target)
+                .unwrap();
+        }
+        app.update();
+        app.update();
+
+        assert!(app.world.get::<Attacking>(stand_ground).is_none());
+
+        let mut paths = SystemState::<EventReader<UpdateEntityPathEvent>>::new(&mut app.world);
+        let paths: Vec<(Entity, Vec2)> = paths
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| (event.entity(), event.target().location()))
+            .collect();
+        assert_eq!(paths, vec![(stand_ground, Vec2::new(-5., 3.))]);
+
+        let mut attacks = SystemState::<EventReader<AttackEvent>>::new(&mut app.world);
+        let attacks: Vec<(Entity, Entity)> = attacks
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| (event.attacker(), event.enemy()))
+            .collect();
+        assert_eq!(attacks, vec![(pursue, next)]);
+    }
+
+    #[test]
+    fn test_guard_area() {
+        let mut app = App::new();
+        app.insert_resource(config())
+            .insert_resource(EntityIndex::new())
+            .add_event::<AttackEvent>()
+            .add_event::<UpdateEntityPathEvent>()
+            .add_systems(Update, (finish_attacks, acquire.after(finish_attacks)));
+
+        let guard = Guard::formation(GuardTarget::Point(Vec2::ZERO), 5., 1)
+            .next()
+            .unwrap();
+        let guarding = spawn(&mut app.world, 5., Player::Player1);
+        app.world.entity_mut(guarding).insert((
+            Local,
+            AcquisitionRange::new(10.),
+            Stance::Aggressive,
+            guard,
+        ));
+        // Out of the acquisition range of the unit but inside the guarded
+        // area.
+        let intruder = spawn(&mut app.world, -9., Player::Player2);
+        spawn(&mut app.world, -20., Player::Player2);
+
+        app.update();
+
+        let mut attacks = SystemState::<EventReader<AttackEvent>>::new(&mut app.world);
+        let events: Vec<(Entity, Entity)> = attacks
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| (event.attacker(), event.enemy()))
+            .collect();
+        assert_eq!(events, vec![(guarding, intruder)]);
+
+        app.world
+            .entity_mut(guarding)
+            .insert(Attacking::new(intruder, Vec2::new(5., 0.)));
+        app.world.despawn(intruder);
+        app.world
+            .resource_mut::<EntityIndex>()
+            .remove(intruder)
+            .unwrap();
+        app.update();
+
+        // The attack is over and the unit is left to its guard order.
+        assert!(app.world.get::<Attacking>(guarding).is_none());
+        assert_eq!(app.world.get::<Guard>(guarding), Some(&guard));
+        assert_eq!(attacks.get_mut(&mut app.world).iter().count(), 0);
+    }
+}
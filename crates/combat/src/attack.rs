@@ -2,13 +2,19 @@ use std::{cmp::Ordering, collections::BinaryHeap};
 
 use bevy::prelude::*;
 use de_behaviour::{ChaseSet, ChaseTarget, ChaseTargetEvent};
-use de_core::{gamestate::GameState, objects::ObjectTypeComponent};
+use de_core::{
+    gamestate::GameState,
+    objects::{Local, ObjectTypeComponent},
+};
 use de_index::SpatialQuery;
 use de_objects::{LaserCannon, SolidObjects};
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPathEvent};
+use de_spawner::AttackOnSpawn;
+use de_types::projection::ToFlat;
 use parry3d::query::Ray;
 
 use crate::laser::LaserFireEvent;
-use crate::{sightline::LineOfSight, AttackingSet};
+use crate::{sightline::LineOfSight, AttackingSet, Stance};
 
 /// Multiple of cannon range. The attacking entities will try to stay as close
 /// or further from attacked targets.
@@ -25,9 +31,12 @@ impl Plugin for AttackPlugin {
             .add_systems(
                 PreUpdate,
                 (
+                    release_units.before(AttackingSet::Attack),
+                    attack_on_spawn.before(AttackingSet::Attack),
                     attack
                         .in_set(AttackingSet::Attack)
                         .before(ChaseSet::ChaseTargetEvent),
+                    finish_attacks.after(AttackingSet::Attack),
                     update_positions.after(AttackingSet::Attack),
                 )
                     .run_if(in_state(GameState::Playing)),
@@ -62,26 +71,29 @@ impl AttackEvent {
         Self { attacker, enemy }
     }
 
-    fn attacker(&self) -> Entity {
+    pub(crate) fn attacker(&self) -> Entity {
         self.attacker
     }
 
-    fn enemy(&self) -> Entity {
+    pub(crate) fn enemy(&self) -> Entity {
         self.enemy
     }
 }
 
 #[derive(Component)]
-struct Attacking {
+pub(crate) struct Attacking {
     enemy: Entity,
+    /// Position of the attacker on the map from before the combat.
+    origin: Vec2,
     muzzle: Vec3,
     target: Option<Vec3>,
 }
 
 impl Attacking {
-    fn new(enemy: Entity) -> Self {
+    pub(crate) fn new(enemy: Entity, origin: Vec2) -> Self {
         Self {
             enemy,
+            origin,
             muzzle: Vec3::ZERO,
             target: None,
         }
@@ -99,17 +111,31 @@ impl Attacking {
     }
 }
 
+/// Stops attacks of units which are no longer simulated by this computer.
+fn release_units(mut commands: Commands, mut released: RemovedComponents<Local>) {
+    for entity in released.iter() {
+        // Despawned entities are reported as well.
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<Attacking>();
+        }
+    }
+}
+
 fn attack(
     mut commands: Commands,
     mut attack_events: EventReader<AttackEvent>,
-    cannons: Query<&LaserCannon>,
+    cannons: Query<(&LaserCannon, &Transform, Option<&Attacking>)>,
     mut chase_events: EventWriter<ChaseTargetEvent>,
 ) {
     for event in attack_events.iter() {
-        if let Ok(cannon) = cannons.get(event.attacker()) {
+        if let Ok((cannon, transform, attacking)) = cannons.get(event.attacker()) {
+            // Switching targets does not change the pre-combat position.
+            let origin = attacking.map_or(transform.translation.to_flat(), |attacking| {
+                attacking.origin
+            });
             commands
                 .entity(event.attacker())
-                .insert(Attacking::new(event.enemy()));
+                .insert(Attacking::new(event.enemy(), origin));
 
             let target = ChaseTarget::new(
                 event.enemy(),
@@ -121,33 +147,70 @@ fn attack(
     }
 }
 
-fn update_positions(
+fn attack_on_spawn(
     mut commands: Commands,
+    spawned: Query<(Entity, &AttackOnSpawn), Added<AttackOnSpawn>>,
+    mut events: EventWriter<AttackEvent>,
+) {
+    for (attacker, attack) in spawned.iter() {
+        commands.entity(attacker).remove::<AttackOnSpawn>();
+        events.send(AttackEvent::new(attacker, attack.enemy()));
+    }
+}
+
+/// Stops attacks of no longer existing enemies. Units with
+/// [`Stance::StandGround`] are sent back to their pre-combat position unless
+/// they were given another order in the meantime.
+pub(crate) fn finish_attacks(
+    mut commands: Commands,
+    attackers: Query<(Entity, &Attacking, Option<&Stance>, Option<&PathTarget>)>,
+    enemies: Query<(), With<Transform>>,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+) {
+    for (attacker, attacking, stance, path) in attackers.iter() {
+        if enemies.contains(attacking.enemy) {
+            continue;
+        }
+
+        commands.entity(attacker).remove::<Attacking>();
+
+        // Chasing is the only source of permanent path targets during an
+        // attack, other path targets come from newer orders.
+        let chasing = path.map_or(true, |path| path.permanent());
+        if stance == Some(&Stance::StandGround) && chasing {
+            path_events.send(UpdateEntityPathEvent::new(
+                attacker,
+                PathTarget::new(attacking.origin, PathQueryProps::exact(), false),
+            ));
+        }
+    }
+}
+
+fn update_positions(
     solids: SolidObjects,
     mut cannons: Query<(Entity, &Transform, &LaserCannon, &mut Attacking)>,
     targets: Query<(&Transform, &ObjectTypeComponent)>,
     sightline: SpatialQuery<Entity>,
 ) {
     for (attacker, transform, cannon, mut attacking) in cannons.iter_mut() {
-        match targets.get(attacking.enemy) {
-            Ok((enemy_transform, &target_type)) => {
-                attacking.muzzle = transform.translation + cannon.muzzle();
-
-                let enemy_aabb = solids.get(*target_type).collider().aabb();
-                let enemy_centroid = enemy_transform.translation + Vec3::from(enemy_aabb.center());
-                let direction = (enemy_centroid - attacking.muzzle)
-                    .try_normalize()
-                    .expect("Attacker and target too close together");
-                let cannon_ray = Ray::new(attacking.muzzle.into(), direction.into());
-
-                attacking.target = sightline
-                    .cast_ray(&cannon_ray, cannon.range(), Some(attacker))
-                    .map(|intersection| cannon_ray.point_at(intersection.toi()).into());
-            }
-            Err(_) => {
-                commands.entity(attacker).remove::<Attacking>();
-            }
-        }
+        // Attacks of no longer existing enemies are stopped by
+        // finish_attacks().
+        let Ok((enemy_transform, &target_type)) = targets.get(attacking.enemy) else {
+            continue;
+        };
+
+        attacking.muzzle = transform.translation + cannon.muzzle();
+
+        let enemy_aabb = solids.get(*target_type).collider().aabb();
+        let enemy_centroid = enemy_transform.translation + Vec3::from(enemy_aabb.center());
+        let direction = (enemy_centroid - attacking.muzzle)
+            .try_normalize()
+            .expect("Attacker and target too close together");
+        let cannon_ray = Ray::new(attacking.muzzle.into(), direction.into());
+
+        attacking.target = sightline
+            .cast_ray(&cannon_ray, cannon.range(), Some(attacker))
+            .map(|intersection| cannon_ray.point_at(intersection.toi()).into());
     }
 }
 
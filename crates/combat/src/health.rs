@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use de_core::{gconfig::GameConfig, objects::Local, state::AppState};
 use de_messages::ToPlayers;
@@ -12,6 +14,7 @@ impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<LocalUpdateHealthEvent>()
             .add_event::<UpdateHealthEvent>()
+            .add_event::<RemoteDamageEvent>()
             .add_systems(
                 Update,
                 (
@@ -74,6 +77,43 @@ impl UpdateHealthEvent {
     }
 }
 
+/// This event is sent when a remotely simulated entity takes damage. It is
+/// not sent when the entity is healed.
+#[derive(Event, Debug, PartialEq)]
+pub struct RemoteDamageEvent {
+    entity: Entity,
+    amount: f32,
+}
+
+impl RemoteDamageEvent {
+    /// Returns a damage event corresponding to a health change or None if the
+    /// change does not decrease the health.
+    ///
+    /// # Panics
+    ///
+    /// Panics if health delta is not finite.
+    fn from_delta(entity: Entity, delta: f32) -> Option<Self> {
+        assert!(delta.is_finite());
+        if delta < 0. {
+            Some(Self {
+                entity,
+                amount: -delta,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Absolute value of the health decrease.
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+}
+
 fn update_local_health(
     config: Res<GameConfig>,
     net_entities: NetEntities,
@@ -96,13 +136,19 @@ fn update_local_health(
 fn update_remote_health(
     mut in_events: EventReader<NetRecvHealthEvent>,
     mut out_events: EventWriter<UpdateHealthEvent>,
+    mut damage_events: EventWriter<RemoteDamageEvent>,
 ) {
     for event in in_events.iter() {
         out_events.send(UpdateHealthEvent::new(event.entity(), event.delta()));
+        if let Some(damage) = RemoteDamageEvent::from_delta(event.entity(), event.delta()) {
+            damage_events.send(damage);
+        }
     }
 }
 
 fn update_health(
+    mut commands: Commands,
+    time: Res<Time>,
     mut healths: Query<&mut Health>,
     mut health_events: EventReader<UpdateHealthEvent>,
     mut bar_events: EventWriter<UpdateBarValueEvent>,
@@ -112,10 +158,42 @@ fn update_health(
             continue;
         };
         health.update(event.delta);
+        if event.delta < 0. {
+            commands
+                .entity(event.entity)
+                .insert(RecentlyDamaged::new(time.elapsed()));
+        }
         bar_events.send(UpdateBarValueEvent::new(event.entity, health.fraction()));
     }
 }
 
+/// Entities with this component lost health at least once. The component is
+/// updated whenever the entity loses health.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RecentlyDamaged {
+    at: Duration,
+}
+
+impl RecentlyDamaged {
+    /// # Arguments
+    ///
+    /// * `at` - time (since the start of the app) of the health decrease.
+    pub fn new(at: Duration) -> Self {
+        Self { at }
+    }
+
+    /// Returns time (since the start of the app) of the most recent health
+    /// decrease.
+    pub fn at(&self) -> Duration {
+        self.at
+    }
+
+    /// Returns true if the entity lost health at most `window` before `now`.
+    pub fn within(&self, now: Duration, window: Duration) -> bool {
+        now.saturating_sub(self.at) <= window
+    }
+}
+
 type LocallyChangedHealth<'w, 's> =
     Query<'w, 's, (Entity, &'static Health), (With<Local>, Changed<Health>)>;
 
@@ -129,3 +207,20 @@ fn find_dead(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_damage_event() {
+        let entity = Entity::from_raw(7);
+
+        let damage = RemoteDamageEvent::from_delta(entity, -12.5).unwrap();
+        assert_eq!(damage.entity(), entity);
+        assert_eq!(damage.amount(), 12.5);
+
+        assert!(RemoteDamageEvent::from_delta(entity, 3.).is_none());
+        assert!(RemoteDamageEvent::from_delta(entity, 0.).is_none());
+    }
+}
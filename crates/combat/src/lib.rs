@@ -1,3 +1,5 @@
+use acquisition::AcquisitionPlugin;
+pub use acquisition::{AcquisitionRange, Stance};
 pub use attack::AttackEvent;
 use attack::AttackPlugin;
 use bevy::{
@@ -5,9 +7,11 @@ use bevy::{
     prelude::{PluginGroup, SystemSet},
 };
 use health::HealthPlugin;
+pub use health::{RecentlyDamaged, RemoteDamageEvent};
 use laser::LaserPlugin;
 use trail::TrailPlugin;
 
+mod acquisition;
 mod attack;
 mod health;
 mod laser;
@@ -23,6 +27,7 @@ impl PluginGroup for CombatPluginGroup {
             .add(AttackPlugin)
             .add(TrailPlugin)
             .add(HealthPlugin)
+            .add(AcquisitionPlugin)
     }
 }
 
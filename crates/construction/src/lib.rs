@@ -1,6 +1,9 @@
 use bevy::{app::PluginGroupBuilder, prelude::*};
 use manufacturing::ManufacturingPlugin;
-pub use manufacturing::{AssemblyLine, ChangeDeliveryLocationEvent, EnqueueAssemblyEvent};
+pub use manufacturing::{
+    AssemblyLine, CancelAssemblyEvent, ChangeDeliveryLocationEvent, EnqueueAssemblyEvent,
+    RallyPath, RallyTarget,
+};
 
 mod manufacturing;
 
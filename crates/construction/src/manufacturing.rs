@@ -4,7 +4,7 @@ use ahash::AHashMap;
 use bevy::prelude::*;
 use de_core::{
     gamestate::GameState,
-    objects::{Local, ObjectTypeComponent},
+    objects::{Active, Local, ObjectTypeComponent},
     player::PlayerComponent,
     state::AppState,
 };
@@ -31,6 +31,7 @@ pub(crate) struct ManufacturingPlugin;
 impl Plugin for ManufacturingPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<EnqueueAssemblyEvent>()
+            .add_event::<CancelAssemblyEvent>()
             .add_event::<ChangeDeliveryLocationEvent>()
             .add_event::<DeliverEvent>()
             .add_systems(
@@ -45,7 +46,10 @@ impl Plugin for ManufacturingPlugin {
                 )
                     .run_if(in_state(GameState::Playing)),
             )
-            .add_systems(Update, enqueue.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (enqueue, cancel.after(enqueue)).run_if(in_state(GameState::Playing)),
+            )
             .add_systems(PostUpdate, configure.run_if(in_state(AppState::InGame)));
     }
 }
@@ -56,24 +60,45 @@ enum ManufacturingSet {
     Produce,
 }
 
-/// Send this event to change target location of freshly manufactured units.
+/// Send this event to change the rally target of freshly manufactured units.
 #[derive(Event)]
 pub struct ChangeDeliveryLocationEvent {
     factory: Entity,
-    position: Vec2,
+    target: RallyTarget,
+    through: bool,
 }
 
 impl ChangeDeliveryLocationEvent {
-    pub fn new(factory: Entity, position: Vec2) -> Self {
-        Self { factory, position }
+    /// Replaces the rally target and removes all waypoints of the rally
+    /// path.
+    pub fn new(factory: Entity, target: RallyTarget) -> Self {
+        Self {
+            factory,
+            target,
+            through: false,
+        }
+    }
+
+    /// Replaces the rally target. The current rally point (if any) is
+    /// appended to the rally path, see [`RallyPath`].
+    pub fn through(factory: Entity, target: RallyTarget) -> Self {
+        Self {
+            factory,
+            target,
+            through: true,
+        }
     }
 
     fn factory(&self) -> Entity {
         self.factory
     }
 
-    fn position(&self) -> Vec2 {
-        self.position
+    fn target(&self) -> RallyTarget {
+        self.target
+    }
+
+    fn is_through(&self) -> bool {
+        self.through
     }
 }
 
@@ -103,6 +128,34 @@ impl EnqueueAssemblyEvent {
     }
 }
 
+/// Send this event to remove a unit from the manufacturing queue of a
+/// factory. Nothing happens if the queue is shorter.
+#[derive(Event, PartialEq, Debug)]
+pub struct CancelAssemblyEvent {
+    factory: Entity,
+    index: usize,
+}
+
+impl CancelAssemblyEvent {
+    /// # Arguments
+    ///
+    /// `factory` - the building producing the unit.
+    ///
+    /// `index` - position of the unit in the queue, zero being the unit
+    /// currently manufactured.
+    pub fn new(factory: Entity, index: usize) -> Self {
+        Self { factory, index }
+    }
+
+    fn factory(&self) -> Entity {
+        self.factory
+    }
+
+    fn index(&self) -> usize {
+        self.index
+    }
+}
+
 #[derive(Event)]
 struct DeliverEvent {
     factory: Entity,
@@ -123,16 +176,80 @@ impl DeliverEvent {
     }
 }
 
-#[derive(Component)]
-struct DeliveryLocation(Vec2);
+/// Rally target of a factory. Freshly manufactured units start the
+/// corresponding action right after they are spawned.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum RallyTarget {
+    /// The units move to a point on the map.
+    Point(Vec2),
+    /// The units attack an enemy entity. The units stay at the spawn point
+    /// if the enemy no longer exists at the time of delivery.
+    Enemy(Entity),
+}
+
+/// Waypoints visited (in the given order) by freshly manufactured units
+/// before they move to the rally point. The waypoints are ignored when the
+/// rally target is an enemy.
+#[derive(Component, Clone, Default, PartialEq, Debug)]
+pub struct RallyPath(Vec<Vec2>);
 
-impl DeliveryLocation {
-    fn initial(local_aabb: Aabb, transform: &Transform) -> Self {
+impl RallyPath {
+    pub fn new(waypoints: Vec<Vec2>) -> Self {
+        Self(waypoints)
+    }
+
+    pub fn waypoints(&self) -> &[Vec2] {
+        self.0.as_slice()
+    }
+}
+
+impl RallyTarget {
+    fn initial_point(local_aabb: Aabb, transform: &Transform) -> Vec2 {
         let target = Vec2::new(
             local_aabb.maxs.x + DEFAULT_TARGET_DISTANCE,
             0.5 * (local_aabb.mins.y + local_aabb.maxs.y),
         );
-        Self(transform.transform_point(target.to_msl()).to_flat())
+        transform.transform_point(target.to_msl()).to_flat()
+    }
+
+    /// Creates an event spawning a freshly manufactured unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - waypoints visited before the rally point.
+    ///
+    /// * `enemy_exists` - returns true if the given enemy entity still
+    ///   exists.
+    fn spawn_event(
+        self,
+        path: &RallyPath,
+        object_type: ActiveObjectType,
+        spawn_point: Vec3,
+        player: Player,
+        enemy_exists: impl Fn(Entity) -> bool,
+    ) -> SpawnLocalActiveEvent {
+        let transform = Transform::from_translation(spawn_point);
+        match self {
+            Self::Point(point) if !path.waypoints().is_empty() => {
+                let mut waypoints = path.waypoints().to_vec();
+                waypoints.push(point);
+                SpawnLocalActiveEvent::stationary(object_type, transform, player)
+                    .with_waypoints(waypoints)
+            }
+            Self::Point(point) => {
+                let path_target =
+                    PathTarget::new(point, PathQueryProps::new(0., f32::INFINITY), false);
+                SpawnLocalActiveEvent::new(object_type, transform, player, Some(path_target))
+            }
+            Self::Enemy(enemy) => {
+                let event = SpawnLocalActiveEvent::stationary(object_type, transform, player);
+                if enemy_exists(enemy) {
+                    event.with_enemy(enemy)
+                } else {
+                    event
+                }
+            }
+        }
     }
 }
 
@@ -149,6 +266,11 @@ impl AssemblyLine {
         &mut self.blocks
     }
 
+    /// Returns enqueued units in the order of their delivery.
+    pub fn queue(&self) -> impl ExactSizeIterator<Item = UnitType> + '_ {
+        self.queue.iter().map(|item| item.unit())
+    }
+
     /// Returns the first item in the assembly line (i.e. the first one to be
     /// delivered).
     fn current(&self) -> Option<UnitType> {
@@ -164,6 +286,24 @@ impl AssemblyLine {
         self.queue.push_back(item);
     }
 
+    /// Removes a unit from the manufacturing queue. Manufacturing of the next
+    /// unit is started if the removed unit was being manufactured.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - position of the unit in the queue.
+    ///
+    /// * `time` - elapsed time since a fixed point in time in the past.
+    fn cancel(&mut self, index: usize, time: Duration) -> Option<UnitType> {
+        let item = self.queue.remove(index)?;
+        if item.is_active() {
+            if let Some(next) = self.queue.front_mut() {
+                next.restart(time);
+            }
+        }
+        Some(item.unit())
+    }
+
     /// Update the production line.
     ///
     /// This method should be called repeatedly and during every tick until it
@@ -301,34 +441,54 @@ fn configure(
         if let Some(factory) = solid.factory() {
             let start = transform.transform_point(factory.position().to_msl());
             let local_aabb = solid.ichnography().local_aabb();
-            let delivery_location = DeliveryLocation::initial(local_aabb, transform);
-            pole_events.send(UpdatePoleLocationEvent::new(entity, delivery_location.0));
-            let end = delivery_location.0.to_msl();
+            let position = RallyTarget::initial_point(local_aabb, transform);
+            pole_events.send(UpdatePoleLocationEvent::new(entity, position));
+            let end = position.to_msl();
             line_events.send(UpdateLineLocationEvent::new(
                 entity,
                 LineLocation::new(start, end),
             ));
-            commands
-                .entity(entity)
-                .insert((AssemblyLine::default(), delivery_location));
+            commands.entity(entity).insert((
+                AssemblyLine::default(),
+                RallyTarget::Point(position),
+                RallyPath::default(),
+            ));
         }
     }
 }
 
 fn change_locations(
     mut events: EventReader<ChangeDeliveryLocationEvent>,
-    mut locations: Query<&mut DeliveryLocation>,
+    mut targets: Query<(&mut RallyTarget, &mut RallyPath)>,
+    enemies: Query<&Transform>,
     mut pole_events: EventWriter<UpdatePoleLocationEvent>,
     mut line_events: EventWriter<UpdateLineEndEvent>,
 ) {
     for event in events.iter() {
-        if let Ok(mut location) = locations.get_mut(event.factory()) {
-            let owner = event.factory();
-            location.0 = event.position();
-            pole_events.send(UpdatePoleLocationEvent::new(owner, event.position()));
-            let end = event.position().to_msl();
-            line_events.send(UpdateLineEndEvent::new(owner, end));
+        let Ok((mut target, mut path)) = targets.get_mut(event.factory()) else {
+            continue;
+        };
+
+        let position = match event.target() {
+            RallyTarget::Point(position) => position,
+            RallyTarget::Enemy(enemy) => match enemies.get(enemy) {
+                Ok(transform) => transform.translation.to_flat(),
+                Err(_) => continue,
+            },
+        };
+
+        if event.is_through() {
+            if let RallyTarget::Point(waypoint) = *target {
+                path.0.push(waypoint);
+            }
+        } else {
+            path.0.clear();
         }
+
+        let owner = event.factory();
+        *target = event.target();
+        pole_events.send(UpdatePoleLocationEvent::new(owner, position));
+        line_events.send(UpdateLineEndEvent::new(owner, position.to_msl()));
     }
 }
 
@@ -350,6 +510,25 @@ fn enqueue(
     }
 }
 
+fn cancel(
+    time: Res<Time>,
+    mut events: EventReader<CancelAssemblyEvent>,
+    mut lines: Query<&mut AssemblyLine>,
+) {
+    for event in events.iter() {
+        let Ok(mut line) = lines.get_mut(event.factory()) else {
+            continue;
+        };
+        if let Some(unit) = line.cancel(event.index(), time.elapsed()) {
+            info!(
+                "Cancelled manufacturing of {} in {:?}.",
+                unit,
+                event.factory()
+            );
+        }
+    }
+}
+
 fn check_spawn_locations(
     solids: SolidObjects,
     space: SpatialQuery<Entity>,
@@ -412,8 +591,10 @@ fn deliver(
         &Transform,
         &ObjectTypeComponent,
         &PlayerComponent,
-        &DeliveryLocation,
+        &RallyTarget,
+        &RallyPath,
     )>,
+    enemies: Query<(), With<Active>>,
 ) {
     for delivery in deliver_events.iter() {
         info!(
@@ -422,7 +603,7 @@ fn deliver(
             delivery.factory()
         );
 
-        let (transform, &factory_object_type, &player, delivery_location) =
+        let (transform, &factory_object_type, &player, &rally_target, rally_path) =
             factories.get(delivery.factory()).unwrap();
         let object_type = ActiveObjectType::Unit(delivery.unit());
 
@@ -430,16 +611,12 @@ fn deliver(
         debug_assert!(factory.products().contains(&delivery.unit()));
         let spawn_point = transform.transform_point(factory.position().to_msl());
 
-        let path_target = PathTarget::new(
-            delivery_location.0,
-            PathQueryProps::new(0., f32::INFINITY),
-            false,
-        );
-        spawn_active_events.send(SpawnLocalActiveEvent::new(
+        spawn_active_events.send(rally_target.spawn_event(
+            rally_path,
             object_type,
-            Transform::from_translation(spawn_point),
+            spawn_point,
             *player,
-            Some(path_target),
+            |enemy| enemies.contains(enemy),
         ));
     }
 }
@@ -485,4 +662,94 @@ mod tests {
         );
         assert!(line.produce(Duration::from_secs(90)).is_none());
     }
+
+    #[test]
+    fn test_cancel() {
+        let mut line = AssemblyLine::default();
+        line.enqueue(UnitType::Attacker, Duration::from_secs(10));
+        line.enqueue(UnitType::Attacker, Duration::from_secs(10));
+        assert_eq!(line.queue().len(), 2);
+
+        assert!(line.cancel(2, Duration::from_secs(11)).is_none());
+        assert_eq!(
+            line.cancel(0, Duration::from_secs(11)).unwrap(),
+            UnitType::Attacker
+        );
+        assert_eq!(line.queue().collect::<Vec<_>>(), vec![UnitType::Attacker]);
+
+        // The remaining unit started to be manufactured at the cancellation.
+        assert!(line.produce(Duration::from_secs(12)).is_none());
+        assert_eq!(
+            line.produce(Duration::from_secs(13)).unwrap(),
+            UnitType::Attacker
+        );
+        assert_eq!(line.queue().len(), 0);
+    }
+
+    #[test]
+    fn test_enemy_rally_target() {
+        let mut world = World::new();
+        let enemy = world.spawn_empty().id();
+        let gone = world.spawn_empty().id();
+        world.despawn(gone);
+
+        let object_type = ActiveObjectType::Unit(UnitType::Attacker);
+        let spawn_point = Vec3::new(1., 0., -2.);
+
+        let event = RallyTarget::Enemy(enemy).spawn_event(
+            &RallyPath::default(),
+            object_type,
+            spawn_point,
+            Player::Player1,
+            |entity| world.get_entity(entity).is_some(),
+        );
+        assert_eq!(event.object_type(), object_type);
+        assert_eq!(event.transform().translation, spawn_point);
+        assert_eq!(event.player(), Player::Player1);
+        assert_eq!(event.enemy(), Some(enemy));
+
+        let event = RallyTarget::Enemy(gone).spawn_event(
+            &RallyPath::default(),
+            object_type,
+            spawn_point,
+            Player::Player1,
+            |entity| world.get_entity(entity).is_some(),
+        );
+        assert_eq!(event.enemy(), None);
+
+        let event = RallyTarget::Point(Vec2::new(10., 20.)).spawn_event(
+            &RallyPath::default(),
+            object_type,
+            spawn_point,
+            Player::Player1,
+            |entity| world.get_entity(entity).is_some(),
+        );
+        assert_eq!(event.enemy(), None);
+    }
+
+    #[test]
+    fn test_rally_path() {
+        let first = Vec2::new(5., -3.);
+        let second = Vec2::new(12., 7.);
+        let rally_point = Vec2::new(30., 20.);
+        let path = RallyPath::new(vec![first, second]);
+
+        let event = RallyTarget::Point(rally_point).spawn_event(
+            &path,
+            ActiveObjectType::Unit(UnitType::Attacker),
+            Vec3::new(1., 0., -2.),
+            Player::Player1,
+            |_| true,
+        );
+        assert_eq!(event.waypoints(), &[first, second, rally_point]);
+
+        let event = RallyTarget::Point(rally_point).spawn_event(
+            &RallyPath::default(),
+            ActiveObjectType::Unit(UnitType::Attacker),
+            Vec3::new(1., 0., -2.),
+            Player::Player1,
+            |_| true,
+        );
+        assert!(event.waypoints().is_empty());
+    }
 }
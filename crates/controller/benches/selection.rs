@@ -0,0 +1,38 @@
+use bevy::prelude::Entity;
+use criterion::{
+    criterion_group, criterion_main, AxisScale, BenchmarkId, Criterion, PlotConfiguration,
+    Throughput,
+};
+use de_controller::{SelectionDiff, SelectionMode};
+
+fn selection_diff_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("selection_diff");
+    let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
+    group.plot_config(plot_config);
+
+    for number in [100, 1000, 10_000] {
+        let selected: Vec<Entity> = (0..number).map(Entity::from_raw).collect();
+        // Half of the units overlap with the current selection.
+        let updated: Vec<Entity> = (number / 2..number + number / 2)
+            .map(Entity::from_raw)
+            .collect();
+
+        group.throughput(Throughput::Elements(number.into()));
+        group.bench_function(BenchmarkId::from_parameter(number), |b| {
+            b.iter(|| {
+                let mut diff = SelectionDiff::new(selected.iter().cloned());
+                for mode in [
+                    SelectionMode::Replace,
+                    SelectionMode::AddToggle,
+                    SelectionMode::Add,
+                ] {
+                    diff.update(&updated, mode);
+                }
+                diff
+            });
+        });
+    }
+}
+
+criterion_group!(benches, selection_diff_benchmark);
+criterion_main!(benches);
@@ -1,26 +1,87 @@
-use bevy::prelude::*;
-use de_behaviour::ChaseTargetEvent;
-use de_combat::AttackEvent;
-use de_construction::{AssemblyLine, ChangeDeliveryLocationEvent};
-use de_core::{gamestate::GameState, objects::MovableSolid, schedule::InputSchedule};
+use std::f32::consts::TAU;
+
+use bevy::{
+    ecs::{
+        query::{Has, ReadOnlyWorldQuery},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+use de_behaviour::{
+    ChaseTargetEvent, CommandQueueEvent, DelayedCommand, FollowEvent, Following, Guard, GuardEvent,
+    GuardTarget, Order, ScoutEvent, StartAt,
+};
+use de_combat::{AttackEvent, Stance};
+use de_construction::{AssemblyLine, ChangeDeliveryLocationEvent, RallyTarget};
+use de_core::{
+    gamestate::GameState,
+    objects::{MovableSolid, ObjectTypeComponent, Playable},
+    schedule::InputSchedule,
+};
+use de_energy::{MovementSpeed, SpeedTier};
+use de_objects::SolidObjects;
 use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPathEvent};
+use de_spawner::{Dying, TransferOwnershipEvent};
+use de_types::{player::Player, projection::ToFlat};
 use glam::Vec2;
 
-use crate::selection::Selected;
+use crate::selection::{Selected, SelectionSet};
+
+/// Distance (in meters) between candidate destinations of spread out units,
+/// see [`SpreadSelectedEvent`].
+const SPREAD_STEP: f32 = 1.;
+/// Maximum number of concentric rings searched for a free destination of a
+/// spread out unit.
+const MAX_SPREAD_RINGS: usize = 100;
 
 pub(super) struct ExecutorPlugin;
 
 impl Plugin for ExecutorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<SendSelectedEvent>()
+        app.add_systems(OnEnter(GameState::Playing), setup)
+            .add_systems(OnExit(GameState::Playing), cleanup)
+            .add_event::<SendSelectedEvent>()
+            .add_event::<SpreadSelectedEvent>()
+            .add_event::<RepeatSelectedEvent>()
             .add_event::<DeliveryLocationSelectedEvent>()
             .add_event::<GroupAttackEvent>()
+            .add_event::<GiveSelectedEvent>()
+            .add_event::<GuardSelectedEvent>()
+            .add_event::<QueueSelectedEvent>()
+            .add_event::<UndoSelectedEvent>()
+            .add_event::<FallBackSelectedEvent>()
+            .add_event::<ScoutSelectedEvent>()
+            .add_event::<FollowSelectedEvent>()
+            .add_event::<ToggleRunSelectedEvent>()
+            .add_event::<ToggleStandGroundSelectedEvent>()
+            .add_event::<CommandDeniedEvent>()
             .add_systems(
                 InputSchedule,
                 (
-                    send_selected_system.in_set(CommandsSet::SendSelected),
+                    repeat_system
+                        .in_set(CommandsSet::Repeat)
+                        .before(CommandsSet::SendSelected)
+                        .before(CommandsSet::Attack)
+                        .before(CommandsSet::Guard),
+                    remember_command,
+                    (send_selected_system, spread_selected_system)
+                        .in_set(CommandsSet::SendSelected),
                     delivery_location_system.in_set(CommandsSet::DeliveryLocation),
                     attack_system.in_set(CommandsSet::Attack),
+                    guard_system.in_set(CommandsSet::Guard),
+                    follow_system.in_set(CommandsSet::Follow),
+                    scout_system.in_set(CommandsSet::Scout),
+                    toggle_run_system.in_set(CommandsSet::Speed),
+                    toggle_stand_ground_system.in_set(CommandsSet::Stance),
+                    (
+                        queue_selected_system,
+                        undo_system.after(queue_selected_system),
+                        fall_back_system.after(queue_selected_system),
+                    )
+                        .in_set(CommandsSet::Queue),
+                    give_selected_system
+                        .in_set(CommandsSet::Give)
+                        .before(SelectionSet::Update),
                 )
                     .run_if(in_state(GameState::Playing)),
             );
@@ -29,13 +90,21 @@ impl Plugin for ExecutorPlugin {
 
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
 pub(crate) enum CommandsSet {
+    Repeat,
     SendSelected,
     DeliveryLocation,
     Attack,
+    Give,
+    Guard,
+    Follow,
+    Scout,
+    Queue,
+    Speed,
+    Stance,
 }
 
 /// Send this event to send all selected movable units to a point on the map.
-#[derive(Event)]
+#[derive(Event, Clone, PartialEq, Debug)]
 pub(crate) struct SendSelectedEvent(Vec2);
 
 impl SendSelectedEvent {
@@ -43,29 +112,124 @@ impl SendSelectedEvent {
         Self(target)
     }
 
-    fn target(&self) -> Vec2 {
+    pub(crate) fn target(&self) -> Vec2 {
         self.0
     }
 }
 
-/// Send this event to set manufacturing delivery location for all selected
-/// building with a factory.
-#[derive(Event)]
-pub(crate) struct DeliveryLocationSelectedEvent(Vec2);
+/// Send this event to send all selected movable units near a point on the
+/// map. Unlike [`SendSelectedEvent`], each unit is sent to a distinct
+/// position so that the units do not pile up at the point.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct SpreadSelectedEvent(Vec2);
 
-impl DeliveryLocationSelectedEvent {
+impl SpreadSelectedEvent {
     pub(crate) fn new(target: Vec2) -> Self {
         Self(target)
     }
 
-    fn target(&self) -> Vec2 {
+    pub(crate) fn target(&self) -> Vec2 {
         self.0
     }
 }
 
+/// Send this event to append a move order to command queues of all selected
+/// movable units.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct QueueSelectedEvent {
+    target: Vec2,
+    start_at: Option<StartAt>,
+}
+
+impl QueueSelectedEvent {
+    pub(crate) fn new(target: Vec2) -> Self {
+        Self {
+            target,
+            start_at: None,
+        }
+    }
+
+    /// Creates an event appending a move order which does not start before
+    /// a condition is met.
+    pub(crate) fn delayed(target: Vec2, start_at: StartAt) -> Self {
+        Self {
+            target,
+            start_at: Some(start_at),
+        }
+    }
+
+    pub(crate) fn target(&self) -> Vec2 {
+        self.target
+    }
+
+    fn start_at(&self) -> Option<StartAt> {
+        self.start_at
+    }
+}
+
+/// Send this event to remove the most recently queued order of all selected
+/// movable units.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct UndoSelectedEvent;
+
+/// Send this event to make all selected movable units retrace their recently
+/// traveled path.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct FallBackSelectedEvent;
+
+/// Send this event to make all selected movable units explore the map, i.e.
+/// repeatedly move to the nearest unexplored tile.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct ScoutSelectedEvent;
+
+/// Send this event to switch all selected combat units between walking and
+/// running. All of them run unless all of them already run.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct ToggleRunSelectedEvent;
+
+/// Send this event to switch all selected combat units between pursuing
+/// enemies and standing ground, see [`Stance::StandGround`]. All of them
+/// stand ground unless all of them already do.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct ToggleStandGroundSelectedEvent;
+
+/// Send this event to set manufacturing rally target for all selected
+/// building with a factory.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct DeliveryLocationSelectedEvent {
+    target: RallyTarget,
+    through: bool,
+}
+
+impl DeliveryLocationSelectedEvent {
+    pub(crate) fn new(target: RallyTarget) -> Self {
+        Self {
+            target,
+            through: false,
+        }
+    }
+
+    /// The current rally points of the buildings become waypoints of their
+    /// rally paths, see [`ChangeDeliveryLocationEvent::through`].
+    pub(crate) fn through(target: RallyTarget) -> Self {
+        Self {
+            target,
+            through: true,
+        }
+    }
+
+    fn target(&self) -> RallyTarget {
+        self.target
+    }
+
+    fn is_through(&self) -> bool {
+        self.through
+    }
+}
+
 /// Send this event to attack an enemy with all selected movable units. The
 /// target must be an enemy entity.
-#[derive(Event)]
+#[derive(Event, Clone, PartialEq, Debug)]
 pub(crate) struct GroupAttackEvent(Entity);
 
 impl GroupAttackEvent {
@@ -78,17 +242,238 @@ impl GroupAttackEvent {
     }
 }
 
-type SelectedMovable = (With<Selected>, With<MovableSolid>);
+/// Send this event to transfer ownership of all selected locally simulated
+/// entities to another player.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct GiveSelectedEvent(Player);
+
+impl GiveSelectedEvent {
+    pub(crate) fn new(player: Player) -> Self {
+        Self(player)
+    }
+
+    fn player(&self) -> Player {
+        self.0
+    }
+}
+
+/// Send this event to make all selected movable units evenly surround and
+/// guard a point or an entity.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct GuardSelectedEvent {
+    target: GuardTarget,
+    radius: f32,
+}
+
+impl GuardSelectedEvent {
+    /// # Arguments
+    ///
+    /// * `target` - the guarded point or entity.
+    ///
+    /// * `radius` - distance in meters between the guarded target and the
+    ///   guarding units.
+    pub(crate) fn new(target: GuardTarget, radius: f32) -> Self {
+        Self { target, radius }
+    }
+
+    fn target(&self) -> GuardTarget {
+        self.target
+    }
+
+    fn radius(&self) -> f32 {
+        self.radius
+    }
+}
+
+/// Send this event to make all selected movable units follow an entity while
+/// keeping their current offsets to it.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct FollowSelectedEvent(Entity);
+
+impl FollowSelectedEvent {
+    /// # Arguments
+    ///
+    /// * `target` - the followed (leading) entity.
+    pub(crate) fn new(target: Entity) -> Self {
+        Self(target)
+    }
+
+    fn target(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Send this event to issue the most recently issued move, spread, attack or
+/// guard command (with the same target) to all selected units.
+///
+/// If the original target entity no longer exists, the units are sent to
+/// (or guard) its position from the time the original command was issued.
+///
+/// The event itself is not recorded by [`crate::recording`], the re-issued
+/// commands are.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct RepeatSelectedEvent;
+
+/// The most recently issued command which can be repeated, see
+/// [`RepeatSelectedEvent`].
+#[derive(Resource, Default)]
+struct LastCommand(Option<RepeatableCommand>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RepeatableCommand {
+    Send(Vec2),
+    Spread(Vec2),
+    /// Attack of an enemy together with its last known position.
+    Attack(Entity, Vec2),
+    /// Guard order together with the last known position of the guarded
+    /// target.
+    Guard(GuardTarget, Vec2, f32),
+}
+
+/// This event is sent when a command is not issued to a selected entity
+/// because the entity is not controlled by the local player.
+#[derive(Event)]
+pub struct CommandDeniedEvent(Entity);
+
+impl CommandDeniedEvent {
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+type SelectedMovable = (With<Selected>, With<MovableSolid>, Without<Dying>);
+
+/// Selected entities which might receive commands. Entities of other players
+/// might be selected (for inspection) but they never receive any commands.
+#[derive(SystemParam)]
+struct Commandable<'w, 's, F>
+where
+    F: ReadOnlyWorldQuery + 'static,
+{
+    selected: Query<'w, 's, (Entity, Has<Playable>), F>,
+    denied: EventWriter<'w, CommandDeniedEvent>,
+}
+
+impl<'w, 's, F> Commandable<'w, 's, F>
+where
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// Returns all entities controlled by the local player. A
+    /// [`CommandDeniedEvent`] is sent for each other entity.
+    fn entities(&mut self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        for (entity, playable) in self.selected.iter() {
+            if playable {
+                entities.push(entity);
+            } else {
+                self.denied.send(CommandDeniedEvent(entity));
+            }
+        }
+        entities
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<LastCommand>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<LastCommand>();
+}
+
+fn remember_command(
+    mut last: ResMut<LastCommand>,
+    mut send_events: EventReader<SendSelectedEvent>,
+    mut spread_events: EventReader<SpreadSelectedEvent>,
+    mut attack_events: EventReader<GroupAttackEvent>,
+    mut guard_events: EventReader<GuardSelectedEvent>,
+    positions: Query<&Transform>,
+) {
+    let position = |entity: Entity| {
+        positions
+            .get(entity)
+            .ok()
+            .map(|transform| transform.translation.to_flat())
+    };
+
+    if let Some(event) = send_events.iter().last() {
+        last.0 = Some(RepeatableCommand::Send(event.target()));
+    }
+    if let Some(event) = spread_events.iter().last() {
+        last.0 = Some(RepeatableCommand::Spread(event.target()));
+    }
+    if let Some(event) = attack_events.iter().last() {
+        if let Some(position) = position(event.target()) {
+            last.0 = Some(RepeatableCommand::Attack(event.target(), position));
+        }
+    }
+    if let Some(event) = guard_events.iter().last() {
+        let position = match event.target() {
+            GuardTarget::Point(point) => Some(point),
+            GuardTarget::Entity(entity) => position(entity),
+        };
+        if let Some(position) = position {
+            last.0 = Some(RepeatableCommand::Guard(
+                event.target(),
+                position,
+                event.radius(),
+            ));
+        }
+    }
+}
+
+fn repeat_system(
+    mut repeat_events: EventReader<RepeatSelectedEvent>,
+    last: Res<LastCommand>,
+    existing: Query<(), With<Transform>>,
+    mut send_events: EventWriter<SendSelectedEvent>,
+    mut spread_events: EventWriter<SpreadSelectedEvent>,
+    mut attack_events: EventWriter<GroupAttackEvent>,
+    mut guard_events: EventWriter<GuardSelectedEvent>,
+) {
+    if repeat_events.iter().count() == 0 {
+        return;
+    }
+    let Some(command) = last.0 else {
+        return;
+    };
+
+    match command {
+        RepeatableCommand::Send(target) => send_events.send(SendSelectedEvent::new(target)),
+        RepeatableCommand::Spread(target) => spread_events.send(SpreadSelectedEvent::new(target)),
+        RepeatableCommand::Attack(enemy, _) if existing.contains(enemy) => {
+            attack_events.send(GroupAttackEvent::new(enemy))
+        }
+        RepeatableCommand::Attack(_, position) => {
+            send_events.send(SendSelectedEvent::new(position))
+        }
+        RepeatableCommand::Guard(target, position, radius) => {
+            let target = match target {
+                GuardTarget::Entity(entity) if !existing.contains(entity) => {
+                    GuardTarget::Point(position)
+                }
+                target => target,
+            };
+            guard_events.send(GuardSelectedEvent::new(target, radius));
+        }
+    }
+}
 
 fn send_selected_system(
     mut send_events: EventReader<SendSelectedEvent>,
-    selected: Query<Entity, SelectedMovable>,
+    mut selected: Commandable<SelectedMovable>,
     mut path_events: EventWriter<UpdateEntityPathEvent>,
     mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
 ) {
     if let Some(send) = send_events.iter().last() {
-        for entity in selected.iter() {
+        for entity in selected.entities() {
             chase_events.send(ChaseTargetEvent::new(entity, None));
+            guard_events.send(GuardEvent::new(entity, None));
+            follow_events.send(FollowEvent::new(entity, None));
+            queue_events.send(CommandQueueEvent::clear(entity));
             path_events.send(UpdateEntityPathEvent::new(
                 entity,
                 PathTarget::new(send.target(), PathQueryProps::exact(), false),
@@ -97,28 +482,492 @@ fn send_selected_system(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+fn spread_selected_system(
+    mut spread_events: EventReader<SpreadSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    solids: SolidObjects,
+    objects: Query<&ObjectTypeComponent>,
+    mut path_events: EventWriter<UpdateEntityPathEvent>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
+) {
+    let Some(spread) = spread_events.iter().last() else {
+        return;
+    };
+
+    let entities = selected.entities();
+    let radii: Vec<f32> = entities
+        .iter()
+        .map(|&entity| {
+            objects.get(entity).map_or(0., |&object_type| {
+                solids.get(*object_type).ichnography().radius()
+            })
+        })
+        .collect();
+
+    for (entity, destination) in entities
+        .into_iter()
+        .zip(spread_destinations(spread.target(), &radii))
+    {
+        chase_events.send(ChaseTargetEvent::new(entity, None));
+        guard_events.send(GuardEvent::new(entity, None));
+        follow_events.send(FollowEvent::new(entity, None));
+        queue_events.send(CommandQueueEvent::clear(entity));
+        path_events.send(UpdateEntityPathEvent::new(
+            entity,
+            PathTarget::new(destination, PathQueryProps::exact(), false),
+        ));
+    }
+}
+
+/// Returns mutually non-overlapping destinations of units with given radii,
+/// each as close to the target as possible. The units are placed one after
+/// another in the given order.
+fn spread_destinations(target: Vec2, radii: &[f32]) -> Vec<Vec2> {
+    let mut placed: Vec<(Vec2, f32)> = Vec::with_capacity(radii.len());
+    for &radius in radii {
+        let position = closest_free_position(&placed, target, radius);
+        placed.push((position, radius));
+    }
+    placed.into_iter().map(|(position, _)| position).collect()
+}
+
+/// Returns the closest position to the target (among positions on
+/// concentric rings spaced by [`SPREAD_STEP`]) where a disc of a given
+/// radius does not overlap any of the already placed discs.
+///
+/// The target itself is returned if the input is not finite or if no free
+/// position is found within [`MAX_SPREAD_RINGS`] rings.
+fn closest_free_position(placed: &[(Vec2, f32)], target: Vec2, radius: f32) -> Vec2 {
+    if !target.is_finite() || !radius.is_finite() {
+        return target;
+    }
+
+    let is_free = |candidate: Vec2| {
+        placed
+            .iter()
+            .all(|&(position, other)| position.distance(candidate) >= radius + other)
+    };
+
+    for ring in 0..MAX_SPREAD_RINGS {
+        let distance = ring as f32 * SPREAD_STEP;
+        let count = ((TAU * distance / SPREAD_STEP).ceil() as usize).max(1);
+        for i in 0..count {
+            let candidate = target + distance * Vec2::from_angle(TAU * i as f32 / count as f32);
+            if is_free(candidate) {
+                return candidate;
+            }
+        }
+    }
+    target
+}
+
 type SelectedFactory = (With<Selected>, With<AssemblyLine>);
 
 fn delivery_location_system(
     mut in_events: EventReader<DeliveryLocationSelectedEvent>,
-    selected: Query<Entity, SelectedFactory>,
+    mut selected: Commandable<SelectedFactory>,
     mut out_events: EventWriter<ChangeDeliveryLocationEvent>,
 ) {
     if let Some(event) = in_events.iter().last() {
-        for entity in selected.iter() {
-            out_events.send(ChangeDeliveryLocationEvent::new(entity, event.target()));
+        for entity in selected.entities() {
+            out_events.send(if event.is_through() {
+                ChangeDeliveryLocationEvent::through(entity, event.target())
+            } else {
+                ChangeDeliveryLocationEvent::new(entity, event.target())
+            });
         }
     }
 }
 
 fn attack_system(
     mut group_events: EventReader<GroupAttackEvent>,
-    selected: Query<Entity, SelectedMovable>,
+    mut selected: Commandable<SelectedMovable>,
     mut individual_events: EventWriter<AttackEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
 ) {
     if let Some(group_event) = group_events.iter().last() {
-        for attacker in selected.iter() {
+        for attacker in selected.entities() {
+            guard_events.send(GuardEvent::new(attacker, None));
+            follow_events.send(FollowEvent::new(attacker, None));
+            queue_events.send(CommandQueueEvent::clear(attacker));
             individual_events.send(AttackEvent::new(attacker, group_event.target()));
         }
     }
 }
+
+fn guard_system(
+    mut in_events: EventReader<GuardSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
+) {
+    if let Some(event) = in_events.iter().last() {
+        // A guarded entity cannot guard itself.
+        let guards: Vec<Entity> = selected
+            .entities()
+            .into_iter()
+            .filter(|&entity| event.target() != GuardTarget::Entity(entity))
+            .collect();
+        let formation = Guard::formation(event.target(), event.radius(), guards.len());
+        for (entity, guard) in guards.into_iter().zip(formation) {
+            chase_events.send(ChaseTargetEvent::new(entity, None));
+            follow_events.send(FollowEvent::new(entity, None));
+            queue_events.send(CommandQueueEvent::clear(entity));
+            guard_events.send(GuardEvent::new(entity, Some(guard)));
+        }
+    }
+}
+
+fn follow_system(
+    mut in_events: EventReader<FollowSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    transforms: Query<&Transform>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+) {
+    let Some(event) = in_events.iter().last() else {
+        return;
+    };
+    let Ok(leader) = transforms.get(event.target()) else {
+        return;
+    };
+    let leader = leader.translation.to_flat();
+
+    for entity in selected.entities() {
+        // A leader cannot follow itself.
+        if entity == event.target() {
+            continue;
+        }
+        let Ok(transform) = transforms.get(entity) else {
+            continue;
+        };
+
+        let offset = transform.translation.to_flat() - leader;
+        chase_events.send(ChaseTargetEvent::new(entity, None));
+        guard_events.send(GuardEvent::new(entity, None));
+        queue_events.send(CommandQueueEvent::clear(entity));
+        follow_events.send(FollowEvent::new(
+            entity,
+            Some(Following::new(event.target(), offset)),
+        ));
+    }
+}
+
+fn queue_selected_system(
+    mut in_events: EventReader<QueueSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
+) {
+    for event in in_events.iter() {
+        for entity in selected.entities() {
+            chase_events.send(ChaseTargetEvent::new(entity, None));
+            guard_events.send(GuardEvent::new(entity, None));
+            follow_events.send(FollowEvent::new(entity, None));
+            let order = Order::Move(event.target());
+            queue_events.send(match event.start_at() {
+                Some(start_at) => {
+                    CommandQueueEvent::append_delayed(entity, DelayedCommand::new(order, start_at))
+                }
+                None => CommandQueueEvent::append(entity, order),
+            });
+        }
+    }
+}
+
+fn undo_system(
+    mut in_events: EventReader<UndoSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+) {
+    for _ in in_events.iter() {
+        for entity in selected.entities() {
+            queue_events.send(CommandQueueEvent::undo(entity));
+        }
+    }
+}
+
+fn fall_back_system(
+    mut in_events: EventReader<FallBackSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
+) {
+    for _ in in_events.iter() {
+        for entity in selected.entities() {
+            chase_events.send(ChaseTargetEvent::new(entity, None));
+            guard_events.send(GuardEvent::new(entity, None));
+            follow_events.send(FollowEvent::new(entity, None));
+            queue_events.send(CommandQueueEvent::fall_back(entity));
+        }
+    }
+}
+
+fn scout_system(
+    mut in_events: EventReader<ScoutSelectedEvent>,
+    mut selected: Commandable<SelectedMovable>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut guard_events: EventWriter<GuardEvent>,
+    mut queue_events: EventWriter<CommandQueueEvent>,
+    mut follow_events: EventWriter<FollowEvent>,
+    mut scout_events: EventWriter<ScoutEvent>,
+) {
+    if in_events.iter().count() == 0 {
+        return;
+    }
+
+    for entity in selected.entities() {
+        chase_events.send(ChaseTargetEvent::new(entity, None));
+        guard_events.send(GuardEvent::new(entity, None));
+        follow_events.send(FollowEvent::new(entity, None));
+        queue_events.send(CommandQueueEvent::clear(entity));
+        scout_events.send(ScoutEvent::new(entity, true));
+    }
+}
+
+/// Selected combat units, i.e. units with a cannon.
+type SelectedCombat = (SelectedMovable, With<Stance>);
+
+fn toggle_run_system(
+    mut commands: Commands,
+    mut in_events: EventReader<ToggleRunSelectedEvent>,
+    mut selected: Commandable<SelectedCombat>,
+    speeds: Query<&MovementSpeed>,
+) {
+    if in_events.iter().last().is_none() {
+        return;
+    }
+
+    let entities = selected.entities();
+    let all_running = entities.iter().all(|&entity| {
+        speeds
+            .get(entity)
+            .is_ok_and(|speed| speed.tier() == SpeedTier::Run)
+    });
+    let tier = if all_running {
+        SpeedTier::Walk
+    } else {
+        SpeedTier::Run
+    };
+
+    for entity in entities {
+        commands.entity(entity).insert(MovementSpeed::new(tier));
+    }
+}
+
+fn toggle_stand_ground_system(
+    mut in_events: EventReader<ToggleStandGroundSelectedEvent>,
+    mut selected: Commandable<SelectedCombat>,
+    mut stances: Query<&mut Stance>,
+) {
+    if in_events.iter().last().is_none() {
+        return;
+    }
+
+    let entities = selected.entities();
+    let all_standing = entities.iter().all(|&entity| {
+        stances
+            .get(entity)
+            .is_ok_and(|&stance| stance == Stance::StandGround)
+    });
+    let stance = if all_standing {
+        Stance::Aggressive
+    } else {
+        Stance::StandGround
+    };
+
+    for entity in entities {
+        if let Ok(mut current) = stances.get_mut(entity) {
+            *current = stance;
+        }
+    }
+}
+
+type SelectedGivable = (With<Selected>, Without<Dying>);
+
+fn give_selected_system(
+    mut give_events: EventReader<GiveSelectedEvent>,
+    mut selected: Commandable<SelectedGivable>,
+    mut transfer_events: EventWriter<TransferOwnershipEvent>,
+) {
+    if let Some(event) = give_events.iter().last() {
+        let entities = selected.entities();
+        if !entities.is_empty() {
+            transfer_events.send(TransferOwnershipEvent::new(entities, event.player()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_command_denied() {
+        let mut app = App::new();
+        app.add_event::<SendSelectedEvent>()
+            .add_event::<UpdateEntityPathEvent>()
+            .add_event::<ChaseTargetEvent>()
+            .add_event::<GuardEvent>()
+            .add_event::<FollowEvent>()
+            .add_event::<CommandQueueEvent>()
+            .add_event::<CommandDeniedEvent>()
+            .add_systems(Update, send_selected_system);
+
+        app.world.spawn((Selected, MovableSolid, Playable));
+        let enemy = app.world.spawn((Selected, MovableSolid)).id();
+        app.world.spawn((MovableSolid, Playable));
+
+        app.world
+            .send_event(SendSelectedEvent::new(Vec2::new(10., 20.)));
+        app.update();
+
+        let mut paths = SystemState::<EventReader<UpdateEntityPathEvent>>::new(&mut app.world);
+        assert_eq!(paths.get_mut(&mut app.world).iter().count(), 1);
+
+        let mut denied = SystemState::<EventReader<CommandDeniedEvent>>::new(&mut app.world);
+        let denied: Vec<Entity> = denied
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| event.entity())
+            .collect();
+        assert_eq!(denied, vec![enemy]);
+    }
+
+    #[test]
+    fn test_repeat() {
+        let mut app = App::new();
+        app.init_resource::<LastCommand>()
+            .add_event::<SendSelectedEvent>()
+            .add_event::<SpreadSelectedEvent>()
+            .add_event::<GroupAttackEvent>()
+            .add_event::<GuardSelectedEvent>()
+            .add_event::<RepeatSelectedEvent>()
+            .add_event::<UpdateEntityPathEvent>()
+            .add_event::<ChaseTargetEvent>()
+            .add_event::<GuardEvent>()
+            .add_event::<FollowEvent>()
+            .add_event::<CommandQueueEvent>()
+            .add_event::<CommandDeniedEvent>()
+            .add_systems(
+                Update,
+                (
+                    repeat_system.before(send_selected_system),
+                    remember_command,
+                    send_selected_system,
+                ),
+            );
+
+        let first = app.world.spawn((Selected, MovableSolid, Playable)).id();
+        let second = app.world.spawn((MovableSolid, Playable)).id();
+
+        let target = Vec2::new(10., 20.);
+        app.world.send_event(SendSelectedEvent::new(target));
+        app.update();
+
+        app.world.entity_mut(first).remove::<Selected>();
+        app.world.entity_mut(second).insert(Selected);
+        app.world.send_event(RepeatSelectedEvent);
+        app.update();
+
+        let mut paths = SystemState::<EventReader<UpdateEntityPathEvent>>::new(&mut app.world);
+        let paths: Vec<(Entity, Vec2)> = paths
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| (event.entity(), event.target().location()))
+            .collect();
+        assert_eq!(paths, vec![(first, target), (second, target)]);
+
+        // Units are sent to the last known position of a no longer existing
+        // enemy.
+        let enemy = app.world.spawn(Transform::from_xyz(-5., 0., -7.)).id();
+        app.world.send_event(GroupAttackEvent::new(enemy));
+        app.update();
+        app.world.despawn(enemy);
+        app.world.send_event(RepeatSelectedEvent);
+        app.update();
+
+        let mut sends = SystemState::<EventReader<SendSelectedEvent>>::new(&mut app.world);
+        let sends: Vec<Vec2> = sends
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| event.target())
+            .collect();
+        assert_eq!(sends, vec![Vec2::new(-5., 7.)]);
+    }
+
+    #[test]
+    fn test_spread_destinations() {
+        let target = Vec2::new(10., -20.);
+        let radii = [1., 1.5, 1., 2., 0.5];
+        let destinations = spread_destinations(target, &radii);
+        assert_eq!(destinations.len(), 5);
+        assert_eq!(destinations[0], target);
+
+        for (i, (&a, &radius_a)) in destinations.iter().zip(radii.iter()).enumerate() {
+            assert!(a.distance(target) < 10.);
+            for (&b, &radius_b) in destinations.iter().zip(radii.iter()).skip(i + 1) {
+                assert!(a.distance(b) >= radius_a + radius_b);
+            }
+        }
+
+        let placed = [(target, 1.)];
+        let nan = Vec2::new(f32::NAN, 0.);
+        assert!(closest_free_position(&placed, nan, 1.).x.is_nan());
+        assert_eq!(
+            closest_free_position(&placed, target, f32::INFINITY),
+            target
+        );
+        assert_eq!(closest_free_position(&placed, target, 1000.), target);
+    }
+
+    #[test]
+    fn test_toggle_run() {
+        let mut app = App::new();
+        app.add_event::<ToggleRunSelectedEvent>()
+            .add_event::<CommandDeniedEvent>()
+            .add_systems(Update, toggle_run_system);
+
+        let combat = app
+            .world
+            .spawn((Selected, MovableSolid, Playable, Stance::default()))
+            .id();
+        let worker = app.world.spawn((Selected, MovableSolid, Playable)).id();
+        let unselected = app
+            .world
+            .spawn((MovableSolid, Playable, Stance::default()))
+            .id();
+
+        let factor = |world: &World, entity: Entity| {
+            world
+                .get::<MovementSpeed>(entity)
+                .map_or(1., |speed| speed.factor())
+        };
+
+        app.world.send_event(ToggleRunSelectedEvent);
+        app.update();
+        assert!(factor(&app.world, combat) > 1.);
+        assert_eq!(factor(&app.world, worker), 1.);
+        assert_eq!(factor(&app.world, unselected), 1.);
+
+        app.world.send_event(ToggleRunSelectedEvent);
+        app.update();
+        assert_eq!(factor(&app.world, combat), 1.);
+    }
+}
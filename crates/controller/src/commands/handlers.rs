@@ -1,7 +1,11 @@
 //! This module implements user input / user command handling, for example
 //! keyboard shortcuts, mouse actions events, and so on.
 
+use std::time::Duration;
+
+use ahash::AHashMap;
 use bevy::{
+    ecs::{query::Has, system::SystemParam},
     input::{
         keyboard::KeyboardInput,
         mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
@@ -10,41 +14,59 @@ use bevy::{
     prelude::*,
     window::PrimaryWindow,
 };
+use de_behaviour::{GoSignalEvent, GuardTarget, QueueSet, StartAt};
 use de_camera::{
     CameraSet, MoveCameraHorizontallyEvent, RotateCameraEvent, TiltCameraEvent, ZoomCameraEvent,
 };
+use de_combat::RecentlyDamaged;
 use de_conf::Configuration;
+use de_construction::RallyTarget;
 use de_core::{
     gamestate::GameState,
     gconfig::GameConfig,
-    objects::{ObjectTypeComponent, Playable},
+    objects::{MovableSolid, ObjectTypeComponent, Playable, StaticSolid},
     player::PlayerComponent,
     schedule::InputSchedule,
-    screengeom::ScreenRect,
+    screengeom::{ScreenPolygon, ScreenRect},
 };
 use de_spawner::{DraftAllowed, ObjectCounter};
 use de_types::{
-    objects::{BuildingType, PLAYER_MAX_BUILDINGS},
+    objects::{ActiveObjectType, BuildingType, ObjectType, PLAYER_MAX_BUILDINGS},
     projection::ToFlat,
 };
-use enum_map::enum_map;
 
 use super::{
-    executor::DeliveryLocationSelectedEvent, keyboard::KeyCondition, CommandsSet, GroupAttackEvent,
-    SendSelectedEvent,
+    executor::DeliveryLocationSelectedEvent, keyboard::KeyCondition, CommandsSet,
+    FallBackSelectedEvent, FollowSelectedEvent, GiveSelectedEvent, GroupAttackEvent,
+    GuardSelectedEvent, QueueSelectedEvent, RepeatSelectedEvent, ScoutSelectedEvent,
+    SendSelectedEvent, SpreadSelectedEvent, ToggleRunSelectedEvent, ToggleStandGroundSelectedEvent,
+    UndoSelectedEvent,
 };
 use crate::{
-    draft::{DiscardDraftsEvent, DraftSet, NewDraftEvent, SpawnDraftsEvent},
-    hud::{GameMenuSet, ToggleGameMenuEvent, UpdateSelectionBoxEvent},
+    draft::{
+        Blueprints, DiscardDraftsEvent, DraftSet, NewBlueprintDraftEvent, NewDraftEvent,
+        SaveBlueprintEvent, SpawnDraftsEvent, ToggleSnappingEvent, UpgradeDraftEvent,
+    },
+    hud::{GameMenuSet, ToggleGameMenuEvent, UpdateSelectionBoxEvent, UpdateSelectionLassoEvent},
     mouse::{
-        DragUpdateType, MouseClickedEvent, MouseDoubleClickedEvent, MouseDraggedEvent, MouseSet,
-        Pointer, PointerSet,
+        DragUpdateType, Gesture, GestureDirection, MouseClickedEvent, MouseDoubleClickedEvent,
+        MouseDraggedEvent, MouseGestureEvent, MousePosition, MouseSet, Pointer, PointerSet,
     },
     selection::{
-        AreaSelectSet, SelectEvent, SelectInRectEvent, Selected, SelectionMode, SelectionSet,
+        AreaSelectSet, BalanceGroupsEvent, ControlGroupEvent, CycleGroupTypeEvent,
+        CyclePrimaryEvent, GroupAction, GroupsSet, MarkersSet, PrimarySet, SelectEvent,
+        SelectInPolygonEvent, SelectInRectEvent, SelectSameTypeEvent, Selected, SelectionMode,
+        SelectionSet, SplitSelectionEvent, ToggleGroupAlertEvent, ToggleMarkersEvent, BRUSH_KEY,
+        GROUP_COUNT,
     },
 };
 
+/// Radius (in meters) of the circle on which units guarding a point or an
+/// entity are placed.
+const GUARD_RADIUS: f32 = 12.;
+/// Units which lost health at most this long ago are selected by
+/// [`select_damaged`].
+const DAMAGE_SELECTION_WINDOW: Duration = Duration::from_secs(1);
 /// Horizontal camera movement is initiated if mouse cursor is within this
 /// distance to window edge.
 const MOVE_MARGIN: f32 = 2.;
@@ -53,19 +75,115 @@ pub(super) struct HandlersPlugin;
 
 impl HandlersPlugin {
     fn add_place_draft_systems(app: &mut App) {
-        let key_map = enum_map! {
-            BuildingType::Base => KeyCode::B,
-            BuildingType::PowerHub => KeyCode::P,
-        };
+        app.init_resource::<BuildHotbar>()
+            .add_event::<HotbarEvent>()
+            .add_systems(
+                InputSchedule,
+                (
+                    hotbar_keys.in_set(HandlersSet::Hotbar),
+                    place_draft
+                        .after(HandlersSet::Hotbar)
+                        .after(PointerSet::Update)
+                        .before(DraftSet::New),
+                    upgrade_draft
+                        .after(HandlersSet::Hotbar)
+                        .before(DraftSet::New),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+
+    fn add_control_group_systems(app: &mut App) {
+        let keys: [KeyCode; GROUP_COUNT] = [
+            KeyCode::Key0,
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+
+        for (group, key) in keys.into_iter().enumerate() {
+            let conditions = [
+                (KeyCondition::single(key), GroupAction::Recall),
+                (KeyCondition::single(key).with_ctrl(), GroupAction::Assign),
+                (
+                    KeyCondition::single(key).with_ctrl().with_shift(),
+                    GroupAction::Append,
+                ),
+            ];
+
+            for (condition, action) in conditions {
+                app.add_systems(
+                    InputSchedule,
+                    control_group(group, action)
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(condition.build())
+                        .before(GroupsSet::Update),
+                );
+            }
 
-        for (building_type, &key) in key_map.iter() {
             app.add_systems(
                 InputSchedule,
-                place_draft(building_type)
+                toggle_group_alert(group)
                     .run_if(in_state(GameState::Playing))
-                    .run_if(KeyCondition::single(key).build())
-                    .before(DraftSet::New)
-                    .after(PointerSet::Update),
+                    .run_if(KeyCondition::single(key).with_shift().build())
+                    .before(GroupsSet::Update),
+            );
+        }
+
+        app.add_systems(
+            InputSchedule,
+            (
+                split_selection
+                    .run_if(KeyCondition::single(KeyCode::O).with_ctrl().build())
+                    .before(GroupsSet::Update),
+                cycle_group_type
+                    .run_if(KeyCondition::single(KeyCode::Tab).with_shift().build())
+                    .before(GroupsSet::Update),
+                balance_groups
+                    .run_if(
+                        KeyCondition::single(KeyCode::O)
+                            .with_ctrl()
+                            .with_shift()
+                            .build(),
+                    )
+                    .before(GroupsSet::Update),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+impl HandlersPlugin {
+    /// Pressing a function key together with control saves selected
+    /// buildings as a blueprint named after the key. Pressing the key alone
+    /// starts drafting of the blueprint.
+    fn add_blueprint_systems(app: &mut App) {
+        let keys = [
+            (KeyCode::F1, "F1"),
+            (KeyCode::F2, "F2"),
+            (KeyCode::F3, "F3"),
+            (KeyCode::F4, "F4"),
+        ];
+
+        for (key, name) in keys {
+            app.add_systems(
+                InputSchedule,
+                (
+                    save_blueprint(name)
+                        .run_if(KeyCondition::single(key).with_ctrl().build())
+                        .before(DraftSet::Blueprint),
+                    place_blueprint(name)
+                        .run_if(KeyCondition::single(key).build())
+                        .after(PointerSet::Update)
+                        .before(DraftSet::New),
+                )
+                    .run_if(in_state(GameState::Playing)),
             );
         }
     }
@@ -74,6 +192,33 @@ impl HandlersPlugin {
 impl Plugin for HandlersPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
+            InputSchedule,
+            gesture_handler
+                .run_if(in_state(GameState::Playing))
+                .run_if(on_event::<MouseGestureEvent>())
+                .after(MouseSet::Gestures)
+                .before(CommandsSet::Queue)
+                .before(QueueSet::QueueEvent),
+        )
+        .add_systems(
+            InputSchedule,
+            (
+                toggle_run
+                    .run_if(KeyCondition::single(KeyCode::R).build())
+                    .before(CommandsSet::Speed),
+                toggle_stand_ground
+                    .run_if(KeyCondition::single(KeyCode::T).build())
+                    .before(CommandsSet::Stance),
+                repeat_order
+                    .run_if(KeyCondition::single(KeyCode::Period).build())
+                    .before(CommandsSet::Repeat),
+                scout
+                    .run_if(KeyCondition::single(KeyCode::C).build())
+                    .before(CommandsSet::Scout),
+            )
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
             InputSchedule,
             (
                 right_click_handler
@@ -82,7 +227,9 @@ impl Plugin for HandlersPlugin {
                     .after(MouseSet::Buttons)
                     .before(CommandsSet::SendSelected)
                     .before(CommandsSet::DeliveryLocation)
-                    .before(CommandsSet::Attack),
+                    .before(CommandsSet::Attack)
+                    .before(CommandsSet::Follow)
+                    .before(CommandsSet::Queue),
                 left_click_handler
                     .run_if(on_click(MouseButton::Left))
                     .in_set(HandlersSet::LeftClick)
@@ -92,6 +239,7 @@ impl Plugin for HandlersPlugin {
                     .after(MouseSet::Buttons),
                 double_click_handler
                     .run_if(on_double_click(MouseButton::Left))
+                    .before(AreaSelectSet::SelectInArea)
                     .before(SelectionSet::Update)
                     .before(DraftSet::Spawn)
                     .after(PointerSet::Update)
@@ -110,6 +258,9 @@ impl Plugin for HandlersPlugin {
                 select_all
                     .run_if(KeyCondition::single(KeyCode::A).with_ctrl().build())
                     .before(SelectionSet::Update),
+                select_damaged
+                    .run_if(KeyCondition::single(KeyCode::D).with_ctrl().build())
+                    .before(SelectionSet::Update),
                 select_all_visible
                     .run_if(
                         KeyCondition::single(KeyCode::A)
@@ -121,17 +272,100 @@ impl Plugin for HandlersPlugin {
                 update_drags
                     .before(AreaSelectSet::SelectInArea)
                     .after(MouseSet::Buttons),
+                update_lasso
+                    .before(AreaSelectSet::SelectInArea)
+                    .after(MouseSet::Buttons)
+                    .after(MouseSet::Gestures),
+                toggle_snapping
+                    .run_if(KeyCondition::single(KeyCode::N).build())
+                    .before(DraftSet::Snapping),
+                toggle_markers
+                    .run_if(KeyCondition::single(KeyCode::M).build())
+                    .before(MarkersSet::Toggle),
+                cycle_primary
+                    .run_if(KeyCondition::single(KeyCode::Tab).build())
+                    .before(PrimarySet::Update),
+                guard_selected
+                    .run_if(KeyCondition::single(KeyCode::H).build())
+                    .after(PointerSet::Update)
+                    .before(CommandsSet::Guard),
+                undo_order
+                    .run_if(KeyCondition::single(KeyCode::Z).with_ctrl().build())
+                    .before(CommandsSet::Queue),
+                go_signal
+                    .run_if(KeyCondition::single(KeyCode::Return).with_ctrl().build())
+                    .before(QueueSet::QueueEvent),
+                fall_back
+                    .run_if(KeyCondition::single(KeyCode::F).build())
+                    .before(CommandsSet::Queue),
+                give_selected
+                    .run_if(KeyCondition::single(KeyCode::G).with_ctrl().build())
+                    .after(PointerSet::Update)
+                    .before(CommandsSet::Give)
+                    .before(SelectionSet::Update),
             )
                 .run_if(in_state(GameState::Playing)),
         );
 
         Self::add_place_draft_systems(app);
+        Self::add_control_group_systems(app);
+        Self::add_blueprint_systems(app);
     }
 }
 
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
 pub(crate) enum HandlersSet {
     LeftClick,
+    Hotbar,
+}
+
+/// Mapping of keys to building types. Pressing a bound key starts drafting of
+/// the building at the pointer. Pressing the key together with control starts
+/// drafting of the building replacing the single selected building.
+///
+/// Insert the resource before the controller plugins are added to override
+/// the default bindings.
+#[derive(Resource, Clone, Debug)]
+pub struct BuildHotbar(AHashMap<KeyCode, BuildingType>);
+
+impl BuildHotbar {
+    /// Creates a hotbar without any bindings.
+    pub fn empty() -> Self {
+        Self(AHashMap::new())
+    }
+
+    /// Binds a key to a building type. Any previous binding of the key is
+    /// replaced.
+    pub fn bind(&mut self, key: KeyCode, building_type: BuildingType) {
+        self.0.insert(key, building_type);
+    }
+
+    /// Removes binding of a key.
+    pub fn unbind(&mut self, key: KeyCode) {
+        self.0.remove(&key);
+    }
+
+    /// Returns the building type bound to a key.
+    pub fn get(&self, key: KeyCode) -> Option<BuildingType> {
+        self.0.get(&key).copied()
+    }
+}
+
+impl Default for BuildHotbar {
+    fn default() -> Self {
+        let mut hotbar = Self::empty();
+        hotbar.bind(KeyCode::B, BuildingType::Base);
+        hotbar.bind(KeyCode::P, BuildingType::PowerHub);
+        hotbar
+    }
+}
+
+/// This event is sent when a key bound in [`BuildHotbar`] is pressed.
+#[derive(Event)]
+struct HotbarEvent {
+    building_type: BuildingType,
+    /// True if the building shall replace the selected building.
+    upgrade: bool,
 }
 
 fn on_click(button: MouseButton) -> impl Fn(EventReader<MouseClickedEvent>) -> bool {
@@ -150,37 +384,180 @@ fn on_double_click(button: MouseButton) -> impl Fn(EventReader<MouseDoubleClicke
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn right_click_handler(
     config: Res<GameConfig>,
+    keys: Res<Input<KeyCode>>,
     mut send_events: EventWriter<SendSelectedEvent>,
+    mut spread_events: EventWriter<SpreadSelectedEvent>,
+    mut queue_events: EventWriter<QueueSelectedEvent>,
+    mut follow_events: EventWriter<FollowSelectedEvent>,
     mut location_events: EventWriter<DeliveryLocationSelectedEvent>,
     mut attack_events: EventWriter<GroupAttackEvent>,
-    targets: Query<&PlayerComponent>,
+    targets: Query<(&PlayerComponent, Has<MovableSolid>)>,
     pointer: Res<Pointer>,
 ) {
-    match pointer.entity().filter(|&entity| {
-        targets
-            .get(entity)
-            .map(|&player| !config.locals().is_playable(*player))
-            .unwrap_or(false)
-    }) {
-        Some(enemy) => attack_events.send(GroupAttackEvent::new(enemy)),
-        None => {
+    let target = pointer
+        .entity()
+        .and_then(|entity| targets.get(entity).ok().map(|target| (entity, target)));
+
+    match target {
+        Some((enemy, (&player, _))) if !config.locals().is_playable(*player) => {
+            attack_events.send(GroupAttackEvent::new(enemy));
+            location_events.send(DeliveryLocationSelectedEvent::new(RallyTarget::Enemy(
+                enemy,
+            )));
+        }
+        // Holding control while clicking a friendly unit makes the selected
+        // units follow it.
+        Some((leader, (_, true)))
+            if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) =>
+        {
+            follow_events.send(FollowSelectedEvent::new(leader));
+        }
+        _ => {
             let Some(target) = pointer.terrain_point().map(|p| p.to_flat()) else {
                 return;
             };
-            send_events.send(SendSelectedEvent::new(target));
-            location_events.send(DeliveryLocationSelectedEvent::new(target));
+            // Holding shift appends the order to the command queue (and the
+            // previous rally point to the rally path). Holding ctrl in
+            // addition delays the order until the go signal. Holding only
+            // ctrl spreads the units around the target.
+            let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+            let rally_target = RallyTarget::Point(target);
+            let location = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+                if ctrl {
+                    queue_events.send(QueueSelectedEvent::delayed(target, StartAt::GoSignal));
+                } else {
+                    queue_events.send(QueueSelectedEvent::new(target));
+                }
+                DeliveryLocationSelectedEvent::through(rally_target)
+            } else {
+                if ctrl {
+                    spread_events.send(SpreadSelectedEvent::new(target));
+                } else {
+                    send_events.send(SendSelectedEvent::new(target));
+                }
+                DeliveryLocationSelectedEvent::new(rally_target)
+            };
+            location_events.send(location);
+        }
+    }
+}
+
+fn repeat_order(mut events: EventWriter<RepeatSelectedEvent>) {
+    events.send(RepeatSelectedEvent);
+}
+
+fn toggle_snapping(mut events: EventWriter<ToggleSnappingEvent>) {
+    events.send(ToggleSnappingEvent);
+}
+
+fn toggle_markers(mut events: EventWriter<ToggleMarkersEvent>) {
+    events.send(ToggleMarkersEvent);
+}
+
+fn cycle_primary(mut events: EventWriter<CyclePrimaryEvent>) {
+    events.send(CyclePrimaryEvent);
+}
+
+fn split_selection(mut events: EventWriter<SplitSelectionEvent>) {
+    events.send(SplitSelectionEvent);
+}
+
+fn cycle_group_type(mut events: EventWriter<CycleGroupTypeEvent>) {
+    events.send(CycleGroupTypeEvent);
+}
+
+fn balance_groups(mut events: EventWriter<BalanceGroupsEvent>) {
+    events.send(BalanceGroupsEvent);
+}
+
+fn undo_order(mut events: EventWriter<UndoSelectedEvent>) {
+    events.send(UndoSelectedEvent);
+}
+
+fn fall_back(mut events: EventWriter<FallBackSelectedEvent>) {
+    events.send(FallBackSelectedEvent);
+}
+
+fn scout(mut events: EventWriter<ScoutSelectedEvent>) {
+    events.send(ScoutSelectedEvent);
+}
+
+fn toggle_run(mut events: EventWriter<ToggleRunSelectedEvent>) {
+    events.send(ToggleRunSelectedEvent);
+}
+
+fn toggle_stand_ground(mut events: EventWriter<ToggleStandGroundSelectedEvent>) {
+    events.send(ToggleStandGroundSelectedEvent);
+}
+
+/// Starts all queued orders waiting for the go signal.
+fn go_signal(mut events: EventWriter<GoSignalEvent>) {
+    events.send(GoSignalEvent);
+}
+
+/// Issues commands mapped to mouse gestures: flick left undoes the most
+/// recently queued order and flick right gives the go signal.
+fn gesture_handler(
+    mut gestures: EventReader<MouseGestureEvent>,
+    mut undo_events: EventWriter<UndoSelectedEvent>,
+    mut go_events: EventWriter<GoSignalEvent>,
+) {
+    for event in gestures.iter() {
+        match event.gesture() {
+            Gesture::Flick(GestureDirection::Left) => undo_events.send(UndoSelectedEvent),
+            Gesture::Flick(GestureDirection::Right) => go_events.send(GoSignalEvent),
+            Gesture::Flick(GestureDirection::Up | GestureDirection::Down) => (),
         }
     }
 }
 
+/// Orders selected units to guard the pointed entity or terrain point.
+fn guard_selected(pointer: Res<Pointer>, mut events: EventWriter<GuardSelectedEvent>) {
+    let target = match pointer.entity() {
+        Some(entity) => GuardTarget::Entity(entity),
+        None => match pointer.terrain_point() {
+            Some(point) => GuardTarget::Point(point.to_flat()),
+            None => return,
+        },
+    };
+    events.send(GuardSelectedEvent::new(target, GUARD_RADIUS));
+}
+
+/// Gives selected units to the owner of the pointed entity if the owner is an
+/// ally. The given units are deselected.
+fn give_selected(
+    config: Res<GameConfig>,
+    pointer: Res<Pointer>,
+    owners: Query<&PlayerComponent>,
+    selected: Query<Entity, (With<Selected>, With<Playable>)>,
+    mut give_events: EventWriter<GiveSelectedEvent>,
+    mut select_events: EventWriter<SelectEvent>,
+) {
+    let Some(&player) = pointer.entity().and_then(|entity| owners.get(entity).ok()) else {
+        return;
+    };
+    if !config.is_ally(*player) {
+        return;
+    }
+
+    let entities: Vec<Entity> = selected.iter().collect();
+    if !entities.is_empty() {
+        give_events.send(GiveSelectedEvent::new(*player));
+        // Toggling already selected entities deselects them.
+        select_events.send(SelectEvent::many(entities, SelectionMode::AddToggle));
+    }
+}
+
 fn double_click_handler(
     keys: Res<Input<KeyCode>>,
     pointer: Res<Pointer>,
     playable: Query<&ObjectTypeComponent, With<Playable>>,
     drafts: Query<(), With<DraftAllowed>>,
     mut select_in_rect_events: EventWriter<SelectInRectEvent>,
+    mut same_type_events: EventWriter<SelectSameTypeEvent>,
 ) {
     if !drafts.is_empty() {
         return;
@@ -198,12 +575,20 @@ fn double_click_handler(
         return;
     };
 
-    // Select all the units visible of the same type as the targeted entity
-    select_in_rect_events.send(SelectInRectEvent::new(
-        ScreenRect::full(),
-        selection_mode,
-        Some(**targeted_entity_type),
-    ));
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        // Select all the units of the same type on the whole map
+        same_type_events.send(SelectSameTypeEvent::new(
+            **targeted_entity_type,
+            selection_mode,
+        ));
+    } else {
+        // Select all the units visible of the same type as the targeted entity
+        select_in_rect_events.send(SelectInRectEvent::new(
+            ScreenRect::full(),
+            selection_mode,
+            Some(**targeted_entity_type),
+        ));
+    }
 }
 
 fn move_camera_arrows_system(
@@ -272,9 +657,16 @@ fn move_camera_mouse_system(
 
 fn zoom_camera(
     conf: Res<Configuration>,
+    keys: Res<Input<KeyCode>>,
     mut wheel_events: EventReader<MouseWheel>,
     mut zoom_events: EventWriter<ZoomCameraEvent>,
 ) {
+    // Mouse wheel changes selection brush size in this case.
+    if keys.pressed(BRUSH_KEY) {
+        wheel_events.clear();
+        return;
+    }
+
     let conf = conf.camera();
     let factor = wheel_events
         .iter()
@@ -344,27 +736,138 @@ fn handle_escape(
     }
 }
 
+fn hotbar_keys(
+    hotbar: Res<BuildHotbar>,
+    keys: Res<Input<KeyCode>>,
+    mut input_events: EventReader<KeyboardInput>,
+    mut hotbar_events: EventWriter<HotbarEvent>,
+) {
+    let control = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for event in input_events.iter() {
+        if event.state != ButtonState::Pressed || shift {
+            continue;
+        }
+        let Some(building_type) = event.key_code.and_then(|key| hotbar.get(key)) else {
+            continue;
+        };
+        hotbar_events.send(HotbarEvent {
+            building_type,
+            upgrade: control,
+        });
+    }
+}
+
 fn place_draft(
-    building_type: BuildingType,
-) -> impl Fn(Res<GameConfig>, Res<ObjectCounter>, Res<Pointer>, EventWriter<NewDraftEvent>) {
-    move |conf: Res<GameConfig>,
-          counter: Res<ObjectCounter>,
-          pointer: Res<Pointer>,
-          mut events: EventWriter<NewDraftEvent>| {
-        if counter
-            .player(conf.locals().playable())
-            .map_or(0, |c| c.building_count())
-            >= PLAYER_MAX_BUILDINGS
-        {
-            warn!("Maximum number of buildings reached.");
+    conf: Res<GameConfig>,
+    counter: Res<ObjectCounter>,
+    pointer: Res<Pointer>,
+    mut hotbar_events: EventReader<HotbarEvent>,
+    mut draft_events: EventWriter<NewDraftEvent>,
+) {
+    let Some(building_type) = hotbar_events
+        .iter()
+        .filter(|event| !event.upgrade)
+        .last()
+        .map(|event| event.building_type)
+    else {
+        return;
+    };
+
+    if counter
+        .player(conf.locals().playable())
+        .map_or(0, |c| c.building_count())
+        >= PLAYER_MAX_BUILDINGS
+    {
+        warn!("Maximum number of buildings reached.");
+        return;
+    }
+
+    let point = match pointer.terrain_point() {
+        Some(point) => point,
+        None => return,
+    };
+    draft_events.send(NewDraftEvent::new(point, building_type));
+}
+
+type SelectedBuildings<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static ObjectTypeComponent),
+    (With<Selected>, With<Playable>, With<StaticSolid>),
+>;
+
+/// Starts drafting of a building which replaces the single selected building.
+fn upgrade_draft(
+    selected: SelectedBuildings,
+    mut hotbar_events: EventReader<HotbarEvent>,
+    mut upgrade_events: EventWriter<UpgradeDraftEvent>,
+) {
+    let Some(building_type) = hotbar_events
+        .iter()
+        .filter(|event| event.upgrade)
+        .last()
+        .map(|event| event.building_type)
+    else {
+        return;
+    };
+
+    let Ok((entity, &object_type)) = selected.get_single() else {
+        return;
+    };
+    if *object_type != ObjectType::Active(ActiveObjectType::Building(building_type)) {
+        upgrade_events.send(UpgradeDraftEvent::new(entity, building_type));
+    }
+}
+
+fn save_blueprint(name: &'static str) -> impl Fn(EventWriter<SaveBlueprintEvent>) {
+    move |mut events: EventWriter<SaveBlueprintEvent>| {
+        events.send(SaveBlueprintEvent::new(name));
+    }
+}
+
+#[derive(SystemParam)]
+struct BlueprintPlacement<'w> {
+    conf: Res<'w, GameConfig>,
+    counter: Res<'w, ObjectCounter>,
+    blueprints: Res<'w, Blueprints>,
+    pointer: Res<'w, Pointer>,
+    events: EventWriter<'w, NewBlueprintDraftEvent>,
+}
+
+fn place_blueprint(name: &'static str) -> impl Fn(BlueprintPlacement) {
+    move |mut placement: BlueprintPlacement| {
+        let Some(blueprint) = placement.blueprints.get(name) else {
+            return;
+        };
+
+        let count = placement
+            .counter
+            .player(placement.conf.locals().playable())
+            .map_or(0, |c| c.building_count());
+        if count as usize + blueprint.len() > PLAYER_MAX_BUILDINGS as usize {
+            warn!("Maximum number of buildings would be exceeded.");
             return;
         }
 
-        let point = match pointer.terrain_point() {
-            Some(point) => point,
-            None => return,
+        let Some(point) = placement.pointer.terrain_point() else {
+            return;
         };
-        events.send(NewDraftEvent::new(point, building_type));
+        let event = NewBlueprintDraftEvent::new(point, blueprint.clone());
+        placement.events.send(event);
+    }
+}
+
+fn control_group(group: usize, action: GroupAction) -> impl Fn(EventWriter<ControlGroupEvent>) {
+    move |mut events: EventWriter<ControlGroupEvent>| {
+        events.send(ControlGroupEvent::new(group, action));
+    }
+}
+
+fn toggle_group_alert(group: usize) -> impl Fn(EventWriter<ToggleGroupAlertEvent>) {
+    move |mut events: EventWriter<ToggleGroupAlertEvent>| {
+        events.send(ToggleGroupAlertEvent::new(group));
     }
 }
 
@@ -376,6 +879,24 @@ fn select_all(
     events.send(SelectEvent::many(entities, SelectionMode::AddToggle));
 }
 
+type DamagedUnits<'w, 's> =
+    Query<'w, 's, (Entity, &'static RecentlyDamaged), (With<Playable>, With<MovableSolid>)>;
+
+/// Selects all units of the local player which are under attack, i.e. which
+/// recently lost health.
+fn select_damaged(time: Res<Time>, units: DamagedUnits, mut events: EventWriter<SelectEvent>) {
+    let entities: Vec<Entity> = units
+        .iter()
+        .filter(|(_, damaged)| damaged.within(time.elapsed(), DAMAGE_SELECTION_WINDOW))
+        .map(|(entity, _)| entity)
+        .collect();
+    // The current selection is kept when no unit was damaged recently.
+    if entities.is_empty() {
+        return;
+    }
+    events.send(SelectEvent::many(entities, SelectionMode::Replace));
+}
+
 fn select_all_visible(mut events: EventWriter<SelectInRectEvent>) {
     events.send(SelectInRectEvent::new(
         ScreenRect::full(),
@@ -419,3 +940,207 @@ fn update_drags(
         ui_events.send(ui_event)
     }
 }
+
+/// Dragging with the right mouse button draws a freehand lasso. Drags
+/// recognized as mouse gestures (e.g. flicks) do not select anything.
+fn update_lasso(
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<MousePosition>,
+    mut lasso: Local<Vec<Vec2>>,
+    mut drag_events: EventReader<MouseDraggedEvent>,
+    mut gestures: EventReader<MouseGestureEvent>,
+    mut ui_events: EventWriter<UpdateSelectionLassoEvent>,
+    mut polygon_events: EventWriter<SelectInPolygonEvent>,
+) {
+    let gesture = gestures.iter().count() > 0;
+
+    for drag_event in drag_events.iter() {
+        if drag_event.button() != MouseButton::Right {
+            continue;
+        }
+
+        match drag_event.update_type() {
+            DragUpdateType::Moved => {
+                lasso.extend(mouse.ndc());
+                ui_events.send(UpdateSelectionLassoEvent::from_vertices(lasso.clone()));
+            }
+            DragUpdateType::Released => {
+                let vertices = std::mem::take(&mut *lasso);
+                // Degenerate polygons enclose nothing.
+                if !gesture && vertices.len() >= 3 {
+                    let mode = if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+                        SelectionMode::Add
+                    } else {
+                        SelectionMode::Replace
+                    };
+                    polygon_events.send(SelectInPolygonEvent::new(
+                        ScreenPolygon::new(vertices),
+                        mode,
+                    ));
+                }
+
+                ui_events.send(UpdateSelectionLassoEvent::none());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::ecs::system::SystemState;
+    use de_core::gconfig::LocalPlayers;
+    use de_types::player::Player;
+
+    use super::*;
+
+    #[test]
+    fn test_select_damaged() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_event::<SelectEvent>()
+            .add_systems(Update, select_damaged);
+
+        let start = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(start);
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(5500));
+        let now = app.world.resource::<Time>().elapsed();
+
+        let recent = app
+            .world
+            .spawn((
+                Playable,
+                MovableSolid,
+                RecentlyDamaged::new(now - Duration::from_millis(500)),
+            ))
+            .id();
+        app.world.spawn((
+            Playable,
+            MovableSolid,
+            RecentlyDamaged::new(now - Duration::from_secs(5)),
+        ));
+        app.update();
+
+        let mut state = SystemState::<EventReader<SelectEvent>>::new(&mut app.world);
+        let events: Vec<SelectEvent> = state.get_mut(&mut app.world).iter().cloned().collect();
+        assert_eq!(
+            events,
+            vec![SelectEvent::many(vec![recent], SelectionMode::Replace)]
+        );
+
+        app.world.entity_mut(recent).remove::<RecentlyDamaged>();
+        app.update();
+        assert!(state.get_mut(&mut app.world).is_empty());
+    }
+
+    #[test]
+    fn test_hotbar() {
+        let mut app = App::new();
+        app.init_resource::<BuildHotbar>()
+            .init_resource::<Input<KeyCode>>()
+            .add_event::<KeyboardInput>()
+            .add_event::<HotbarEvent>()
+            .add_systems(Update, hotbar_keys);
+
+        let window = app.world.spawn_empty().id();
+        let press = |app: &mut App, key: KeyCode| {
+            app.world.send_event(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key),
+                state: ButtonState::Pressed,
+                window,
+            });
+            app.update();
+        };
+        let mut state = SystemState::<EventReader<HotbarEvent>>::new(&mut app.world);
+
+        let mut events = |app: &mut App| -> Vec<(BuildingType, bool)> {
+            state
+                .get_mut(&mut app.world)
+                .iter()
+                .map(|event| (event.building_type, event.upgrade))
+                .collect()
+        };
+
+        press(&mut app, KeyCode::P);
+        assert_eq!(events(&mut app), vec![(BuildingType::PowerHub, false)]);
+
+        press(&mut app, KeyCode::K);
+        assert!(events(&mut app).is_empty());
+
+        app.world
+            .resource_mut::<BuildHotbar>()
+            .bind(KeyCode::K, BuildingType::Base);
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::ControlLeft);
+        press(&mut app, KeyCode::K);
+        assert_eq!(events(&mut app), vec![(BuildingType::Base, true)]);
+    }
+
+    #[test]
+    fn test_give_selected() {
+        let mut app = App::new();
+        app.insert_resource(
+            GameConfig::new("map.tar", false, LocalPlayers::from_single(Player::Player1))
+                .with_allies(&[Player::Player2]),
+        )
+        .init_resource::<Pointer>()
+        .add_event::<GiveSelectedEvent>()
+        .add_event::<SelectEvent>()
+        .add_systems(Update, give_selected);
+
+        app.world
+            .spawn((Selected, Playable, PlayerComponent::from(Player::Player1)));
+        let ally = app.world.spawn(PlayerComponent::from(Player::Player2)).id();
+        let enemy = app.world.spawn(PlayerComponent::from(Player::Player3)).id();
+
+        let mut state = SystemState::<EventReader<GiveSelectedEvent>>::new(&mut app.world);
+
+        // Units are never given to enemies.
+        app.world.resource_mut::<Pointer>().set_entity(Some(enemy));
+        app.update();
+        assert_eq!(state.get_mut(&mut app.world).iter().count(), 0);
+
+        app.world.resource_mut::<Pointer>().set_entity(Some(ally));
+        app.update();
+        let events: Vec<GiveSelectedEvent> =
+            state.get_mut(&mut app.world).iter().cloned().collect();
+        assert_eq!(events, vec![GiveSelectedEvent::new(Player::Player2)]);
+    }
+
+    #[test]
+    fn test_right_click_without_terrain() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::new(
+            "map.tar",
+            false,
+            LocalPlayers::from_single(Player::Player1),
+        ))
+        .init_resource::<Input<KeyCode>>()
+        // The pointer points to neither an entity nor the terrain, e.g.
+        // because the camera faces the horizon.
+        .init_resource::<Pointer>()
+        .add_event::<SendSelectedEvent>()
+        .add_event::<SpreadSelectedEvent>()
+        .add_event::<QueueSelectedEvent>()
+        .add_event::<FollowSelectedEvent>()
+        .add_event::<DeliveryLocationSelectedEvent>()
+        .add_event::<GroupAttackEvent>()
+        .add_systems(Update, right_click_handler);
+        app.update();
+
+        assert!(app.world.resource::<Events<SendSelectedEvent>>().is_empty());
+        assert!(app
+            .world
+            .resource::<Events<QueueSelectedEvent>>()
+            .is_empty());
+        assert!(app
+            .world
+            .resource::<Events<DeliveryLocationSelectedEvent>>()
+            .is_empty());
+    }
+}
@@ -2,9 +2,14 @@
 //! actions.
 
 use bevy::prelude::*;
+pub use executor::CommandDeniedEvent;
 pub(crate) use executor::{
-    CommandsSet, DeliveryLocationSelectedEvent, GroupAttackEvent, SendSelectedEvent,
+    CommandsSet, DeliveryLocationSelectedEvent, FallBackSelectedEvent, FollowSelectedEvent,
+    GiveSelectedEvent, GroupAttackEvent, GuardSelectedEvent, QueueSelectedEvent,
+    RepeatSelectedEvent, ScoutSelectedEvent, SendSelectedEvent, SpreadSelectedEvent,
+    ToggleRunSelectedEvent, ToggleStandGroundSelectedEvent, UndoSelectedEvent,
 };
+pub use handlers::BuildHotbar;
 
 use self::{executor::ExecutorPlugin, handlers::HandlersPlugin};
 
@@ -1,12 +1,34 @@
+use ahash::AHashMap;
 use bevy::prelude::*;
 use de_core::{
-    cleanup::DespawnOnGameExit, gamestate::GameState, gconfig::GameConfig,
-    objects::ObjectTypeComponent, schedule::InputSchedule, state::AppState,
+    cleanup::DespawnOnGameExit,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    objects::{ObjectTypeComponent, Playable, StaticSolid},
+    schedule::InputSchedule,
+    state::AppState,
 };
-use de_spawner::{DraftAllowed, DraftBundle, SpawnLocalActiveEvent};
-use de_types::objects::{BuildingType, ObjectType};
+use de_index::EntityIndex;
+use de_objects::SolidObjects;
+use de_spawner::{
+    DespawnActiveLocalEvent, DraftAllowed, DraftBundle, DraftReplaces, SpawnLocalActiveEvent,
+    UpgradeDraftBundle,
+};
+use de_types::{
+    objects::{ActiveObjectType, BuildingType, ObjectType},
+    projection::{ToAltitude, ToFlat},
+};
+use parry2d::{bounding_volume::Aabb, math::Isometry};
 
-use crate::mouse::{Pointer, PointerSet};
+use crate::{
+    mouse::{Pointer, PointerSet},
+    selection::Selected,
+};
+
+/// Drafts are snapped to buildings closer than this distance.
+const SNAP_DISTANCE: f32 = 5.;
+/// Gap between edges of a snapped draft and the building it is snapped to.
+const SNAP_GAP: f32 = 1.;
 
 pub(crate) struct DraftPlugin;
 
@@ -14,7 +36,13 @@ impl Plugin for DraftPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnDraftsEvent>()
             .add_event::<NewDraftEvent>()
+            .add_event::<UpgradeDraftEvent>()
             .add_event::<DiscardDraftsEvent>()
+            .add_event::<ToggleSnappingEvent>()
+            .add_event::<SaveBlueprintEvent>()
+            .add_event::<NewBlueprintDraftEvent>()
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
                 InputSchedule,
                 (
@@ -23,14 +51,31 @@ impl Plugin for DraftPlugin {
                             .run_if(on_event::<SpawnDraftsEvent>())
                             .in_set(DraftSet::Spawn),
                         new_drafts.in_set(DraftSet::New),
+                        upgrade_drafts
+                            .run_if(on_event::<UpgradeDraftEvent>())
+                            .in_set(DraftSet::New)
+                            .after(new_drafts),
+                        new_blueprint_drafts
+                            .run_if(on_event::<NewBlueprintDraftEvent>())
+                            .in_set(DraftSet::New)
+                            .after(upgrade_drafts),
                         discard_drafts
                             .run_if(on_event::<DiscardDraftsEvent>())
                             .in_set(DraftSet::Discard),
                     )
                         .run_if(in_state(AppState::InGame)),
+                    save_blueprint
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(on_event::<SaveBlueprintEvent>())
+                        .in_set(DraftSet::Blueprint),
+                    toggle_snapping
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(on_event::<ToggleSnappingEvent>())
+                        .in_set(DraftSet::Snapping),
                     move_drafts
                         .run_if(in_state(GameState::Playing))
-                        .after(PointerSet::Update),
+                        .after(PointerSet::Update)
+                        .after(DraftSet::Snapping),
                 ),
             );
     }
@@ -41,20 +86,152 @@ pub(crate) enum DraftSet {
     Spawn,
     New,
     Discard,
+    Snapping,
+    Blueprint,
 }
 
-#[derive(Event)]
+#[derive(Event, Clone, PartialEq, Debug)]
 pub(crate) struct SpawnDraftsEvent;
 
-#[derive(Event)]
+#[derive(Event, Clone, PartialEq, Debug)]
 pub(crate) struct NewDraftEvent {
     point: Vec3,
     building_type: BuildingType,
 }
 
-#[derive(Event)]
+/// Send this event to start drafting of a building which replaces (upgrades)
+/// an existing building.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct UpgradeDraftEvent {
+    entity: Entity,
+    building_type: BuildingType,
+}
+
+impl UpgradeDraftEvent {
+    /// # Arguments
+    ///
+    /// * `entity` - the building to be replaced.
+    ///
+    /// * `building_type` - type of the new building.
+    pub(crate) fn new(entity: Entity, building_type: BuildingType) -> Self {
+        Self {
+            entity,
+            building_type,
+        }
+    }
+}
+
+#[derive(Event, Clone, PartialEq, Debug)]
 pub(crate) struct DiscardDraftsEvent;
 
+/// Send this event to toggle snapping of drafts to nearby buildings.
+#[derive(Event)]
+pub(crate) struct ToggleSnappingEvent;
+
+/// Send this event to save selected buildings of the local player as a named
+/// blueprint. A previously saved blueprint of the same name is replaced.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct SaveBlueprintEvent(String);
+
+impl SaveBlueprintEvent {
+    pub(crate) fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    fn name(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Send this event to start drafting of all buildings of a blueprint at
+/// once. All current drafts are discarded.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub(crate) struct NewBlueprintDraftEvent {
+    point: Vec3,
+    blueprint: Blueprint,
+}
+
+impl NewBlueprintDraftEvent {
+    /// # Arguments
+    ///
+    /// * `point` - position of the center of the blueprint.
+    ///
+    /// * `blueprint` - the drafted arrangement of buildings.
+    pub(crate) fn new(point: Vec3, blueprint: Blueprint) -> Self {
+        Self { point, blueprint }
+    }
+
+    fn point(&self) -> Vec3 {
+        self.point
+    }
+
+    fn blueprint(&self) -> &Blueprint {
+        &self.blueprint
+    }
+}
+
+/// Saved blueprints by their names.
+#[derive(Resource, Default)]
+pub(crate) struct Blueprints(AHashMap<String, Blueprint>);
+
+impl Blueprints {
+    pub(crate) fn get(&self, name: &str) -> Option<&Blueprint> {
+        self.0.get(name)
+    }
+
+    fn insert(&mut self, name: String, blueprint: Blueprint) {
+        self.0.insert(name, blueprint);
+    }
+}
+
+/// An arrangement of buildings which is drafted and placed as a whole.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Blueprint(Vec<(BuildingType, Vec2)>);
+
+impl Blueprint {
+    /// Creates a blueprint from building types and their positions on the
+    /// map. The positions are stored relative to their mean.
+    fn from_buildings(buildings: Vec<(BuildingType, Vec2)>) -> Self {
+        let center = buildings
+            .iter()
+            .map(|&(_, position)| position)
+            .sum::<Vec2>()
+            / buildings.len().max(1) as f32;
+        Self(
+            buildings
+                .into_iter()
+                .map(|(building_type, position)| (building_type, position - center))
+                .collect(),
+        )
+    }
+
+    /// Returns number of buildings in the blueprint.
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns types of the buildings together with their offsets (in map
+    /// coordinates) from the center of the blueprint.
+    fn buildings(&self) -> impl Iterator<Item = (BuildingType, Vec2)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// Offset (in map coordinates) of a blueprint draft from the pointed
+/// position.
+#[derive(Component)]
+struct BlueprintOffset(Vec2);
+
+/// Whether drafts are snapped edge-to-edge to nearby buildings.
+#[derive(Resource)]
+struct DraftSnapping(bool);
+
+impl Default for DraftSnapping {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
 impl NewDraftEvent {
     pub(crate) fn new(point: Vec3, building_type: BuildingType) -> Self {
         Self {
@@ -72,15 +249,31 @@ impl NewDraftEvent {
     }
 }
 
+type DraftQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static ObjectTypeComponent,
+        &'static DraftAllowed,
+        Option<&'static DraftReplaces>,
+    ),
+>;
+
 fn spawn(
     mut commands: Commands,
     game_config: Res<GameConfig>,
-    drafts: Query<(Entity, &Transform, &ObjectTypeComponent, &DraftAllowed)>,
+    drafts: DraftQuery,
     mut spawn_active_events: EventWriter<SpawnLocalActiveEvent>,
+    mut despawn_active_events: EventWriter<DespawnActiveLocalEvent>,
 ) {
-    for (entity, &transform, &object_type, draft) in drafts.iter() {
+    for (entity, &transform, &object_type, draft, replaces) in drafts.iter() {
         if draft.allowed() {
             commands.entity(entity).despawn_recursive();
+            if let Some(replaces) = replaces {
+                despawn_active_events.send(DespawnActiveLocalEvent::new(replaces.entity()));
+            }
             let ObjectType::Active(object_type) = *object_type else {
                 panic!("Cannot place draft of an inactive object.");
             };
@@ -120,23 +313,391 @@ fn new_drafts(
     ));
 }
 
+fn upgrade_drafts(
+    mut commands: Commands,
+    mut events: EventReader<UpgradeDraftEvent>,
+    drafts: Query<Entity, With<DraftAllowed>>,
+    buildings: Query<&Transform, With<StaticSolid>>,
+) {
+    let Some(event) = events.iter().last() else {
+        return;
+    };
+    let Ok(&transform) = buildings.get(event.entity) else {
+        return;
+    };
+
+    for entity in drafts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.spawn((
+        UpgradeDraftBundle::new(event.building_type, transform, event.entity),
+        DespawnOnGameExit,
+    ));
+}
+
+fn new_blueprint_drafts(
+    mut commands: Commands,
+    mut events: EventReader<NewBlueprintDraftEvent>,
+    drafts: Query<Entity, With<DraftAllowed>>,
+) {
+    let Some(event) = events.iter().last() else {
+        return;
+    };
+
+    for entity in drafts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for (building_type, offset) in event.blueprint().buildings() {
+        commands.spawn((
+            DraftBundle::new(
+                building_type,
+                Transform::from_translation(event.point() + offset.to_msl()),
+            ),
+            BlueprintOffset(offset),
+            DespawnOnGameExit,
+        ));
+    }
+}
+
+type BlueprintBuildings<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Transform, &'static ObjectTypeComponent),
+    (With<Selected>, With<Playable>, With<StaticSolid>),
+>;
+
+fn save_blueprint(
+    mut blueprints: ResMut<Blueprints>,
+    mut events: EventReader<SaveBlueprintEvent>,
+    buildings: BlueprintBuildings,
+) {
+    let Some(event) = events.iter().last() else {
+        return;
+    };
+
+    let buildings: Vec<(BuildingType, Vec2)> = buildings
+        .iter()
+        .filter_map(|(transform, &object_type)| match *object_type {
+            ObjectType::Active(ActiveObjectType::Building(building_type)) => {
+                Some((building_type, transform.translation.to_flat()))
+            }
+            _ => None,
+        })
+        .collect();
+    if buildings.is_empty() {
+        return;
+    }
+    blueprints.insert(
+        event.name().to_owned(),
+        Blueprint::from_buildings(buildings),
+    );
+}
+
 fn discard_drafts(mut commands: Commands, drafts: Query<Entity, With<DraftAllowed>>) {
     for entity in drafts.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-fn move_drafts(pointer: Res<Pointer>, mut drafts: Query<&mut Transform, With<DraftAllowed>>) {
-    let pointer_changed = pointer.is_changed();
+fn setup(mut commands: Commands) {
+    commands.init_resource::<DraftSnapping>();
+    commands.init_resource::<Blueprints>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<DraftSnapping>();
+    commands.remove_resource::<Blueprints>();
+}
+
+fn toggle_snapping(mut snapping: ResMut<DraftSnapping>) {
+    snapping.0 = !snapping.0;
+}
+
+type MovedDrafts<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static ObjectTypeComponent,
+        Option<&'static BlueprintOffset>,
+    ),
+    (With<DraftAllowed>, Without<DraftReplaces>),
+>;
+
+/// Moves all drafts, except upgrade drafts which stay in place of the
+/// replaced building, to the pointed position. Blueprint drafts keep their
+/// offsets from the pointed position. The other drafts are snapped to nearby
+/// buildings if snapping is enabled.
+fn move_drafts(
+    pointer: Res<Pointer>,
+    snapping: Res<DraftSnapping>,
+    index: Res<EntityIndex>,
+    solids: SolidObjects,
+    buildings: Query<(&Transform, &ObjectTypeComponent), With<StaticSolid>>,
+    mut drafts: MovedDrafts,
+) {
+    let changed = pointer.is_changed() || snapping.is_changed();
 
     let point = match pointer.terrain_point() {
         Some(point) => point,
         None => return,
     };
 
-    for mut transform in drafts.iter_mut() {
-        if transform.is_added() || pointer_changed {
-            transform.translation = point;
+    for (mut transform, &object_type, offset) in drafts.iter_mut() {
+        if !transform.is_added() && !changed {
+            continue;
+        }
+
+        // Snapping of individual buildings would break the arrangement of a
+        // blueprint.
+        if let Some(offset) = offset {
+            transform.translation = point + offset.0.to_msl();
+            continue;
         }
+
+        let mut translation = point;
+        if snapping.0 {
+            let ichnography = solids.get(*object_type).ichnography();
+            let draft = footprint(
+                ichnography.local_aabb(),
+                &Transform::from_translation(point),
+            );
+            let neighbours = index
+                .entities_in_circle(point.to_flat(), ichnography.radius() + SNAP_DISTANCE)
+                .into_iter()
+                .filter_map(|entity| buildings.get(entity).ok())
+                .map(|(transform, &object_type)| {
+                    footprint(
+                        solids.get(*object_type).ichnography().local_aabb(),
+                        transform,
+                    )
+                });
+            translation += snap_offset(&draft, neighbours).to_msl();
+        }
+        transform.translation = translation;
+    }
+}
+
+/// Returns map projected bounding box of a footprint placed with a transform.
+fn footprint(local_aabb: Aabb, transform: &Transform) -> Aabb {
+    let (angle, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+    let isometry = Isometry::new(transform.translation.to_flat().into(), angle);
+    local_aabb.transform_by(&isometry)
+}
+
+/// Returns translation (in map coordinates) which aligns a draft footprint
+/// edge-to-edge (with [`SNAP_GAP`]) to the nearest building footprint within
+/// [`SNAP_DISTANCE`]. The draft is centered to the building along the other
+/// axis. Zero offset is returned if there is no building near enough.
+fn snap_offset(draft: &Aabb, buildings: impl Iterator<Item = Aabb>) -> Vec2 {
+    let draft_center = Vec2::from(draft.center());
+    let draft_half = Vec2::from(draft.half_extents());
+
+    let nearest = buildings
+        .map(|building| {
+            let center = Vec2::from(building.center());
+            let half = Vec2::from(building.half_extents());
+            // Signed distances between the edges along individual axes.
+            let separation = (center - draft_center).abs() - draft_half - half;
+            (center, half, separation)
+        })
+        .filter(|(_, _, separation)| separation.max(Vec2::ZERO).length() <= SNAP_DISTANCE)
+        .min_by(|(_, _, a), (_, _, b)| {
+            let a = a.max(Vec2::ZERO).length();
+            let b = b.max(Vec2::ZERO).length();
+            a.total_cmp(&b)
+        });
+
+    let Some((center, half, separation)) = nearest else {
+        return Vec2::ZERO;
+    };
+
+    let direction = (draft_center - center).signum();
+    let distance = draft_half + half + SNAP_GAP;
+    let target = if separation.x >= separation.y {
+        Vec2::new(center.x + direction.x * distance.x, center.y)
+    } else {
+        Vec2::new(center.x, center.y + direction.y * distance.y)
+    };
+    target - draft_center
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_core::gconfig::LocalPlayers;
+    use de_types::player::Player;
+    use parry2d::math::{Point, Vector};
+
+    use super::*;
+
+    #[test]
+    fn test_snap_offset() {
+        let draft = Aabb::new(Point::new(11.5, 4.), Point::new(15.5, 8.));
+        let building = Aabb::new(Point::new(0., 0.), Point::new(10., 10.));
+        let far = Aabb::new(Point::new(30., 0.), Point::new(40., 10.));
+
+        let offset = snap_offset(&draft, [far, building].into_iter());
+        assert_eq!(offset, Vec2::new(-0.5, -1.));
+        let snapped = Aabb::new(
+            draft.mins + Vector::from(offset),
+            draft.maxs + Vector::from(offset),
+        );
+        assert_eq!(snapped.mins.x - building.maxs.x, SNAP_GAP);
+        assert_eq!(snapped.center().y, building.center().y);
+
+        let below = Aabb::new(Point::new(2., -10.), Point::new(6., -2.5));
+        assert_eq!(
+            snap_offset(&below, [building].into_iter()),
+            Vec2::new(1., 1.5)
+        );
+
+        assert_eq!(snap_offset(&draft, [far].into_iter()), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_blueprint() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::new(
+            "/some/path",
+            false,
+            LocalPlayers::from_single(Player::Player1),
+        ))
+        .init_resource::<Blueprints>()
+        .add_event::<SaveBlueprintEvent>()
+        .add_event::<NewBlueprintDraftEvent>()
+        .add_event::<SpawnDraftsEvent>()
+        .add_event::<SpawnLocalActiveEvent>()
+        .add_event::<DespawnActiveLocalEvent>()
+        .add_systems(
+            Update,
+            (
+                save_blueprint,
+                new_blueprint_drafts,
+                spawn.run_if(on_event::<SpawnDraftsEvent>()),
+            ),
+        );
+
+        let buildings = [
+            (BuildingType::Base, Vec2::new(10., 20.)),
+            (BuildingType::PowerHub, Vec2::new(30., 20.)),
+            (BuildingType::PowerHub, Vec2::new(20., -10.)),
+        ];
+        for (building_type, position) in buildings {
+            let object_type = ObjectTypeComponent::from(ObjectType::Active(
+                ActiveObjectType::Building(building_type),
+            ));
+            app.world.spawn((
+                Selected,
+                Playable,
+                StaticSolid,
+                object_type,
+                Transform::from_translation(position.to_msl()),
+            ));
+        }
+        // Not owned by the local player.
+        app.world.spawn((
+            Selected,
+            StaticSolid,
+            ObjectTypeComponent::from(ObjectType::Active(ActiveObjectType::Building(
+                BuildingType::Base,
+            ))),
+            Transform::default(),
+        ));
+
+        app.world.send_event(SaveBlueprintEvent::new("base"));
+        app.update();
+        let blueprint = app.world.resource::<Blueprints>().get("base").unwrap();
+        assert_eq!(blueprint.len(), 3);
+
+        let point = Vec3::new(-100., 0., 50.);
+        app.world
+            .send_event(NewBlueprintDraftEvent::new(point, blueprint.clone()));
+        app.update();
+
+        let mut drafts = app.world.query::<&mut DraftAllowed>();
+        for mut draft in drafts.iter_mut(&mut app.world) {
+            *draft = DraftAllowed::new(true);
+        }
+        app.world.send_event(SpawnDraftsEvent);
+        app.update();
+
+        let mut spawns = SystemState::<EventReader<SpawnLocalActiveEvent>>::new(&mut app.world);
+        let mut spawns: Vec<(ActiveObjectType, Vec2)> = spawns
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| (event.object_type(), event.transform().translation.to_flat()))
+            .collect();
+        spawns.sort_by(|(_, a), (_, b)| a.x.total_cmp(&b.x));
+
+        // The buildings are centered at (20, 10).
+        let center = point.to_flat();
+        assert_eq!(
+            spawns,
+            vec![
+                (
+                    ActiveObjectType::Building(BuildingType::Base),
+                    center + Vec2::new(-10., 10.)
+                ),
+                (
+                    ActiveObjectType::Building(BuildingType::PowerHub),
+                    center + Vec2::new(0., -20.)
+                ),
+                (
+                    ActiveObjectType::Building(BuildingType::PowerHub),
+                    center + Vec2::new(10., 10.)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spawn_upgrade() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::new(
+            "/some/path",
+            false,
+            LocalPlayers::from_single(Player::Player1),
+        ))
+        .add_event::<SpawnLocalActiveEvent>()
+        .add_event::<DespawnActiveLocalEvent>()
+        .add_systems(Update, spawn);
+
+        let transform = Transform::from_xyz(1., 2., 3.).with_rotation(Quat::from_rotation_y(0.7));
+        let old = app.world.spawn((StaticSolid, transform)).id();
+        let draft = app
+            .world
+            .spawn(UpgradeDraftBundle::new(
+                BuildingType::PowerHub,
+                transform,
+                old,
+            ))
+            .insert(DraftAllowed::new(true))
+            .id();
+
+        app.update();
+
+        assert!(app.world.get_entity(draft).is_none());
+
+        let mut despawns = SystemState::<EventReader<DespawnActiveLocalEvent>>::new(&mut app.world);
+        let despawns: Vec<Entity> = despawns
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| event.entity())
+            .collect();
+        assert_eq!(despawns, vec![old]);
+
+        let mut spawns = SystemState::<EventReader<SpawnLocalActiveEvent>>::new(&mut app.world);
+        let mut spawns = spawns.get_mut(&mut app.world);
+        let spawns: Vec<&SpawnLocalActiveEvent> = spawns.iter().collect();
+        assert_eq!(spawns.len(), 1);
+        assert_eq!(
+            spawns[0].object_type(),
+            ActiveObjectType::Building(BuildingType::PowerHub)
+        );
+        assert_eq!(spawns[0].transform(), transform);
+        assert_eq!(spawns[0].player(), Player::Player1);
     }
 }
@@ -4,7 +4,7 @@ use bevy::{
     ecs::system::SystemParam,
     prelude::*,
     render::{
-        camera::Projection,
+        camera::{CameraProjection, Projection},
         primitives::{Frustum, HalfSpace},
     },
 };
@@ -64,4 +64,30 @@ impl<'w, 's> ScreenFrustum<'w, 's> {
 
         Frustum { half_spaces }
     }
+
+    /// Projects points from world space to screen space. Returned screen
+    /// coordinates follow the convention of [`ScreenRect`]. None is returned
+    /// for points behind the camera.
+    ///
+    /// # Panics
+    ///
+    /// If there is not exactly one `Camera3d` in the world with `Transform`
+    /// and `Projection` components.
+    pub(crate) fn project_points<'a>(
+        &self,
+        points: impl Iterator<Item = Vec3> + 'a,
+    ) -> impl Iterator<Item = Option<Vec2>> + 'a {
+        let (transform, projection) = self.camera.single();
+        let view_projection =
+            projection.get_projection_matrix() * transform.compute_matrix().inverse();
+
+        points.map(move |point| {
+            let clip = view_projection * point.extend(1.);
+            if clip.w <= 0. {
+                None
+            } else {
+                Some(clip.truncate().truncate() / clip.w)
+            }
+        })
+    }
 }
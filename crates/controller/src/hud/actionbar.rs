@@ -9,7 +9,7 @@ use de_objects::SolidObjects;
 use de_types::objects::UnitType;
 
 use super::{interaction::InteractionBlocker, HUD_COLOR};
-use crate::selection::Selected;
+use crate::selection::PrimarySelected;
 
 pub(crate) struct ActionBarPlugin;
 
@@ -80,8 +80,8 @@ fn setup(mut commands: Commands) {
     commands.init_resource::<ActiveEntity>();
 }
 
-fn detect_update(mut active: ResMut<ActiveEntity>, selected: Query<Entity, With<Selected>>) {
-    let new = selected.get_single().ok();
+fn detect_update(mut active: ResMut<ActiveEntity>, primary: Query<Entity, With<PrimarySelected>>) {
+    let new = primary.get_single().ok();
     if active.0 != new {
         active.0 = new;
     }
@@ -1,10 +1,9 @@
 use bevy::prelude::*;
 use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState};
-use de_energy::Battery;
 use de_gui::{BodyTextCommands, BodyTextOps, GuiCommands, OuterStyle};
 
 use super::{interaction::InteractionBlocker, HUD_COLOR};
-use crate::selection::Selected;
+use crate::selection::CurrentSelection;
 
 const PREFIXES: [&str; 5] = ["", "k", "M", "G", "T"];
 
@@ -78,24 +77,19 @@ fn most_significant(value: f64) -> f64 {
     }
 }
 
-fn update(
-    ui: Res<DetailsText>,
-    selected: Query<Entity, With<Selected>>,
-    battery: Query<&Battery>,
-    mut text_ops: BodyTextOps,
-) {
+fn update(ui: Res<DetailsText>, selection: CurrentSelection, mut text_ops: BodyTextOps) {
     let mut battery_total = 0.;
     let mut battery_max = 0.;
     let mut selected_count = 0;
 
-    for entity in selected.iter() {
-        if let Ok(battery) = battery.get(entity) {
+    selection.for_each(|_, _, battery, _| {
+        if let Some(battery) = battery {
             selected_count += 1;
 
             battery_total += battery.energy();
             battery_max += battery.capacity();
         }
-    }
+    });
 
     if battery_max == 0. {
         text_ops
@@ -41,26 +41,26 @@ impl Plugin for FillPlugin {
 }
 
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
-enum FillSet {
+pub(super) enum FillSet {
     Clear,
     DrawEntities,
 }
 
 #[derive(SystemParam)]
-struct UiCoords<'w> {
+pub(super) struct UiCoords<'w> {
     bounds: Res<'w, MapBounds>,
 }
 
 impl<'w> UiCoords<'w> {
     /// Transforms 2D flat position (in meters from origin) to relative UI
     /// position (from 0 to 1 from top-right corner).
-    fn flat_to_rel(&self, point: Vec2) -> Vec2 {
+    pub(super) fn flat_to_rel(&self, point: Vec2) -> Vec2 {
         Vec2::new(point.x - self.bounds.min().x, self.bounds.max().y - point.y) / self.bounds.size()
     }
 
     /// Transforms 2D flat position (in meters from origin) to relative UI
     /// position (from 0 to 1 from top-right corner).
-    fn size_to_rel(&self, size: Vec2) -> Vec2 {
+    pub(super) fn size_to_rel(&self, size: Vec2) -> Vec2 {
         size / self.bounds.size()
     }
 }
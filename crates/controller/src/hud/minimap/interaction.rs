@@ -9,6 +9,7 @@ use bevy::{
     window::PrimaryWindow,
 };
 use de_camera::MoveFocusEvent;
+use de_construction::RallyTarget;
 use de_core::{gamestate::GameState, schedule::InputSchedule};
 use de_map::size::MapBounds;
 
@@ -212,6 +213,8 @@ fn delivery_location_system(
         if press.button() != MouseButton::Right {
             continue;
         }
-        location_events.send(DeliveryLocationSelectedEvent::new(press.position()));
+        location_events.send(DeliveryLocationSelectedEvent::new(RallyTarget::Point(
+            press.position(),
+        )));
     }
 }
@@ -1,16 +1,19 @@
 use bevy::prelude::*;
 
-use self::{fill::FillPlugin, interaction::InteractionPlugin, nodes::NodesPlugin};
+use self::{
+    fill::FillPlugin, interaction::InteractionPlugin, nodes::NodesPlugin, ping::PingPlugin,
+};
 
 mod draw;
 mod fill;
 mod interaction;
 mod nodes;
+mod ping;
 
 pub(crate) struct MinimapPlugin;
 
 impl Plugin for MinimapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((NodesPlugin, FillPlugin, InteractionPlugin));
+        app.add_plugins((NodesPlugin, FillPlugin, InteractionPlugin, PingPlugin));
     }
 }
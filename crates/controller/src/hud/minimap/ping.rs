@@ -0,0 +1,221 @@
+//! This module implements command confirmation pings. A ping is shown on the
+//! minimap (and as a marker in the world) whenever units are sent, spread out
+//! or queued to move to a point which is not visible on the screen.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, render::primitives::Sphere};
+use de_core::{
+    gamestate::GameState,
+    schedule::{InputSchedule, PostMovement},
+    screengeom::ScreenRect,
+    state::AppState,
+};
+use de_types::projection::ToAltitude;
+
+use super::{
+    draw::DrawingParam,
+    fill::{FillSet, UiCoords},
+};
+use crate::{
+    commands::{CommandsSet, QueueSelectedEvent, SendSelectedEvent, SpreadSelectedEvent},
+    frustum::ScreenFrustum,
+};
+
+const PING_LIFETIME: Duration = Duration::from_millis(1500);
+const PING_COLOR: Color = Color::rgb(1., 0.9, 0.1);
+/// Size of the minimap ping (relative to the minimap) at the moment of its
+/// creation. The ping shrinks to zero during its lifetime.
+const MINIMAP_PING_SIZE: Vec2 = Vec2::splat(0.06);
+/// Radius (in meters) of the world marker at the moment of its creation.
+const MARKER_RADIUS: f32 = 4.;
+/// The marker is drawn slightly above the terrain to avoid z-fighting.
+const MARKER_ELEVATION: f32 = 0.1;
+
+pub(super) struct PingPlugin;
+
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                InputSchedule,
+                create_pings
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(
+                        on_event::<SendSelectedEvent>()
+                            .or_else(on_event::<SpreadSelectedEvent>())
+                            .or_else(on_event::<QueueSelectedEvent>()),
+                    )
+                    .after(CommandsSet::SendSelected)
+                    .after(CommandsSet::Queue),
+            )
+            .add_systems(
+                PostMovement,
+                (
+                    expire_pings,
+                    draw_minimap_pings
+                        .after(FillSet::DrawEntities)
+                        .after(expire_pings),
+                    draw_markers.after(expire_pings),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Pings(Vec<Ping>);
+
+impl Pings {
+    fn push(&mut self, ping: Ping) {
+        self.0.push(ping);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Ping> {
+        self.0.iter()
+    }
+
+    /// Advances all pings by `delta` and removes the expired ones.
+    fn tick(&mut self, delta: Duration) {
+        self.0.retain_mut(|ping| {
+            ping.timer.tick(delta);
+            !ping.timer.finished()
+        });
+    }
+}
+
+struct Ping {
+    /// Target of the command in map coordinates.
+    target: Vec2,
+    /// Target of the command in relative minimap coordinates.
+    minimap: Vec2,
+    timer: Timer,
+}
+
+impl Ping {
+    fn new(target: Vec2, minimap: Vec2) -> Self {
+        Self {
+            target,
+            minimap,
+            timer: Timer::new(PING_LIFETIME, TimerMode::Once),
+        }
+    }
+
+    /// Returns the fraction (from 1 to 0) of the remaining ping lifetime.
+    fn remaining(&self) -> f32 {
+        self.timer.percent_left()
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Pings>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Pings>();
+}
+
+fn create_pings(
+    mut pings: ResMut<Pings>,
+    screen_frustum: ScreenFrustum,
+    ui_coords: UiCoords,
+    mut send_events: EventReader<SendSelectedEvent>,
+    mut spread_events: EventReader<SpreadSelectedEvent>,
+    mut queue_events: EventReader<QueueSelectedEvent>,
+) {
+    let frustum = screen_frustum.rect(ScreenRect::full());
+    let targets = send_events
+        .iter()
+        .map(|event| event.target())
+        .chain(spread_events.iter().map(|event| event.target()))
+        .chain(queue_events.iter().map(|event| event.target()));
+    for target in targets {
+        let sphere = Sphere {
+            center: target.to_msl().into(),
+            radius: 0.,
+        };
+        if !frustum.intersects_sphere(&sphere, true) {
+            pings.push(Ping::new(target, ui_coords.flat_to_rel(target)));
+        }
+    }
+}
+
+fn expire_pings(time: Res<Time>, mut pings: ResMut<Pings>) {
+    pings.tick(time.delta());
+}
+
+fn draw_minimap_pings(pings: Res<Pings>, mut drawing: DrawingParam) {
+    let mut drawing = drawing.drawing();
+    for ping in pings.iter() {
+        let size = ping.remaining() * MINIMAP_PING_SIZE;
+        if size.cmpgt(Vec2::ZERO).all() {
+            drawing.rect(ping.minimap.clamp(Vec2::ZERO, Vec2::ONE), size, PING_COLOR);
+        }
+    }
+}
+
+fn draw_markers(pings: Res<Pings>, mut gizmos: Gizmos) {
+    for ping in pings.iter() {
+        gizmos.circle(
+            ping.target.to_altitude(MARKER_ELEVATION),
+            Vec3::Y,
+            ping.remaining() * MARKER_RADIUS,
+            PING_COLOR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::camera::Projection;
+    use de_map::size::MapBounds;
+
+    use super::*;
+
+    #[test]
+    fn test_ping() {
+        let mut app = App::new();
+        app.init_resource::<Pings>()
+            .insert_resource(MapBounds::new(Vec2::new(1000., 500.)))
+            .add_event::<SendSelectedEvent>()
+            .add_event::<SpreadSelectedEvent>()
+            .add_event::<QueueSelectedEvent>()
+            .add_systems(Update, create_pings);
+        app.world.spawn((
+            Camera3d::default(),
+            Transform::from_xyz(0., 50., 0.).looking_at(Vec3::ZERO, -Vec3::Z),
+            Projection::Perspective(PerspectiveProjection::default()),
+        ));
+
+        app.world
+            .send_event(SendSelectedEvent::new(Vec2::new(1., 1.)));
+        app.update();
+        assert_eq!(app.world.resource::<Pings>().0.len(), 0);
+
+        app.world
+            .send_event(SendSelectedEvent::new(Vec2::new(250., -125.)));
+        app.update();
+        let mut pings = app.world.resource_mut::<Pings>();
+        assert_eq!(pings.0.len(), 1);
+        assert_eq!(pings.0[0].target, Vec2::new(250., -125.));
+        assert_eq!(pings.0[0].minimap, Vec2::new(0.75, 0.75));
+
+        pings.tick(PING_LIFETIME / 2);
+        assert_eq!(pings.0.len(), 1);
+        assert_eq!(pings.0[0].remaining(), 0.5);
+        pings.tick(PING_LIFETIME / 2);
+        assert!(pings.0.is_empty());
+
+        // Spread and queued moves are confirmed as well.
+        app.world
+            .send_event(SpreadSelectedEvent::new(Vec2::new(-250., 125.)));
+        app.world
+            .send_event(QueueSelectedEvent::new(Vec2::new(250., 125.)));
+        app.update();
+        let pings = app.world.resource::<Pings>();
+        assert_eq!(pings.0.len(), 2);
+        assert_eq!(pings.0[0].minimap, Vec2::new(0.25, 0.25));
+        assert_eq!(pings.0[1].minimap, Vec2::new(0.75, 0.25));
+    }
+}
@@ -5,15 +5,16 @@ mod details;
 mod interaction;
 mod menu;
 mod minimap;
+mod queue;
 mod selection;
 
 pub(crate) use interaction::HudNodes;
 pub(crate) use menu::{GameMenuSet, ToggleGameMenuEvent};
-pub(crate) use selection::UpdateSelectionBoxEvent;
+pub(crate) use selection::{UpdateSelectionBoxEvent, UpdateSelectionLassoEvent};
 
 use self::{
     actionbar::ActionBarPlugin, details::DetailsPlugin, menu::MenuPlugin, minimap::MinimapPlugin,
-    selection::SelectionPlugin,
+    queue::QueuePanelPlugin, selection::SelectionPlugin,
 };
 
 const HUD_COLOR: Color = Color::BLACK;
@@ -28,6 +29,7 @@ impl Plugin for HudPlugin {
             ActionBarPlugin,
             MenuPlugin,
             MinimapPlugin,
+            QueuePanelPlugin,
         ));
     }
 }
@@ -0,0 +1,241 @@
+//! This module implements a HUD panel listing orders queued to the primary
+//! selected unit or units enqueued in the assembly line of the primary
+//! selected factory. Clicking an enqueued unit cancels its manufacturing.
+
+use bevy::prelude::*;
+use de_behaviour::{CommandQueue, Order};
+use de_construction::{AssemblyLine, CancelAssemblyEvent};
+use de_core::{
+    cleanup::DespawnOnGameExit, gamestate::GameState, objects::Playable, schedule::InputSchedule,
+};
+use de_gui::{BodyTextCommands, GuiCommands, OuterStyle};
+use de_types::objects::UnitType;
+
+use super::{interaction::InteractionBlocker, HUD_COLOR};
+use crate::selection::PrimarySelected;
+
+pub(crate) struct QueuePanelPlugin;
+
+impl Plugin for QueuePanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), setup)
+            .add_systems(OnExit(GameState::Playing), cleanup)
+            .add_systems(
+                PostUpdate,
+                (update_items, label_items.after(update_items))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                InputSchedule,
+                cancel_assembly.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct QueuePanel {
+    node: Entity,
+    /// Entries currently displayed in the panel.
+    shown: Vec<QueueEntry>,
+}
+
+impl QueuePanel {
+    fn new(node: Entity) -> Self {
+        Self {
+            node,
+            shown: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum QueueEntry {
+    Order(Order),
+    Unit(UnitType),
+}
+
+/// A panel node displaying a single queued order or enqueued unit.
+#[derive(Component)]
+struct QueueItem {
+    /// Position of the entry in the queue.
+    index: usize,
+    entry: QueueEntry,
+}
+
+fn setup(mut commands: Commands) {
+    let node = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(60.),
+                    height: Val::Percent(4.),
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(20.),
+                    right: Val::Percent(80.),
+                    top: Val::Percent(81.),
+                    bottom: Val::Percent(85.),
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                background_color: HUD_COLOR.into(),
+                ..default()
+            },
+            DespawnOnGameExit,
+            InteractionBlocker,
+        ))
+        .id();
+    commands.insert_resource(QueuePanel::new(node));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<QueuePanel>();
+}
+
+/// Re-creates the panel items whenever the command queue or the assembly
+/// line of the primary selected object (or the primary object itself)
+/// changes.
+fn update_items(
+    mut commands: Commands,
+    mut panel: ResMut<QueuePanel>,
+    queues: Query<(Option<&CommandQueue>, Option<&AssemblyLine>), With<PrimarySelected>>,
+) {
+    let mut entries = Vec::new();
+    if let Ok((queue, line)) = queues.get_single() {
+        if let Some(queue) = queue {
+            entries.extend(queue.orders().map(QueueEntry::Order));
+        }
+        if let Some(line) = line {
+            entries.extend(line.queue().map(QueueEntry::Unit));
+        }
+    }
+    if panel.shown == entries {
+        return;
+    }
+
+    commands.entity(panel.node).despawn_descendants();
+    for (index, &entry) in entries.iter().enumerate() {
+        let style = Style {
+            height: Val::Percent(100.),
+            margin: UiRect::horizontal(Val::Percent(0.5)),
+            ..default()
+        };
+        let mut item = match entry {
+            QueueEntry::Order(_) => commands.spawn(NodeBundle { style, ..default() }),
+            // Enqueued units are clickable so that they can be cancelled.
+            QueueEntry::Unit(_) => commands.spawn(ButtonBundle {
+                style,
+                background_color: Color::NONE.into(),
+                ..default()
+            }),
+        };
+        let item = item.insert(QueueItem { index, entry }).id();
+        commands.entity(panel.node).add_child(item);
+    }
+    panel.shown = entries;
+}
+
+fn label_items(mut commands: GuiCommands, items: Query<(Entity, &QueueItem), Added<QueueItem>>) {
+    for (entity, item) in items.iter() {
+        let caption = match item.entry {
+            QueueEntry::Order(Order::Move(_)) => "Move".to_owned(),
+            QueueEntry::Unit(unit) => unit.to_string(),
+        };
+        let label = commands
+            .spawn_body_text(
+                OuterStyle::default(),
+                format!("{}. {}", item.index + 1, caption),
+            )
+            .id();
+        commands.entity(entity).add_child(label);
+    }
+}
+
+type PrimaryFactory = (With<PrimarySelected>, With<AssemblyLine>, With<Playable>);
+
+fn cancel_assembly(
+    factories: Query<Entity, PrimaryFactory>,
+    items: Query<(&Interaction, &QueueItem), Changed<Interaction>>,
+    mut events: EventWriter<CancelAssemblyEvent>,
+) {
+    let Ok(factory) = factories.get_single() else {
+        return;
+    };
+
+    for (&interaction, item) in items.iter() {
+        if let (Interaction::Pressed, QueueEntry::Unit(_)) = (interaction, item.entry) {
+            events.send(CancelAssemblyEvent::new(factory, item.index));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_queue_items() {
+        let mut app = App::new();
+        app.add_systems(Update, update_items);
+
+        let node = app.world.spawn(NodeBundle::default()).id();
+        app.world.insert_resource(QueuePanel::new(node));
+
+        let targets = [Vec2::new(1., 2.), Vec2::new(-3., 4.), Vec2::new(5., -6.)];
+        let queue: CommandQueue = targets.iter().map(|&target| Order::Move(target)).collect();
+        app.world.spawn((queue, PrimarySelected));
+        app.world
+            .spawn(CommandQueue::from_iter([Order::Move(Vec2::ZERO)]));
+        app.update();
+
+        let children = app.world.get::<Children>(node).unwrap();
+        let items: Vec<(usize, QueueEntry)> = children
+            .iter()
+            .map(|&child| {
+                let item = app.world.get::<QueueItem>(child).unwrap();
+                (item.index, item.entry)
+            })
+            .collect();
+        assert_eq!(
+            items,
+            targets
+                .iter()
+                .enumerate()
+                .map(|(index, &target)| (index, QueueEntry::Order(Order::Move(target))))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_cancel_assembly() {
+        let mut app = App::new();
+        app.add_event::<CancelAssemblyEvent>()
+            .add_systems(Update, cancel_assembly);
+
+        let factory = app
+            .world
+            .spawn((PrimarySelected, Playable, AssemblyLine::default()))
+            .id();
+        app.world.spawn((
+            Interaction::Pressed,
+            QueueItem {
+                index: 1,
+                entry: QueueEntry::Unit(UnitType::Attacker),
+            },
+        ));
+        app.world.spawn((
+            Interaction::Hovered,
+            QueueItem {
+                index: 2,
+                entry: QueueEntry::Unit(UnitType::Attacker),
+            },
+        ));
+        app.update();
+
+        let mut state = SystemState::<EventReader<CancelAssemblyEvent>>::new(&mut app.world);
+        let mut events = state.get_mut(&mut app.world);
+        let events: Vec<&CancelAssemblyEvent> = events.iter().collect();
+        assert_eq!(events, vec![&CancelAssemblyEvent::new(factory, 1)]);
+    }
+}
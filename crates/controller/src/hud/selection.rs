@@ -2,15 +2,26 @@ use bevy::prelude::*;
 use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState, screengeom::ScreenRect};
 
 const SELECTION_BOX_COLOR: Color = Color::rgba(0., 0.5, 0.8, 0.2);
+const SELECTION_LASSO_COLOR: Color = Color::rgba(0., 0.5, 0.8, 0.8);
+/// Distance (in NDC) between neighboring dots of the lasso outline.
+const LASSO_DOT_SPACING: f32 = 0.005;
+/// Size (in pixels) of a lasso outline dot.
+const LASSO_DOT_SIZE: f32 = 2.;
 
 pub(crate) struct SelectionPlugin;
 
 impl Plugin for SelectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<UpdateSelectionBoxEvent>().add_systems(
-            PostUpdate,
-            process_events.run_if(in_state(GameState::Playing)),
-        );
+        app.add_event::<UpdateSelectionBoxEvent>()
+            .add_event::<UpdateSelectionLassoEvent>()
+            .add_systems(
+                PostUpdate,
+                (
+                    process_events,
+                    process_lasso_events.run_if(on_event::<UpdateSelectionLassoEvent>()),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -27,9 +38,28 @@ impl UpdateSelectionBoxEvent {
     }
 }
 
+/// Send this event to update the outline of a freehand selection. Vertices
+/// are in NDC and they are expected to be only appended during a single
+/// selection.
+#[derive(Event)]
+pub struct UpdateSelectionLassoEvent(Vec<Vec2>);
+
+impl UpdateSelectionLassoEvent {
+    pub fn none() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn from_vertices(vertices: Vec<Vec2>) -> Self {
+        Self(vertices)
+    }
+}
+
 #[derive(Component)]
 struct SelectionBox;
 
+#[derive(Component)]
+struct LassoDot;
+
 fn process_events(
     mut commands: Commands,
     mut boxes: Query<(Entity, &mut Style), With<SelectionBox>>,
@@ -81,3 +111,52 @@ fn process_events(
         }
     }
 }
+
+/// Draws the lasso outline as a dotted line. Only dots of newly appended
+/// vertices are spawned.
+fn process_lasso_events(
+    mut commands: Commands,
+    dots: Query<Entity, With<LassoDot>>,
+    mut drawn: Local<usize>,
+    mut events: EventReader<UpdateSelectionLassoEvent>,
+) {
+    let Some(event) = events.iter().last() else {
+        return;
+    };
+    let vertices = event.0.as_slice();
+
+    // The dots might have been despawned at the end of a game as well.
+    if vertices.len() < *drawn || dots.is_empty() {
+        for entity in dots.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        *drawn = 0;
+    }
+
+    for (i, &vertex) in vertices.iter().enumerate().skip(*drawn) {
+        let start = if i == 0 { vertex } else { vertices[i - 1] };
+        let steps = ((vertex - start).length() / LASSO_DOT_SPACING)
+            .ceil()
+            .max(1.) as usize;
+        for step in 1..=steps {
+            let point = start.lerp(vertex, step as f32 / steps as f32);
+            commands.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(LASSO_DOT_SIZE),
+                        height: Val::Px(LASSO_DOT_SIZE),
+                        left: Val::Percent(50. * (point.x + 1.)),
+                        top: Val::Percent(50. * (1. - point.y)),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(SELECTION_LASSO_COLOR),
+                    ..Default::default()
+                },
+                LassoDot,
+                DespawnOnGameExit,
+            ));
+        }
+    }
+    *drawn = vertices.len();
+}
@@ -2,17 +2,24 @@
 
 use bevy::{app::PluginGroupBuilder, prelude::*};
 use commands::CommandsPlugin;
+pub use commands::{BuildHotbar, CommandDeniedEvent};
 use draft::DraftPlugin;
 use hud::HudPlugin;
 use mouse::MousePlugin;
+use orders::OrdersPlugin;
+use recording::RecordingPlugin;
+pub use recording::{InputRecorder, Recording};
 use selection::SelectionPlugin;
+pub use selection::{PersistSelection, SelectionDiff, SelectionMode};
 
 mod commands;
 mod draft;
 mod frustum;
 mod hud;
 mod mouse;
+mod orders;
 mod ray;
+mod recording;
 mod selection;
 
 const SELECTION_BAR_ID: u32 = 0;
@@ -28,5 +35,7 @@ impl PluginGroup for ControllerPluginGroup {
             .add(SelectionPlugin)
             .add(DraftPlugin)
             .add(HudPlugin)
+            .add(RecordingPlugin)
+            .add(OrdersPlugin)
     }
 }
@@ -0,0 +1,211 @@
+//! This module implements recognition of simple mouse gestures drawn with the
+//! right mouse button pressed.
+//!
+//! Right button drags are also used for lasso selection. The lasso handler
+//! ignores drags recognized as gestures, so that a gesture does not change
+//! the selection its command acts on.
+
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, schedule::InputSchedule};
+
+use super::{MousePosition, MouseSet};
+
+const GESTURE_BUTTON: MouseButton = MouseButton::Right;
+/// Maximum duration (in seconds) of a flick.
+const FLICK_MAX_DURATION: f64 = 0.3;
+/// Minimum distance (in normalized device coordinates) between the start and
+/// the end of a flick.
+const FLICK_MIN_DISTANCE: f32 = 0.15;
+/// Minimum ratio of the distance between the start and the end of a gesture
+/// and the length of the drawn path. Less straight paths are not recognized.
+const MIN_STRAIGHTNESS: f32 = 0.8;
+
+pub(super) struct GesturePlugin;
+
+impl Plugin for GesturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MouseGestureEvent>().add_systems(
+            InputSchedule,
+            update_gestures
+                .run_if(in_state(GameState::Playing))
+                .in_set(MouseSet::Gestures)
+                .after(MouseSet::Position),
+        );
+    }
+}
+
+/// This event is sent when a mouse gesture is recognized.
+#[derive(Event)]
+pub(crate) struct MouseGestureEvent(Gesture);
+
+impl MouseGestureEvent {
+    pub(crate) fn gesture(&self) -> Gesture {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Gesture {
+    /// A short and fast straight stroke.
+    Flick(GestureDirection),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum GestureDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl GestureDirection {
+    /// Returns direction of the dominant axis of a vector in normalized
+    /// device coordinates.
+    fn from_vector(vector: Vec2) -> Self {
+        if vector.x.abs() >= vector.y.abs() {
+            if vector.x >= 0. {
+                Self::Right
+            } else {
+                Self::Left
+            }
+        } else if vector.y >= 0. {
+            Self::Up
+        } else {
+            Self::Down
+        }
+    }
+}
+
+/// Cursor path drawn while the gesture button is pressed.
+#[derive(Default)]
+struct CursorPath(Vec<(f64, Vec2)>);
+
+impl CursorPath {
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Appends a cursor position (in normalized device coordinates) sampled
+    /// at `time` (in seconds).
+    fn push(&mut self, time: f64, position: Vec2) {
+        if self.0.last().map(|&(_, last)| last) != Some(position) {
+            self.0.push((time, position));
+        }
+    }
+
+    /// Returns the gesture matching the path or None if the path does not
+    /// match any gesture.
+    fn classify(&self) -> Option<Gesture> {
+        let (&(start_time, start), &(stop_time, stop)) = (self.0.first()?, self.0.last()?);
+
+        let displacement = stop - start;
+        let distance = displacement.length();
+        let length: f32 = self
+            .0
+            .windows(2)
+            .map(|pair| pair[0].1.distance(pair[1].1))
+            .sum();
+        if distance < FLICK_MIN_DISTANCE || distance < MIN_STRAIGHTNESS * length {
+            return None;
+        }
+
+        if stop_time - start_time <= FLICK_MAX_DURATION {
+            Some(Gesture::Flick(GestureDirection::from_vector(displacement)))
+        } else {
+            None
+        }
+    }
+}
+
+fn update_gestures(
+    time: Res<Time>,
+    buttons: Res<Input<MouseButton>>,
+    mouse: Res<MousePosition>,
+    mut path: Local<CursorPath>,
+    mut events: EventWriter<MouseGestureEvent>,
+) {
+    if buttons.just_pressed(GESTURE_BUTTON) {
+        path.clear();
+    }
+
+    if buttons.pressed(GESTURE_BUTTON) || buttons.just_released(GESTURE_BUTTON) {
+        if let Some(position) = mouse.ndc() {
+            path.push(time.elapsed_seconds_f64(), position);
+        }
+    }
+
+    if buttons.just_released(GESTURE_BUTTON) {
+        if let Some(gesture) = path.classify() {
+            events.send(MouseGestureEvent(gesture));
+        }
+        path.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_flick_right() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<MousePosition>()
+            .add_event::<MouseGestureEvent>()
+            .add_systems(Update, update_gestures);
+
+        // A recorded cursor path: (milliseconds since the start, position
+        // normalized to [0, 1]).
+        let recording = [
+            (0, Vec2::new(0.40, 0.50)),
+            (16, Vec2::new(0.43, 0.51)),
+            (33, Vec2::new(0.48, 0.51)),
+            (50, Vec2::new(0.55, 0.52)),
+            (66, Vec2::new(0.61, 0.52)),
+            (83, Vec2::new(0.64, 0.51)),
+        ];
+
+        let start = Instant::now();
+        let frame = |app: &mut App, millis: u64, position: Vec2, pressed: bool| {
+            app.world
+                .resource_mut::<Time>()
+                .update_with_instant(start + Duration::from_millis(millis));
+            app.world
+                .resource_mut::<MousePosition>()
+                .set_position(Some(position));
+            let mut buttons = app.world.resource_mut::<Input<MouseButton>>();
+            buttons.clear();
+            if pressed {
+                buttons.press(GESTURE_BUTTON);
+            } else {
+                buttons.release(GESTURE_BUTTON);
+            }
+            app.update();
+        };
+
+        for &(millis, position) in recording.iter() {
+            frame(&mut app, millis, position, true);
+        }
+        frame(&mut app, 100, Vec2::new(0.65, 0.51), false);
+
+        let mut state = SystemState::<EventReader<MouseGestureEvent>>::new(&mut app.world);
+        let gestures: Vec<Gesture> = state
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| event.gesture())
+            .collect();
+        assert_eq!(gestures, vec![Gesture::Flick(GestureDirection::Right)]);
+
+        // Slow drags are not flicks.
+        for &(millis, position) in recording.iter() {
+            frame(&mut app, 1000 + 10 * millis, position, true);
+        }
+        frame(&mut app, 2000, Vec2::new(0.65, 0.51), false);
+        assert_eq!(state.get_mut(&mut app.world).iter().count(), 0);
+    }
+}
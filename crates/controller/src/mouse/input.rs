@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use ahash::AHashMap;
 use bevy::{
     input::{mouse::MouseButtonInput, ButtonState},
@@ -12,6 +14,7 @@ use crate::hud::HudNodes;
 
 const DRAGGING_THRESHOLD: f32 = 0.02;
 const DOUBLE_CLICK_TIME: f64 = 0.5;
+const HOLD_MS: u64 = 500;
 
 pub(super) struct InputPlugin;
 
@@ -20,6 +23,7 @@ impl Plugin for InputPlugin {
         app.add_event::<MouseClickedEvent>()
             .add_event::<MouseDoubleClickedEvent>()
             .add_event::<MouseDraggedEvent>()
+            .add_event::<MouseHeldEvent>()
             .add_systems(OnEnter(AppState::InGame), setup)
             .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
@@ -33,6 +37,9 @@ impl Plugin for InputPlugin {
                     update_buttons
                         .in_set(MouseSet::SingeButton)
                         .after(MouseSet::Drags),
+                    check_holds
+                        .in_set(MouseSet::Buttons)
+                        .after(MouseSet::SingeButton),
                     check_double_click
                         .in_set(MouseSet::Buttons)
                         .after(MouseSet::SingeButton),
@@ -48,6 +55,7 @@ pub(crate) enum MouseSet {
     Drags,
     SingeButton,
     Buttons,
+    Gestures,
 }
 
 #[derive(Event)]
@@ -70,6 +78,29 @@ impl MouseClickedEvent {
     }
 }
 
+/// This event is sent when a mouse button is held down for at least
+/// [`MouseThresholds::hold`] without moving the mouse. Release of a held
+/// button does not result in a click, but moving the mouse afterwards still
+/// starts a drag.
+///
+/// No action is bound to a hold at the moment.
+#[derive(Event)]
+pub(crate) struct MouseHeldEvent {
+    #[allow(dead_code)]
+    button: MouseButton,
+}
+
+impl MouseHeldEvent {
+    fn new(button: MouseButton) -> Self {
+        Self { button }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn button(&self) -> MouseButton {
+        self.button
+    }
+}
+
 #[derive(Event)]
 pub(crate) struct MouseDoubleClickedEvent {
     button: MouseButton,
@@ -138,17 +169,52 @@ impl MousePosition {
         self.0
     }
 
-    fn set_position(&mut self, position: Option<Vec2>) {
+    pub(super) fn set_position(&mut self, position: Option<Vec2>) {
         self.0 = position;
     }
 }
 
+/// Thresholds used to disambiguate clicks, drags and holds. Insert the
+/// resource before the game starts to override the defaults.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct MouseThresholds {
+    drag: f32,
+    hold: Duration,
+}
+
+impl MouseThresholds {
+    /// # Arguments
+    ///
+    /// * `drag` - minimum mouse movement (in normalized device coordinates)
+    ///   for a press to be treated as a drag.
+    ///
+    /// * `hold` - minimum duration of a stationary press for it to be treated
+    ///   as a hold.
+    pub(crate) fn new(drag: f32, hold: Duration) -> Self {
+        Self { drag, hold }
+    }
+
+    pub(crate) fn drag(&self) -> f32 {
+        self.drag
+    }
+
+    pub(crate) fn hold(&self) -> Duration {
+        self.hold
+    }
+}
+
+impl Default for MouseThresholds {
+    fn default() -> Self {
+        Self::new(DRAGGING_THRESHOLD, Duration::from_millis(HOLD_MS))
+    }
+}
+
 #[derive(Default, Resource)]
 struct MouseDragStates(AHashMap<MouseButton, DragState>);
 
 impl MouseDragStates {
-    fn set(&mut self, button: MouseButton, position: Option<Vec2>) {
-        self.0.insert(button, DragState::new(position));
+    fn set(&mut self, button: MouseButton, position: Option<Vec2>, time: Duration) {
+        self.0.insert(button, DragState::new(position, time));
     }
 
     fn resolve(&mut self, button: MouseButton) -> Option<DragResolution> {
@@ -160,33 +226,55 @@ impl MouseDragStates {
     ///
     /// None means that the drag is (temporarily) canceled, Some means that the
     /// drag has been updated to this new rectangle.
-    fn update(&mut self, position: Option<Vec2>) -> AHashMap<MouseButton, Option<ScreenRect>> {
+    fn update(
+        &mut self,
+        position: Option<Vec2>,
+        threshold: f32,
+    ) -> AHashMap<MouseButton, Option<ScreenRect>> {
         let mut updates = AHashMap::new();
         for (&button, drag) in self.0.iter_mut() {
-            if let Some(update) = drag.update(position) {
+            if let Some(update) = drag.update(position, threshold) {
                 updates.insert(button, update);
             }
         }
         updates
     }
+
+    /// Marks all stationary presses longer than `hold` as held. Buttons of
+    /// newly held presses are returned.
+    fn hold(&mut self, time: Duration, hold: Duration) -> Vec<MouseButton> {
+        self.0
+            .iter_mut()
+            .filter_map(|(&button, drag)| drag.hold(time, hold).then_some(button))
+            .collect()
+    }
 }
 
 struct DragState {
     start: Option<Vec2>,
     stop: Option<Vec2>,
+    /// Time (since the start of the app) of the button press.
+    pressed_at: Duration,
     active: bool,
+    held: bool,
 }
 
 impl DragState {
-    fn new(start: Option<Vec2>) -> Self {
+    fn new(start: Option<Vec2>, pressed_at: Duration) -> Self {
         Self {
             start,
             stop: start,
+            pressed_at,
             active: false,
+            held: false,
         }
     }
 
     fn resolve(self) -> Option<DragResolution> {
+        if self.held && !self.active {
+            return None;
+        }
+
         match self.start {
             Some(start) => match (self.active, self.stop) {
                 (true, Some(stop)) => Some(DragResolution::Rect(Some(ScreenRect::from_points(
@@ -200,14 +288,29 @@ impl DragState {
         }
     }
 
-    fn update(&mut self, position: Option<Vec2>) -> Option<Option<ScreenRect>> {
+    /// Marks the press as held and returns true if it has been stationary
+    /// (over the 3D world) for at least `hold`.
+    fn hold(&mut self, time: Duration, hold: Duration) -> bool {
+        if self.start.is_none()
+            || self.active
+            || self.held
+            || time.saturating_sub(self.pressed_at) < hold
+        {
+            return false;
+        }
+
+        self.held = true;
+        true
+    }
+
+    fn update(&mut self, position: Option<Vec2>, threshold: f32) -> Option<Option<ScreenRect>> {
         let changed = self.stop != position;
         self.stop = position;
 
         if let Some(start) = self.start {
             let rect = match self.stop {
                 Some(stop) => {
-                    self.active |= start.distance(stop) >= DRAGGING_THRESHOLD;
+                    self.active |= start.distance(stop) >= threshold;
                     Some(ScreenRect::from_points(start, stop))
                 }
                 None => None,
@@ -230,24 +333,31 @@ enum DragResolution {
 fn setup(mut commands: Commands) {
     commands.init_resource::<MousePosition>();
     commands.init_resource::<MouseDragStates>();
+    commands.init_resource::<MouseThresholds>();
 }
 
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<MousePosition>();
     commands.remove_resource::<MouseDragStates>();
+    // Thresholds are kept so that overrides inserted before the game starts
+    // apply to subsequent games too.
 }
 
+/// Updates mouse position relative to the viewport of the 3D camera. The
+/// position is None when the cursor is outside of the viewport or over a HUD
+/// node, thus no world input is generated there.
 fn update_position(
     window_query: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<&Camera, With<Camera3d>>,
     hud: HudNodes,
     mut mouse: ResMut<MousePosition>,
 ) {
     let window = window_query.single();
+    let viewport = viewport_rect(window, cameras.get_single().ok());
     let position = window
         .cursor_position()
-        .filter(|&position| !hud.contains_point(position))
-        .map(|position| position / Vec2::new(window.width(), window.height()))
-        .map(|normalised_position| normalised_position.clamp(Vec2::ZERO, Vec2::ONE));
+        .filter(|&position| viewport.contains(position) && !hud.contains_point(position))
+        .map(|position| (position - viewport.min) / viewport.size());
 
     // Avoid unnecessary change detection.
     if mouse.position() != position {
@@ -255,18 +365,34 @@ fn update_position(
     }
 }
 
+/// Returns the area (in logical pixels) of the window rendered by the 3D
+/// camera. It is the whole window unless the camera renders to a
+/// sub-viewport.
+fn viewport_rect(window: &Window, camera: Option<&Camera>) -> Rect {
+    match camera.and_then(|camera| camera.viewport.as_ref()) {
+        Some(viewport) => {
+            let scale = window.scale_factor() as f32;
+            let min = viewport.physical_position.as_vec2() / scale;
+            Rect::from_corners(min, min + viewport.physical_size.as_vec2() / scale)
+        }
+        None => Rect::new(0., 0., window.width(), window.height()),
+    }
+}
+
 fn update_drags(
+    thresholds: Res<MouseThresholds>,
     mouse_position: Res<MousePosition>,
     mut mouse_state: ResMut<MouseDragStates>,
     mut drags: EventWriter<MouseDraggedEvent>,
 ) {
-    let resolutions = mouse_state.update(mouse_position.ndc());
+    let resolutions = mouse_state.update(mouse_position.ndc(), thresholds.drag());
     for (&button, &rect) in resolutions.iter() {
         drags.send(MouseDraggedEvent::new(button, rect, DragUpdateType::Moved));
     }
 }
 
 fn update_buttons(
+    time: Res<Time>,
     mouse_position: Res<MousePosition>,
     mut mouse_state: ResMut<MouseDragStates>,
     mut input_events: EventReader<MouseButtonInput>,
@@ -292,13 +418,25 @@ fn update_buttons(
                 }
             }
             ButtonState::Pressed => {
-                mouse_state.set(event.button, mouse_position.ndc());
+                mouse_state.set(event.button, mouse_position.ndc(), time.elapsed());
             }
         }
     }
 }
 
+fn check_holds(
+    time: Res<Time>,
+    thresholds: Res<MouseThresholds>,
+    mut mouse_state: ResMut<MouseDragStates>,
+    mut holds: EventWriter<MouseHeldEvent>,
+) {
+    for button in mouse_state.hold(time.elapsed(), thresholds.hold()) {
+        holds.send(MouseHeldEvent::new(button));
+    }
+}
+
 fn check_double_click(
+    thresholds: Res<MouseThresholds>,
     mut clicks: EventReader<MouseClickedEvent>,
     mut double_clicks: EventWriter<MouseDoubleClickedEvent>,
     mut last_click_position: Local<Option<Vec2>>,
@@ -309,7 +447,7 @@ fn check_double_click(
         let current_time = time.elapsed_seconds_f64();
 
         if last_click_position.map_or(true, |p| {
-            p.distance(mouse_clicked.position()) < DRAGGING_THRESHOLD
+            p.distance(mouse_clicked.position()) < thresholds.drag()
         }) {
             // Check if double click using timer
             if (current_time - *last_click_time) < DOUBLE_CLICK_TIME {
@@ -321,3 +459,155 @@ fn check_double_click(
         *last_click_position = Some(mouse_clicked.position());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::{ecs::system::SystemState, render::camera::Viewport};
+
+    use super::*;
+
+    #[test]
+    fn test_hold() {
+        let mut app = App::new();
+        app.init_resource::<MouseDragStates>()
+            .init_resource::<MouseThresholds>()
+            .insert_resource(MousePosition(Some(Vec2::new(0.5, 0.5))))
+            .init_resource::<Time>()
+            .add_event::<MouseButtonInput>()
+            .add_event::<MouseClickedEvent>()
+            .add_event::<MouseDraggedEvent>()
+            .add_event::<MouseHeldEvent>()
+            .add_systems(
+                Update,
+                (
+                    update_drags,
+                    update_buttons.after(update_drags),
+                    check_holds.after(update_buttons),
+                ),
+            );
+
+        let start = Instant::now();
+        let step = |app: &mut App, millis: u64, state: Option<ButtonState>| {
+            if let Some(state) = state {
+                app.world.send_event(MouseButtonInput {
+                    button: MouseButton::Left,
+                    state,
+                    window: Entity::PLACEHOLDER,
+                });
+            }
+            app.world
+                .resource_mut::<Time>()
+                .update_with_instant(start + Duration::from_millis(millis));
+            app.update();
+        };
+        let mut state = SystemState::<(
+            EventReader<MouseClickedEvent>,
+            EventReader<MouseHeldEvent>,
+            EventReader<MouseDraggedEvent>,
+        )>::new(&mut app.world);
+        let mut counts = |app: &mut App| {
+            let (mut clicks, mut holds, mut drags) = state.get_mut(&mut app.world);
+            (
+                clicks.iter().count(),
+                holds.iter().count(),
+                drags.iter().count(),
+            )
+        };
+
+        // A quick press is a click.
+        step(&mut app, 0, Some(ButtonState::Pressed));
+        step(&mut app, 100, None);
+        assert_eq!(counts(&mut app), (0, 0, 0));
+        step(&mut app, 150, Some(ButtonState::Released));
+        assert_eq!(counts(&mut app), (1, 0, 0));
+
+        // A long stationary press is a hold and not a click.
+        step(&mut app, 1000, Some(ButtonState::Pressed));
+        step(&mut app, 1300, None);
+        assert_eq!(counts(&mut app), (0, 0, 0));
+        step(&mut app, 1500, None);
+        assert_eq!(counts(&mut app), (0, 1, 0));
+        step(&mut app, 1800, None);
+        assert_eq!(counts(&mut app), (0, 0, 0));
+        step(&mut app, 1900, Some(ButtonState::Released));
+        assert_eq!(counts(&mut app), (0, 0, 0));
+
+        // Moving the mouse after a hold still starts a drag.
+        step(&mut app, 3000, Some(ButtonState::Pressed));
+        step(&mut app, 3600, None);
+        assert_eq!(counts(&mut app), (0, 1, 0));
+        app.world.resource_mut::<MousePosition>().0 = Some(Vec2::new(0.8, 0.5));
+        step(&mut app, 3700, None);
+        assert_eq!(counts(&mut app), (0, 0, 1));
+        step(&mut app, 3800, Some(ButtonState::Released));
+        assert_eq!(counts(&mut app), (0, 0, 1));
+    }
+
+    #[test]
+    fn test_viewport() {
+        let mut app = App::new();
+        app.init_resource::<MouseDragStates>()
+            .init_resource::<MousePosition>()
+            .init_resource::<Time>()
+            .add_event::<MouseButtonInput>()
+            .add_event::<MouseClickedEvent>()
+            .add_event::<MouseDraggedEvent>()
+            .add_systems(
+                Update,
+                (update_position, update_buttons.after(update_position)),
+            );
+
+        let window = app
+            .world
+            .spawn((
+                Window {
+                    resolution: (800., 600.).into(),
+                    ..default()
+                },
+                PrimaryWindow,
+            ))
+            .id();
+        app.world.spawn(Camera3dBundle {
+            camera: Camera {
+                viewport: Some(Viewport {
+                    physical_position: UVec2::new(200, 0),
+                    physical_size: UVec2::new(600, 600),
+                    ..default()
+                }),
+                ..default()
+            },
+            ..default()
+        });
+
+        let mut clicks = SystemState::<EventReader<MouseClickedEvent>>::new(&mut app.world);
+        let mut click = |app: &mut App, cursor: Vec2| {
+            app.world
+                .get_mut::<Window>(window)
+                .unwrap()
+                .set_cursor_position(Some(cursor));
+            for state in [ButtonState::Pressed, ButtonState::Released] {
+                app.update();
+                app.world.send_event(MouseButtonInput {
+                    button: MouseButton::Left,
+                    state,
+                    window,
+                });
+            }
+            app.update();
+            clicks
+                .get_mut(&mut app.world)
+                .iter()
+                .map(|event| event.position())
+                .collect::<Vec<Vec2>>()
+        };
+
+        // Outside of the viewport, e.g. over an editor panel.
+        assert!(click(&mut app, Vec2::new(100., 300.)).is_empty());
+        assert_eq!(app.world.resource::<MousePosition>().ndc(), None);
+
+        let positions = click(&mut app, Vec2::new(500., 150.));
+        assert_eq!(positions, vec![Vec2::new(0., 0.5)]);
+    }
+}
@@ -1,4 +1,6 @@
 use bevy::prelude::*;
+use gesture::GesturePlugin;
+pub(crate) use gesture::{Gesture, GestureDirection, MouseGestureEvent};
 use input::InputPlugin;
 pub(crate) use input::{
     DragUpdateType, MouseClickedEvent, MouseDoubleClickedEvent, MouseDraggedEvent, MousePosition,
@@ -7,6 +9,7 @@ pub(crate) use input::{
 use pointer::PointerPlugin;
 pub(crate) use pointer::{Pointer, PointerSet};
 
+mod gesture;
 mod input;
 mod pointer;
 
@@ -14,6 +17,6 @@ pub(crate) struct MousePlugin;
 
 impl Plugin for MousePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((InputPlugin, PointerPlugin));
+        app.add_plugins((InputPlugin, PointerPlugin, GesturePlugin));
     }
 }
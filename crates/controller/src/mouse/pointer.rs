@@ -4,6 +4,7 @@ use de_index::SpatialQuery;
 use de_signs::UpdateBarVisibilityEvent;
 use de_terrain::TerrainCollider;
 use glam::Vec3;
+use parry3d::query::Ray;
 
 use crate::{
     mouse::{MousePosition, MouseSet},
@@ -11,6 +12,11 @@ use crate::{
     POINTER_BAR_ID,
 };
 
+/// Rays descending by less than this (sine of the angle below the horizon)
+/// are treated as not hitting the terrain. Such rays point to the horizon or
+/// to the sky and would hit the terrain, if at all, very far away.
+const MIN_RAY_DESCENT: f32 = 0.02;
+
 pub(super) struct PointerPlugin;
 
 impl Plugin for PointerPlugin {
@@ -49,12 +55,12 @@ impl Pointer {
 
     /// Pointed to 3D position on the surface of the terrain. This can be below
     /// (occluded) another entity. It is None if the mouse is not over terrain
-    /// at all.
+    /// at all or if the pointer ray is (nearly) parallel to the ground.
     pub(crate) fn terrain_point(&self) -> Option<Vec3> {
         self.terrain
     }
 
-    fn set_entity(&mut self, entity: Option<Entity>) {
+    pub(crate) fn set_entity(&mut self, entity: Option<Entity>) {
         self.entity = entity;
     }
 
@@ -90,9 +96,7 @@ fn pointer_update_system(
         resource.set_entity(entity);
     }
 
-    let terrain_point = ray
-        .and_then(|ray| terrain.cast_ray(&ray, f32::INFINITY))
-        .map(|intersection| ray.unwrap().point_at(intersection.toi).into());
+    let terrain_point = ray.and_then(|ray| terrain_hit(&ray, &terrain));
 
     // Do not unnecessarily trigger change detection.
     if resource.terrain_point() != terrain_point {
@@ -100,6 +104,19 @@ fn pointer_update_system(
     }
 }
 
+/// Returns the intersection of a pointer ray with the terrain. None is
+/// returned if the ray does not descend steeply enough, see
+/// [`MIN_RAY_DESCENT`].
+fn terrain_hit(ray: &Ray, terrain: &TerrainCollider) -> Option<Vec3> {
+    if ray.dir.y > -MIN_RAY_DESCENT * ray.dir.norm() {
+        return None;
+    }
+
+    terrain
+        .cast_ray(ray, f32::INFINITY)
+        .map(|intersection| ray.point_at(intersection.toi).into())
+}
+
 fn update_bar_visibility(
     pointer: Res<Pointer>,
     mut previous: Local<Option<Entity>>,
@@ -118,3 +135,35 @@ fn update_bar_visibility(
 
     *previous = pointer.entity();
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_map::size::MapBounds;
+    use de_terrain::TerrainBundle;
+
+    use super::*;
+
+    #[test]
+    fn test_terrain_hit() {
+        let mut world = World::new();
+        world.spawn(TerrainBundle::flat(MapBounds::new(Vec2::new(2000., 2000.))));
+        let mut state = SystemState::<TerrainCollider>::new(&mut world);
+        let terrain = state.get(&world);
+
+        let origin = Vec3::new(0., 10., 0.);
+        let ray = |direction: Vec3| Ray::new(origin.into(), direction.into());
+
+        let point = terrain_hit(&ray(Vec3::new(1., -1., -1.)), &terrain).unwrap();
+        assert!(point.distance(Vec3::new(10., 0., -10.)) < 1e-4);
+
+        // Facing the horizon or the sky.
+        assert!(terrain_hit(&ray(Vec3::new(1., 0., -1.)), &terrain).is_none());
+        assert!(terrain_hit(&ray(Vec3::new(1., 0.2, -1.)), &terrain).is_none());
+
+        // Grazing ray which hits the terrain almost 1 km away.
+        let grazing = ray(Vec3::new(1., -0.015, -1.));
+        assert!(terrain.cast_ray(&grazing, f32::INFINITY).is_some());
+        assert!(terrain_hit(&grazing, &terrain).is_none());
+    }
+}
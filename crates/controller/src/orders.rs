@@ -0,0 +1,115 @@
+//! This module implements visualization of command queues of selected units.
+//! Paths connecting queued orders are drawn along the terrain surface.
+
+use bevy::prelude::*;
+use de_behaviour::{CommandQueue, Order};
+use de_core::{gamestate::GameState, schedule::PostMovement};
+use de_terrain::TerrainCollider;
+use de_types::projection::{ToAltitude, ToFlat};
+use parry3d::query::Ray;
+
+use crate::selection::Selected;
+
+const PATH_COLOR: Color = Color::rgb(0.2, 0.9, 0.3);
+/// Maximum distance (in meters) between two consecutive terrain samples along
+/// a path segment.
+const SAMPLE_DISTANCE: f32 = 2.;
+/// The path is drawn slightly above the terrain to avoid z-fighting.
+const PATH_ELEVATION: f32 = 0.2;
+
+pub(crate) struct OrdersPlugin;
+
+impl Plugin for OrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostMovement,
+            draw_queues.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Returns a polyline which connects the given points on the map and follows
+/// the terrain surface.
+///
+/// # Arguments
+///
+/// * `points` - points to be connected in map coordinates.
+///
+/// * `height` - returns terrain height at a point on the map.
+fn terrain_polyline(points: &[Vec2], height: impl Fn(Vec2) -> f32) -> Vec<Vec3> {
+    let to_surface = |point: Vec2| point.to_altitude(height(point) + PATH_ELEVATION);
+
+    let mut polyline = Vec::new();
+    if let Some(&first) = points.first() {
+        polyline.push(to_surface(first));
+    }
+
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let samples = (start.distance(end) / SAMPLE_DISTANCE).ceil().max(1.) as usize;
+        for i in 1..=samples {
+            let point = start.lerp(end, i as f32 / samples as f32);
+            polyline.push(to_surface(point));
+        }
+    }
+
+    polyline
+}
+
+fn draw_queues(
+    queues: Query<(&Transform, &CommandQueue), With<Selected>>,
+    terrain: TerrainCollider,
+    mut gizmos: Gizmos,
+) {
+    let height = |point: Vec2| {
+        let ray = Ray::new(point.to_msl().into(), Vec3::Y.into());
+        terrain
+            .cast_ray_bidir_msl(&ray, f32::INFINITY)
+            .map_or(0., |intersection| intersection.toi)
+    };
+
+    for (transform, queue) in queues.iter() {
+        if queue.is_empty() {
+            continue;
+        }
+
+        let points: Vec<Vec2> = std::iter::once(transform.translation.to_flat())
+            .chain(queue.orders().map(|order| match order {
+                Order::Move(target) => target,
+            }))
+            .collect();
+        gizmos.linestrip(terrain_polyline(&points, height), PATH_COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terrain_polyline() {
+        let height = |point: Vec2| 3. * (0.5 * point.x).sin() + 0.1 * point.y;
+        let points = [
+            Vec2::new(0., 0.),
+            Vec2::new(9., 0.),
+            Vec2::new(9., -3.),
+            Vec2::new(9., -3.),
+        ];
+
+        let polyline = terrain_polyline(&points, height);
+        // 5 samples of the first segment, 2 samples of the second segment and
+        // a single sample of the last (zero length) segment.
+        assert_eq!(polyline.len(), 9);
+        assert_eq!(polyline[0].to_flat(), points[0]);
+        assert_eq!(polyline[5].to_flat(), points[1]);
+        assert_eq!(polyline[8].to_flat(), points[3]);
+
+        for vertex in polyline.iter() {
+            let expected = height(vertex.to_flat()) + PATH_ELEVATION;
+            assert!((vertex.y - expected).abs() < 1e-5);
+        }
+        for pair in polyline.windows(2) {
+            assert!(pair[0].to_flat().distance(pair[1].to_flat()) <= SAMPLE_DISTANCE + 1e-5);
+        }
+    }
+}
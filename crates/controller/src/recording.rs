@@ -0,0 +1,490 @@
+//! This module implements recording and replaying of controller input events
+//! (selection, commands and draft placements). It is meant for debugging and
+//! deterministic testing.
+//!
+//! The events are recorded together with the number of the frame (counted
+//! since the start of the recording) during which they were sent. During a
+//! replay, the events are re-sent on the same frames (counted since the start
+//! of the replay).
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_behaviour::GoSignalEvent;
+use de_core::{gamestate::GameState, schedule::InputSchedule, state::AppState};
+
+use crate::{
+    commands::{
+        CommandsSet, DeliveryLocationSelectedEvent, FallBackSelectedEvent, FollowSelectedEvent,
+        GiveSelectedEvent, GroupAttackEvent, GuardSelectedEvent, QueueSelectedEvent,
+        ScoutSelectedEvent, SendSelectedEvent, SpreadSelectedEvent, ToggleRunSelectedEvent,
+        ToggleStandGroundSelectedEvent, UndoSelectedEvent,
+    },
+    draft::{
+        DiscardDraftsEvent, DraftSet, NewBlueprintDraftEvent, NewDraftEvent, SpawnDraftsEvent,
+        UpgradeDraftEvent,
+    },
+    selection::{SelectEvent, SelectionSet},
+};
+
+pub(crate) struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                InputSchedule,
+                replay
+                    .run_if(in_state(GameState::Playing))
+                    .before(SelectionSet::Update)
+                    .before(CommandsSet::SendSelected)
+                    .before(CommandsSet::DeliveryLocation)
+                    .before(CommandsSet::Attack)
+                    .before(CommandsSet::Give)
+                    .before(CommandsSet::Guard)
+                    .before(CommandsSet::Follow)
+                    .before(CommandsSet::Scout)
+                    .before(CommandsSet::Queue)
+                    .before(CommandsSet::Speed)
+                    .before(CommandsSet::Stance)
+                    .before(DraftSet::Spawn)
+                    .before(DraftSet::New)
+                    .before(DraftSet::Discard),
+            )
+            // Events are recorded at the end of the frame so that all events
+            // sent during the frame are included.
+            .add_systems(Last, record.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Recorder and player of controller input events.
+#[derive(Resource, Default)]
+pub struct InputRecorder {
+    recording: Option<Recording>,
+    replay: Option<Replay>,
+}
+
+impl InputRecorder {
+    /// Starts a new recording. Any unfinished recording is discarded.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+
+    /// Stops recording and returns the recorded events. None is returned if
+    /// there is no recording in progress.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Starts replaying of previously recorded events. Any unfinished replay
+    /// is interrupted.
+    pub fn replay(&mut self, recording: Recording) {
+        self.replay = Some(Replay::new(recording));
+    }
+
+    /// Returns true if a replay is in progress.
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+}
+
+/// Controller input events recorded with a frame timestamp.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Recording {
+    /// Number of frames recorded so far.
+    frames: u32,
+    /// Recorded events ordered by frame numbers.
+    events: Vec<(u32, RecordedEvent)>,
+}
+
+impl Recording {
+    fn push(&mut self, event: RecordedEvent) {
+        self.events.push((self.frames, event));
+    }
+
+    fn finish_frame(&mut self) {
+        self.frames += 1;
+    }
+}
+
+struct Replay {
+    recording: Recording,
+    frame: u32,
+    /// Index of the next to be sent event.
+    next: usize,
+}
+
+impl Replay {
+    fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            next: 0,
+        }
+    }
+
+    /// Returns events recorded for the current frame and advances to the
+    /// next frame.
+    fn next_frame(&mut self) -> &[(u32, RecordedEvent)] {
+        let start = self.next;
+        let events = &self.recording.events[start..];
+        let count = events
+            .iter()
+            .take_while(|(frame, _)| *frame == self.frame)
+            .count();
+
+        self.next += count;
+        self.frame += 1;
+        &self.recording.events[start..start + count]
+    }
+
+    /// Returns true if all recorded frames were replayed.
+    fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum RecordedEvent {
+    Select(SelectEvent),
+    SendSelected(SendSelectedEvent),
+    SpreadSelected(SpreadSelectedEvent),
+    DeliveryLocation(DeliveryLocationSelectedEvent),
+    GroupAttack(GroupAttackEvent),
+    GiveSelected(GiveSelectedEvent),
+    GuardSelected(GuardSelectedEvent),
+    QueueSelected(QueueSelectedEvent),
+    UndoSelected(UndoSelectedEvent),
+    FallBackSelected(FallBackSelectedEvent),
+    ScoutSelected(ScoutSelectedEvent),
+    FollowSelected(FollowSelectedEvent),
+    ToggleRunSelected(ToggleRunSelectedEvent),
+    ToggleStandGroundSelected(ToggleStandGroundSelectedEvent),
+    GoSignal(GoSignalEvent),
+    NewDraft(NewDraftEvent),
+    UpgradeDraft(UpgradeDraftEvent),
+    NewBlueprintDraft(NewBlueprintDraftEvent),
+    SpawnDrafts(SpawnDraftsEvent),
+    DiscardDrafts(DiscardDraftsEvent),
+}
+
+#[derive(SystemParam)]
+struct InputEventReaders<'w, 's> {
+    select: EventReader<'w, 's, SelectEvent>,
+    send_selected: EventReader<'w, 's, SendSelectedEvent>,
+    spread_selected: EventReader<'w, 's, SpreadSelectedEvent>,
+    delivery_location: EventReader<'w, 's, DeliveryLocationSelectedEvent>,
+    group_attack: EventReader<'w, 's, GroupAttackEvent>,
+    give_selected: EventReader<'w, 's, GiveSelectedEvent>,
+    guard_selected: EventReader<'w, 's, GuardSelectedEvent>,
+    queue_selected: EventReader<'w, 's, QueueSelectedEvent>,
+    undo_selected: EventReader<'w, 's, UndoSelectedEvent>,
+    fall_back_selected: EventReader<'w, 's, FallBackSelectedEvent>,
+    scout_selected: EventReader<'w, 's, ScoutSelectedEvent>,
+    follow_selected: EventReader<'w, 's, FollowSelectedEvent>,
+    toggle_run_selected: EventReader<'w, 's, ToggleRunSelectedEvent>,
+    toggle_stand_ground_selected: EventReader<'w, 's, ToggleStandGroundSelectedEvent>,
+    go_signal: EventReader<'w, 's, GoSignalEvent>,
+    new_draft: EventReader<'w, 's, NewDraftEvent>,
+    upgrade_draft: EventReader<'w, 's, UpgradeDraftEvent>,
+    new_blueprint_draft: EventReader<'w, 's, NewBlueprintDraftEvent>,
+    spawn_drafts: EventReader<'w, 's, SpawnDraftsEvent>,
+    discard_drafts: EventReader<'w, 's, DiscardDraftsEvent>,
+}
+
+impl<'w, 's> InputEventReaders<'w, 's> {
+    /// Returns all events sent since the last call.
+    fn read(&mut self) -> Vec<RecordedEvent> {
+        let mut events = Vec::new();
+        events.extend(self.select.iter().cloned().map(RecordedEvent::Select));
+        events.extend(
+            self.send_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::SendSelected),
+        );
+        events.extend(
+            self.spread_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::SpreadSelected),
+        );
+        events.extend(
+            self.delivery_location
+                .iter()
+                .cloned()
+                .map(RecordedEvent::DeliveryLocation),
+        );
+        events.extend(
+            self.group_attack
+                .iter()
+                .cloned()
+                .map(RecordedEvent::GroupAttack),
+        );
+        events.extend(
+            self.give_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::GiveSelected),
+        );
+        events.extend(
+            self.guard_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::GuardSelected),
+        );
+        events.extend(
+            self.queue_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::QueueSelected),
+        );
+        events.extend(
+            self.undo_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::UndoSelected),
+        );
+        events.extend(
+            self.fall_back_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::FallBackSelected),
+        );
+        events.extend(
+            self.scout_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::ScoutSelected),
+        );
+        events.extend(
+            self.follow_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::FollowSelected),
+        );
+        events.extend(
+            self.toggle_run_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::ToggleRunSelected),
+        );
+        events.extend(
+            self.toggle_stand_ground_selected
+                .iter()
+                .cloned()
+                .map(RecordedEvent::ToggleStandGroundSelected),
+        );
+        events.extend(self.go_signal.iter().cloned().map(RecordedEvent::GoSignal));
+        events.extend(self.new_draft.iter().cloned().map(RecordedEvent::NewDraft));
+        events.extend(
+            self.upgrade_draft
+                .iter()
+                .cloned()
+                .map(RecordedEvent::UpgradeDraft),
+        );
+        events.extend(
+            self.new_blueprint_draft
+                .iter()
+                .cloned()
+                .map(RecordedEvent::NewBlueprintDraft),
+        );
+        events.extend(
+            self.spawn_drafts
+                .iter()
+                .cloned()
+                .map(RecordedEvent::SpawnDrafts),
+        );
+        events.extend(
+            self.discard_drafts
+                .iter()
+                .cloned()
+                .map(RecordedEvent::DiscardDrafts),
+        );
+        events
+    }
+}
+
+#[derive(SystemParam)]
+struct InputEventWriters<'w> {
+    select: EventWriter<'w, SelectEvent>,
+    send_selected: EventWriter<'w, SendSelectedEvent>,
+    spread_selected: EventWriter<'w, SpreadSelectedEvent>,
+    delivery_location: EventWriter<'w, DeliveryLocationSelectedEvent>,
+    group_attack: EventWriter<'w, GroupAttackEvent>,
+    give_selected: EventWriter<'w, GiveSelectedEvent>,
+    guard_selected: EventWriter<'w, GuardSelectedEvent>,
+    queue_selected: EventWriter<'w, QueueSelectedEvent>,
+    undo_selected: EventWriter<'w, UndoSelectedEvent>,
+    fall_back_selected: EventWriter<'w, FallBackSelectedEvent>,
+    scout_selected: EventWriter<'w, ScoutSelectedEvent>,
+    follow_selected: EventWriter<'w, FollowSelectedEvent>,
+    toggle_run_selected: EventWriter<'w, ToggleRunSelectedEvent>,
+    toggle_stand_ground_selected: EventWriter<'w, ToggleStandGroundSelectedEvent>,
+    go_signal: EventWriter<'w, GoSignalEvent>,
+    new_draft: EventWriter<'w, NewDraftEvent>,
+    upgrade_draft: EventWriter<'w, UpgradeDraftEvent>,
+    new_blueprint_draft: EventWriter<'w, NewBlueprintDraftEvent>,
+    spawn_drafts: EventWriter<'w, SpawnDraftsEvent>,
+    discard_drafts: EventWriter<'w, DiscardDraftsEvent>,
+}
+
+impl<'w> InputEventWriters<'w> {
+    fn send(&mut self, event: RecordedEvent) {
+        match event {
+            RecordedEvent::Select(event) => self.select.send(event),
+            RecordedEvent::SendSelected(event) => self.send_selected.send(event),
+            RecordedEvent::SpreadSelected(event) => self.spread_selected.send(event),
+            RecordedEvent::DeliveryLocation(event) => self.delivery_location.send(event),
+            RecordedEvent::GroupAttack(event) => self.group_attack.send(event),
+            RecordedEvent::GiveSelected(event) => self.give_selected.send(event),
+            RecordedEvent::GuardSelected(event) => self.guard_selected.send(event),
+            RecordedEvent::QueueSelected(event) => self.queue_selected.send(event),
+            RecordedEvent::UndoSelected(event) => self.undo_selected.send(event),
+            RecordedEvent::FallBackSelected(event) => self.fall_back_selected.send(event),
+            RecordedEvent::ScoutSelected(event) => self.scout_selected.send(event),
+            RecordedEvent::FollowSelected(event) => self.follow_selected.send(event),
+            RecordedEvent::ToggleRunSelected(event) => self.toggle_run_selected.send(event),
+            RecordedEvent::ToggleStandGroundSelected(event) => {
+                self.toggle_stand_ground_selected.send(event)
+            }
+            RecordedEvent::GoSignal(event) => self.go_signal.send(event),
+            RecordedEvent::NewDraft(event) => self.new_draft.send(event),
+            RecordedEvent::UpgradeDraft(event) => self.upgrade_draft.send(event),
+            RecordedEvent::NewBlueprintDraft(event) => self.new_blueprint_draft.send(event),
+            RecordedEvent::SpawnDrafts(event) => self.spawn_drafts.send(event),
+            RecordedEvent::DiscardDrafts(event) => self.discard_drafts.send(event),
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<InputRecorder>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<InputRecorder>();
+}
+
+fn replay(mut recorder: ResMut<InputRecorder>, mut writers: InputEventWriters) {
+    let Some(replay) = recorder.replay.as_mut() else {
+        return;
+    };
+
+    for (_, event) in replay.next_frame() {
+        writers.send(event.clone());
+    }
+
+    if replay.is_finished() {
+        recorder.replay = None;
+    }
+}
+
+fn record(mut recorder: ResMut<InputRecorder>, mut readers: InputEventReaders) {
+    // Events are read even when not recording so that stale events are not
+    // included in a later recording.
+    let events = readers.read();
+
+    let Some(recording) = recorder.recording.as_mut() else {
+        return;
+    };
+    for event in events {
+        recording.push(event);
+    }
+    recording.finish_frame();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use glam::Vec2;
+
+    use super::*;
+    use crate::selection::SelectionMode;
+
+    #[test]
+    fn test_record_replay() {
+        let mut app = App::new();
+        app.init_resource::<InputRecorder>()
+            .add_event::<SelectEvent>()
+            .add_event::<SendSelectedEvent>()
+            .add_event::<SpreadSelectedEvent>()
+            .add_event::<DeliveryLocationSelectedEvent>()
+            .add_event::<GroupAttackEvent>()
+            .add_event::<GiveSelectedEvent>()
+            .add_event::<GuardSelectedEvent>()
+            .add_event::<QueueSelectedEvent>()
+            .add_event::<UndoSelectedEvent>()
+            .add_event::<FallBackSelectedEvent>()
+            .add_event::<ScoutSelectedEvent>()
+            .add_event::<FollowSelectedEvent>()
+            .add_event::<ToggleRunSelectedEvent>()
+            .add_event::<ToggleStandGroundSelectedEvent>()
+            .add_event::<GoSignalEvent>()
+            .add_event::<NewDraftEvent>()
+            .add_event::<UpgradeDraftEvent>()
+            .add_event::<NewBlueprintDraftEvent>()
+            .add_event::<SpawnDraftsEvent>()
+            .add_event::<DiscardDraftsEvent>()
+            .add_systems(Update, replay)
+            .add_systems(Last, record);
+
+        let entity = app.world.spawn_empty().id();
+        let select = SelectEvent::single(entity, SelectionMode::Replace);
+        let send = SendSelectedEvent::new(Vec2::new(10., -20.));
+
+        app.world.resource_mut::<InputRecorder>().start_recording();
+        app.world.send_event(select.clone());
+        app.update();
+        app.update();
+        app.world.send_event(send.clone());
+        app.update();
+        let recording = app
+            .world
+            .resource_mut::<InputRecorder>()
+            .stop_recording()
+            .unwrap();
+        assert_eq!(
+            recording.events,
+            vec![
+                (0, RecordedEvent::Select(select.clone())),
+                (2, RecordedEvent::SendSelected(send.clone())),
+            ]
+        );
+
+        let mut select_state = SystemState::<EventReader<SelectEvent>>::new(&mut app.world);
+        let mut send_state = SystemState::<EventReader<SendSelectedEvent>>::new(&mut app.world);
+        let mut select_events = |world: &mut World| -> Vec<SelectEvent> {
+            select_state.get_mut(world).iter().cloned().collect()
+        };
+        let mut send_events = |world: &mut World| -> Vec<SendSelectedEvent> {
+            send_state.get_mut(world).iter().cloned().collect()
+        };
+        select_events(&mut app.world);
+        send_events(&mut app.world);
+
+        {
+            let mut recorder = app.world.resource_mut::<InputRecorder>();
+            recorder.replay(recording.clone());
+            recorder.start_recording();
+        }
+
+        app.update();
+        assert_eq!(select_events(&mut app.world), vec![select]);
+        assert!(send_events(&mut app.world).is_empty());
+        app.update();
+        assert!(select_events(&mut app.world).is_empty());
+        assert!(send_events(&mut app.world).is_empty());
+        assert!(app.world.resource::<InputRecorder>().is_replaying());
+        app.update();
+        assert!(select_events(&mut app.world).is_empty());
+        assert_eq!(send_events(&mut app.world), vec![send]);
+        assert!(!app.world.resource::<InputRecorder>().is_replaying());
+
+        let replayed = app
+            .world
+            .resource_mut::<InputRecorder>()
+            .stop_recording()
+            .unwrap();
+        assert_eq!(replayed, recording);
+    }
+}
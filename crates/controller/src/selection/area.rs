@@ -4,7 +4,7 @@ use de_core::{
     gamestate::GameState,
     objects::{ObjectTypeComponent, Playable},
     schedule::InputSchedule,
-    screengeom::ScreenRect,
+    screengeom::{ScreenPolygon, ScreenRect},
 };
 use de_objects::SolidObjects;
 use de_types::objects::ObjectType;
@@ -18,13 +18,16 @@ pub(super) struct AreaPlugin;
 
 impl Plugin for AreaPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<SelectInRectEvent>().add_systems(
-            InputSchedule,
-            select_in_area
-                .run_if(in_state(GameState::Playing))
-                .in_set(AreaSelectSet::SelectInArea)
-                .before(SelectionSet::Update),
-        );
+        app.add_event::<SelectInRectEvent>()
+            .add_event::<SelectInPolygonEvent>()
+            .add_event::<SelectSameTypeEvent>()
+            .add_systems(
+                InputSchedule,
+                (select_in_area, select_in_polygon, select_same_type)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AreaSelectSet::SelectInArea)
+                    .before(SelectionSet::Update),
+            );
     }
 }
 
@@ -66,6 +69,50 @@ impl SelectInRectEvent {
     }
 }
 
+/// Send this event to select all playable entities whose projected screen
+/// position lies inside a polygon (e.g. a lasso drawn by the player).
+#[derive(Event)]
+pub(crate) struct SelectInPolygonEvent {
+    polygon: ScreenPolygon,
+    mode: SelectionMode,
+}
+
+impl SelectInPolygonEvent {
+    pub(crate) fn new(polygon: ScreenPolygon, mode: SelectionMode) -> Self {
+        Self { polygon, mode }
+    }
+
+    fn polygon(&self) -> &ScreenPolygon {
+        &self.polygon
+    }
+
+    fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+}
+
+/// Send this event to select all playable entities of an object type on the
+/// whole map, regardless of their visibility on the screen.
+#[derive(Event)]
+pub(crate) struct SelectSameTypeEvent {
+    object_type: ObjectType,
+    mode: SelectionMode,
+}
+
+impl SelectSameTypeEvent {
+    pub(crate) fn new(object_type: ObjectType, mode: SelectionMode) -> Self {
+        Self { object_type, mode }
+    }
+
+    fn object_type(&self) -> ObjectType {
+        self.object_type
+    }
+
+    fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+}
+
 fn select_in_area(
     screen_frustum: ScreenFrustum,
     solids: SolidObjects,
@@ -94,3 +141,100 @@ fn select_in_area(
         out_events.send(SelectEvent::many(entities, in_event.mode()));
     }
 }
+
+fn select_in_polygon(
+    screen_frustum: ScreenFrustum,
+    candidates: Query<(Entity, &Transform), With<Playable>>,
+    mut in_events: EventReader<SelectInPolygonEvent>,
+    mut out_events: EventWriter<SelectEvent>,
+) {
+    for in_event in in_events.iter() {
+        let projected =
+            screen_frustum.project_points(candidates.iter().map(|(_, t)| t.translation));
+        let entities: Vec<Entity> = candidates
+            .iter()
+            .zip(projected)
+            .filter_map(|((entity, _), position)| {
+                position
+                    .filter(|&position| in_event.polygon().contains(position))
+                    .map(|_| entity)
+            })
+            .collect();
+        // An empty polygon (e.g. a thin sliver) keeps the current selection.
+        if entities.is_empty() {
+            continue;
+        }
+        out_events.send(SelectEvent::many(entities, in_event.mode()));
+    }
+}
+
+fn select_same_type(
+    candidates: Query<(Entity, &ObjectTypeComponent), With<Playable>>,
+    mut in_events: EventReader<SelectSameTypeEvent>,
+    mut out_events: EventWriter<SelectEvent>,
+) {
+    for in_event in in_events.iter() {
+        let entities: Vec<Entity> = candidates
+            .iter()
+            .filter(|(_, &object_type)| *object_type == in_event.object_type())
+            .map(|(entity, _)| entity)
+            .collect();
+        out_events.send(SelectEvent::many(entities, in_event.mode()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_types::objects::{ActiveObjectType, BuildingType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_select_same_type() {
+        let mut app = App::new();
+        app.add_event::<SelectSameTypeEvent>()
+            .add_event::<SelectEvent>()
+            .add_systems(Update, select_same_type);
+
+        let attacker = ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker));
+        let base = ObjectType::Active(ActiveObjectType::Building(BuildingType::Base));
+
+        let mut expected = Vec::new();
+        for x in [-900., -35., 0., 470., 1200.] {
+            expected.push(
+                app.world
+                    .spawn((
+                        Playable,
+                        ObjectTypeComponent::from(attacker),
+                        Transform::from_xyz(x, 0., -x),
+                    ))
+                    .id(),
+            );
+        }
+        app.world.spawn((
+            Playable,
+            ObjectTypeComponent::from(base),
+            Transform::from_xyz(10., 0., 10.),
+        ));
+        // Entities of other players.
+        app.world.spawn((
+            ObjectTypeComponent::from(attacker),
+            Transform::from_xyz(20., 0., 20.),
+        ));
+
+        app.world
+            .send_event(SelectSameTypeEvent::new(attacker, SelectionMode::Replace));
+        app.update();
+
+        let mut state = SystemState::<EventReader<SelectEvent>>::new(&mut app.world);
+        let events: Vec<SelectEvent> = state.get_mut(&mut app.world).iter().cloned().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].mode(), SelectionMode::Replace);
+        // Query iteration order is not guaranteed.
+        let mut selected = events[0].entities().to_vec();
+        selected.sort();
+        expected.sort();
+        assert_eq!(selected, expected);
+    }
+}
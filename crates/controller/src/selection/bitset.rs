@@ -0,0 +1,94 @@
+//! This module implements a set of entities backed by a bitset. It is used to
+//! compute selection changes of large numbers of entities.
+
+use ahash::AHashSet;
+use bevy::prelude::*;
+
+/// Entities with index smaller than this are stored in the bitset. Entities
+/// with higher index are stored in a fallback hash set so that a few sparse
+/// high-index entities do not inflate the bitset.
+const DENSE_LIMIT: u32 = 1 << 20;
+const WORD_BITS: u32 = u64::BITS;
+
+/// Set of alive entities indexed by [`Entity::index`].
+///
+/// Only a single generation of each entity index is expected to be stored,
+/// which holds for all entities alive at the same time.
+#[derive(Default)]
+pub(super) struct EntityBitSet {
+    words: Vec<u64>,
+    /// Entities stored in the bitset in the order of their insertion.
+    dense: Vec<Entity>,
+    sparse: AHashSet<Entity>,
+}
+
+impl EntityBitSet {
+    pub(super) fn contains(&self, entity: Entity) -> bool {
+        let index = entity.index();
+        if index >= DENSE_LIMIT {
+            return self.sparse.contains(&entity);
+        }
+
+        let (word, bit) = Self::position(index);
+        self.words.get(word).is_some_and(|&value| value & bit != 0)
+    }
+
+    pub(super) fn insert(&mut self, entity: Entity) {
+        let index = entity.index();
+        if index >= DENSE_LIMIT {
+            self.sparse.insert(entity);
+            return;
+        }
+
+        let (word, bit) = Self::position(index);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        if self.words[word] & bit == 0 {
+            self.words[word] |= bit;
+            self.dense.push(entity);
+        }
+    }
+
+    /// Iterates over all entities in the set. Entities stored in the bitset
+    /// are yielded in the order of their insertion.
+    pub(super) fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.dense.iter().chain(self.sparse.iter()).cloned()
+    }
+
+    /// Returns a set of entities contained in `self` but not in `other`.
+    pub(super) fn difference(&self, other: &Self) -> Self {
+        self.filtered(|entity| !other.contains(entity))
+    }
+
+    /// Returns a set of entities contained both in `self` and `other`.
+    pub(super) fn intersection(&self, other: &Self) -> Self {
+        self.filtered(|entity| other.contains(entity))
+    }
+
+    fn filtered(&self, predicate: impl Fn(Entity) -> bool) -> Self {
+        let mut result = Self::default();
+        result.extend(self.iter().filter(|&entity| predicate(entity)));
+        result
+    }
+
+    fn position(index: u32) -> (usize, u64) {
+        ((index / WORD_BITS) as usize, 1 << (index % WORD_BITS))
+    }
+}
+
+impl Extend<Entity> for EntityBitSet {
+    fn extend<T: IntoIterator<Item = Entity>>(&mut self, iter: T) {
+        for entity in iter {
+            self.insert(entity);
+        }
+    }
+}
+
+impl FromIterator<Entity> for EntityBitSet {
+    fn from_iter<T: IntoIterator<Item = Entity>>(iter: T) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
@@ -1,9 +1,9 @@
-use ahash::AHashSet;
 use bevy::{ecs::system::SystemParam, prelude::*};
-use de_core::{gamestate::GameState, schedule::InputSchedule};
+use de_core::{gamestate::GameState, objects::Playable, schedule::InputSchedule};
 use de_signs::{UpdateBarVisibilityEvent, UpdateLineVisibilityEvent, UpdatePoleVisibilityEvent};
 use de_terrain::MarkerVisibility;
 
+use super::bitset::EntityBitSet;
 use crate::SELECTION_BAR_ID;
 
 pub(super) struct BookkeepingPlugin;
@@ -13,6 +13,9 @@ impl Plugin for BookkeepingPlugin {
         app.add_event::<SelectEvent>()
             .add_event::<SelectedEvent>()
             .add_event::<DeselectedEvent>()
+            .init_resource::<PersistSelection>()
+            .add_systems(OnExit(GameState::Playing), clear_selection)
+            .add_systems(OnEnter(GameState::Playing), revalidate_selection)
             .add_systems(
                 InputSchedule,
                 (
@@ -29,7 +32,7 @@ pub(crate) enum SelectionSet {
     Update,
 }
 
-#[derive(Event)]
+#[derive(Event, Clone, PartialEq, Debug)]
 pub(crate) struct SelectEvent {
     entities: Vec<Entity>,
     mode: SelectionMode,
@@ -54,11 +57,11 @@ impl SelectEvent {
         Self { entities, mode }
     }
 
-    fn entities(&self) -> &[Entity] {
+    pub(super) fn entities(&self) -> &[Entity] {
         self.entities.as_slice()
     }
 
-    fn mode(&self) -> SelectionMode {
+    pub(super) fn mode(&self) -> SelectionMode {
         self.mode
     }
 }
@@ -72,8 +75,22 @@ struct DeselectedEvent(Entity);
 #[derive(Component)]
 pub(crate) struct Selected;
 
-#[derive(Clone, Copy, PartialEq)]
-pub(crate) enum SelectionMode {
+/// Whether the selection is kept when the game leaves
+/// [`GameState::Playing`] and later returns to it. Entities which are no
+/// longer playable are deselected on the return.
+#[derive(Resource)]
+pub struct PersistSelection(pub bool);
+
+impl Default for PersistSelection {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// How a set of entities updates the current selection.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SelectionMode {
+    /// Selected entities are replaced with to be selected entities.
     Replace,
     /// Selected entities are union of currently selected and to be selected
     /// entities.
@@ -93,12 +110,9 @@ struct SelectorBuilder<'w, 's> {
 
 impl<'w, 's> SelectorBuilder<'w, 's> {
     fn build(self) -> Selector<'w, 's> {
-        let selected: AHashSet<Entity> = self.selected.iter().collect();
         Selector {
             commands: self.commands,
-            selected,
-            to_select: AHashSet::new(),
-            to_deselect: AHashSet::new(),
+            diff: SelectionDiff::new(self.selected.iter()),
             selected_events: self.selected_events,
             deselected_events: self.deselected_events,
         }
@@ -107,46 +121,75 @@ impl<'w, 's> SelectorBuilder<'w, 's> {
 
 struct Selector<'w, 's> {
     commands: Commands<'w, 's>,
-    selected: AHashSet<Entity>,
-    to_select: AHashSet<Entity>,
-    to_deselect: AHashSet<Entity>,
+    diff: SelectionDiff,
     selected_events: EventWriter<'w, SelectedEvent>,
     deselected_events: EventWriter<'w, DeselectedEvent>,
 }
 
 impl<'w, 's> Selector<'w, 's> {
     fn update(&mut self, entities: &[Entity], mode: SelectionMode) {
-        let updated: AHashSet<Entity> = entities.iter().cloned().collect();
-
-        match mode {
-            SelectionMode::Replace => {
-                self.to_select = &updated - &self.selected;
-                self.to_deselect = &self.selected - &updated;
-            }
-            SelectionMode::AddToggle => {
-                self.to_select = &(&updated - &self.to_select) - &self.selected;
-                self.to_deselect = &updated & &self.selected;
-            }
-            SelectionMode::Add => {
-                self.to_select.extend(&updated - &self.selected);
-                self.to_deselect = &self.to_deselect - &updated;
-            }
-        }
+        self.diff.update(entities, mode);
     }
 
     fn execute(mut self) {
-        for entity in self.to_deselect {
+        for entity in self.diff.to_deselect.iter() {
             self.commands.entity(entity).remove::<Selected>();
             self.deselected_events.send(DeselectedEvent(entity));
         }
 
-        for entity in self.to_select {
+        for entity in self.diff.to_select.iter() {
             self.commands.entity(entity).insert(Selected);
             self.selected_events.send(SelectedEvent(entity));
         }
     }
 }
 
+/// Entities to be selected and deselected by a sequence of selection updates.
+// Needs to be public because it is used in a benchmark.
+pub struct SelectionDiff {
+    selected: EntityBitSet,
+    to_select: EntityBitSet,
+    to_deselect: EntityBitSet,
+}
+
+impl SelectionDiff {
+    /// Creates a new empty diff.
+    ///
+    /// # Arguments
+    ///
+    /// * `selected` - currently selected entities.
+    pub fn new(selected: impl IntoIterator<Item = Entity>) -> Self {
+        Self {
+            selected: selected.into_iter().collect(),
+            to_select: EntityBitSet::default(),
+            to_deselect: EntityBitSet::default(),
+        }
+    }
+
+    /// Updates the diff with a set of entities in a given mode.
+    pub fn update(&mut self, entities: &[Entity], mode: SelectionMode) {
+        let updated: EntityBitSet = entities.iter().cloned().collect();
+
+        match mode {
+            SelectionMode::Replace => {
+                self.to_select = updated.difference(&self.selected);
+                self.to_deselect = self.selected.difference(&updated);
+            }
+            SelectionMode::AddToggle => {
+                self.to_select = updated
+                    .difference(&self.to_select)
+                    .difference(&self.selected);
+                self.to_deselect = updated.intersection(&self.selected);
+            }
+            SelectionMode::Add => {
+                self.to_select
+                    .extend(updated.difference(&self.selected).iter());
+                self.to_deselect = self.to_deselect.difference(&updated);
+            }
+        }
+    }
+}
+
 fn update_selection(mut events: EventReader<SelectEvent>, selector_builder: SelectorBuilder) {
     let mut selector = selector_builder.build();
     for event in events.iter() {
@@ -155,6 +198,24 @@ fn update_selection(mut events: EventReader<SelectEvent>, selector_builder: Sele
     selector.execute();
 }
 
+fn clear_selection(persist: Res<PersistSelection>, selector_builder: SelectorBuilder) {
+    if !persist.0 {
+        let mut selector = selector_builder.build();
+        selector.update(&[], SelectionMode::Replace);
+        selector.execute();
+    }
+}
+
+fn revalidate_selection(
+    selector_builder: SelectorBuilder,
+    playable: Query<Entity, (With<Selected>, With<Playable>)>,
+) {
+    let valid: Vec<Entity> = playable.iter().collect();
+    let mut selector = selector_builder.build();
+    selector.update(&valid, SelectionMode::Replace);
+    selector.execute();
+}
+
 fn selected_system(
     mut events: EventReader<SelectedEvent>,
     mut markers: Query<&mut MarkerVisibility>,
@@ -200,3 +261,119 @@ fn deselected_system(
         lines.send(UpdateLineVisibilityEvent::new(event.0, false));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+    use bevy::ecs::schedule::ScheduleLabel;
+
+    use super::*;
+
+    #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Exit;
+
+    #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Enter;
+
+    fn selected(world: &mut World) -> AHashSet<Entity> {
+        world
+            .query_filtered::<Entity, With<Selected>>()
+            .iter(world)
+            .collect()
+    }
+
+    #[test]
+    fn test_persist_selection() {
+        let mut app = App::new();
+        app.init_resource::<PersistSelection>()
+            .add_event::<SelectedEvent>()
+            .add_event::<DeselectedEvent>()
+            .add_systems(Exit, clear_selection)
+            .add_systems(Enter, revalidate_selection);
+
+        let a = app.world.spawn((Playable, Selected)).id();
+        let b = app.world.spawn((Playable, Selected)).id();
+        let c = app.world.spawn((Playable, Selected)).id();
+        let d = app.world.spawn(Playable).id();
+
+        app.world.run_schedule(Exit);
+        app.world.despawn(b);
+        app.world.entity_mut(c).remove::<Playable>();
+        app.world.run_schedule(Enter);
+        assert_eq!(selected(&mut app.world), AHashSet::from_iter([a]));
+        assert!(app.world.get::<Selected>(d).is_none());
+
+        app.world.resource_mut::<PersistSelection>().0 = false;
+        app.world.run_schedule(Exit);
+        app.world.run_schedule(Enter);
+        assert!(selected(&mut app.world).is_empty());
+    }
+
+    /// The original hash set based implementation of [`SelectionDiff::update`].
+    fn hash_set_update(
+        selected: &AHashSet<Entity>,
+        to_select: &mut AHashSet<Entity>,
+        to_deselect: &mut AHashSet<Entity>,
+        entities: &[Entity],
+        mode: SelectionMode,
+    ) {
+        let updated: AHashSet<Entity> = entities.iter().cloned().collect();
+        match mode {
+            SelectionMode::Replace => {
+                *to_select = &updated - selected;
+                *to_deselect = selected - &updated;
+            }
+            SelectionMode::AddToggle => {
+                *to_select = &(&updated - to_select) - selected;
+                *to_deselect = &updated & selected;
+            }
+            SelectionMode::Add => {
+                to_select.extend(&updated - selected);
+                *to_deselect = &*to_deselect - &updated;
+            }
+        }
+    }
+
+    #[test]
+    fn test_bitset_diff() {
+        let entity = |index: u32| Entity::from_raw(index);
+        let selected: Vec<Entity> = [0, 1, 2, 64, 130, u32::MAX - 1]
+            .into_iter()
+            .map(entity)
+            .collect();
+        let updates = [
+            (vec![1, 2, 3, 200, u32::MAX - 2], SelectionMode::Add),
+            (vec![2, 3, 64, 65, u32::MAX - 1], SelectionMode::AddToggle),
+            (vec![0, 3, 5, u32::MAX - 2], SelectionMode::AddToggle),
+            (vec![130, 131, 1 << 21], SelectionMode::Add),
+            (vec![0, 1, 64, 1 << 21], SelectionMode::Replace),
+            (vec![7, 64, u32::MAX - 1], SelectionMode::Add),
+        ];
+
+        let mut diff = SelectionDiff::new(selected.iter().cloned());
+        let expected_selected: AHashSet<Entity> = selected.iter().cloned().collect();
+        let mut expected_to_select = AHashSet::new();
+        let mut expected_to_deselect = AHashSet::new();
+
+        for (indices, mode) in updates {
+            let entities: Vec<Entity> = indices.into_iter().map(entity).collect();
+            diff.update(&entities, mode);
+            hash_set_update(
+                &expected_selected,
+                &mut expected_to_select,
+                &mut expected_to_deselect,
+                &entities,
+                mode,
+            );
+
+            assert_eq!(
+                diff.to_select.iter().collect::<AHashSet<Entity>>(),
+                expected_to_select
+            );
+            assert_eq!(
+                diff.to_deselect.iter().collect::<AHashSet<Entity>>(),
+                expected_to_deselect
+            );
+        }
+    }
+}
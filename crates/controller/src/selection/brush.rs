@@ -0,0 +1,165 @@
+//! This module implements "paint" selection: while a modifier key is held,
+//! all playable entities under a circular brush placed under the mouse cursor
+//! are added to the selection.
+
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use de_core::{gamestate::GameState, objects::Playable, schedule::InputSchedule, state::AppState};
+use de_index::EntityIndex;
+use de_types::projection::ToFlat;
+
+use super::{SelectEvent, SelectionMode, SelectionSet};
+use crate::mouse::{Pointer, PointerSet};
+
+/// Brush selection is active while this key is pressed.
+pub(crate) const BRUSH_KEY: KeyCode = KeyCode::AltLeft;
+const DEFAULT_RADIUS: f32 = 5.;
+const MIN_RADIUS: f32 = 1.;
+const MAX_RADIUS: f32 = 50.;
+/// Brush radius change per mouse wheel line.
+const RADIUS_STEP: f32 = 1.;
+const BRUSH_COLOR: Color = Color::rgba(0.2, 0.8, 0.2, 0.8);
+/// The brush circle is drawn slightly above the terrain to avoid z-fighting.
+const BRUSH_ELEVATION: f32 = 0.1;
+
+pub(super) struct BrushPlugin;
+
+impl Plugin for BrushPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                InputSchedule,
+                (
+                    update_center.after(PointerSet::Update),
+                    update_radius,
+                    paint_select
+                        .after(update_center)
+                        .after(update_radius)
+                        .before(SelectionSet::Update),
+                    draw_brush.after(update_center).after(update_radius),
+                )
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(brush_active),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct Brush {
+    center: Option<Vec3>,
+    radius: f32,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            center: None,
+            radius: DEFAULT_RADIUS,
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Brush>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Brush>();
+}
+
+fn brush_active(keys: Res<Input<KeyCode>>) -> bool {
+    keys.pressed(BRUSH_KEY)
+}
+
+fn update_center(pointer: Res<Pointer>, mut brush: ResMut<Brush>) {
+    let center = pointer.terrain_point();
+    // Do not trigger change detection unnecessarily.
+    if brush.center != center {
+        brush.center = center;
+    }
+}
+
+fn update_radius(mut brush: ResMut<Brush>, mut wheel_events: EventReader<MouseWheel>) {
+    let delta: f32 = wheel_events.iter().map(|event| event.y.signum()).sum();
+    if delta != 0. {
+        brush.radius = (brush.radius + RADIUS_STEP * delta).clamp(MIN_RADIUS, MAX_RADIUS);
+    }
+}
+
+fn paint_select(
+    brush: Res<Brush>,
+    index: Res<EntityIndex>,
+    playable: Query<(), With<Playable>>,
+    mut events: EventWriter<SelectEvent>,
+) {
+    let Some(center) = brush.center else {
+        return;
+    };
+
+    let entities: Vec<Entity> = index
+        .entities_in_circle(center.to_flat(), brush.radius)
+        .into_iter()
+        .filter(|&entity| playable.contains(entity))
+        .collect();
+    if !entities.is_empty() {
+        events.send(SelectEvent::many(entities, SelectionMode::Add));
+    }
+}
+
+fn draw_brush(brush: Res<Brush>, mut gizmos: Gizmos) {
+    if let Some(center) = brush.center {
+        gizmos.circle(
+            center + BRUSH_ELEVATION * Vec3::Y,
+            Vec3::Y,
+            brush.radius,
+            BRUSH_COLOR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_index::LocalCollider;
+    use de_objects::ObjectCollider;
+    use parry3d::{
+        math::{Isometry, Vector},
+        shape::{Cuboid, TriMesh, TriMeshFlags},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_paint_select() {
+        let mut app = App::new();
+        app.init_resource::<Brush>()
+            .insert_resource(EntityIndex::new())
+            .add_event::<SelectEvent>()
+            .add_systems(Update, paint_select);
+
+        let entity = app.world.spawn(Playable).id();
+        let mut trimesh: TriMesh = Cuboid::new(Vector::new(0.5, 0.5, 0.5)).into();
+        trimesh.set_flags(TriMeshFlags::ORIENTED).unwrap();
+        let collider = LocalCollider::new(
+            ObjectCollider::from(trimesh),
+            Isometry::translation(10.5, 0., 0.),
+        );
+        app.world
+            .resource_mut::<EntityIndex>()
+            .insert(entity, collider);
+        app.world.resource_mut::<Brush>().center = Some(Vec3::ZERO);
+
+        let mut state = SystemState::<EventReader<SelectEvent>>::new(&mut app.world);
+
+        app.update();
+        assert_eq!(state.get_mut(&mut app.world).iter().count(), 0);
+
+        app.world.resource_mut::<Brush>().radius = 15.;
+        app.update();
+        let mut events = state.get_mut(&mut app.world);
+        let events: Vec<&SelectEvent> = events.iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entities(), &[entity]);
+        assert!(events[0].mode() == SelectionMode::Add);
+    }
+}
@@ -0,0 +1,85 @@
+//! This module implements a system parameter giving access to selected
+//! entities together with their commonly used components.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_core::objects::ObjectTypeComponent;
+use de_energy::Battery;
+use de_objects::Health;
+use de_types::objects::ObjectType;
+
+use super::Selected;
+
+type SelectedComponents<'a> = (
+    Entity,
+    &'a ObjectTypeComponent,
+    Option<&'a Battery>,
+    &'a Health,
+);
+
+/// System parameter iterating over selected objects together with their
+/// object type, battery and health. Selected entities without health (and
+/// object type) are skipped.
+#[derive(SystemParam)]
+pub(crate) struct CurrentSelection<'w, 's> {
+    selected: Query<'w, 's, SelectedComponents<'static>, With<Selected>>,
+}
+
+impl<'w, 's> CurrentSelection<'w, 's> {
+    /// Calls `f` for each selected object (in no particular order).
+    pub(crate) fn for_each(
+        &self,
+        mut f: impl FnMut(Entity, ObjectType, Option<&Battery>, &Health),
+    ) {
+        for (entity, &object_type, battery, health) in self.selected.iter() {
+            f(entity, *object_type, battery, health);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_objects::InitialHealths;
+    use de_types::objects::{ActiveObjectType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_for_each() {
+        let mut world = World::new();
+
+        let unit_type = ActiveObjectType::Unit(UnitType::Attacker);
+        let full = InitialHealths::default().health(unit_type).clone();
+        let mut damaged = full.clone();
+        damaged.update(-4.);
+        let object_type = ObjectTypeComponent::from(ObjectType::Active(unit_type));
+
+        let first = world
+            .spawn((Selected, object_type, Battery::default(), full.clone()))
+            .id();
+        let second = world.spawn((Selected, object_type, damaged)).id();
+        world.spawn((object_type, Battery::default(), full));
+
+        let mut state = SystemState::<CurrentSelection>::new(&mut world);
+        let selection = state.get(&world);
+        let mut visited = Vec::new();
+        selection.for_each(|entity, object_type, battery, health| {
+            visited.push((
+                entity,
+                object_type,
+                battery.map(|battery| battery.energy()),
+                health.fraction(),
+            ));
+        });
+        visited.sort_by_key(|&(entity, _, _, _)| entity);
+
+        let energy = Battery::default().energy();
+        assert_eq!(
+            visited,
+            vec![
+                (first, ObjectType::Active(unit_type), Some(energy), 1.),
+                (second, ObjectType::Active(unit_type), None, 0.6),
+            ]
+        );
+    }
+}
@@ -0,0 +1,774 @@
+//! This module implements control groups: numbered sets of entities which
+//! might be quickly re-selected.
+//!
+//! Selected entities might be also automatically split to several control
+//! groups by their proximity.
+//!
+//! Entities of a single object type might be selected from the most recently
+//! recalled control group by cycling through the object types present in the
+//! group.
+//!
+//! Members of control groups might be redistributed so that the groups are
+//! of (roughly) equal size.
+//!
+//! Control groups might be flagged to alert the player once all their
+//! members become idle or once any of them is damaged.
+
+use std::time::Duration;
+
+use ahash::AHashSet;
+use bevy::{ecs::query::Has, prelude::*};
+use de_behaviour::CommandQueue;
+use de_combat::RecentlyDamaged;
+use de_core::{
+    gamestate::GameState,
+    objects::{ObjectTypeComponent, Playable},
+    schedule::InputSchedule,
+    state::AppState,
+};
+use de_gui::ToastEvent;
+use de_pathing::PathTarget;
+use de_types::{objects::ObjectType, projection::ToFlat};
+
+use super::{SelectEvent, Selected, SelectionMode, SelectionSet};
+
+/// Number of available control groups.
+pub(crate) const GROUP_COUNT: usize = 10;
+/// Entities closer than this (in meters) are put to the same cluster when
+/// selection is split to control groups.
+const CLUSTER_DISTANCE: f32 = 20.;
+/// A control group is considered under attack while any of its members was
+/// damaged during this time window. The player is alerted only once the group
+/// comes under attack, not on each damage.
+const DAMAGE_ALERT_WINDOW: Duration = Duration::from_secs(10);
+
+pub(super) struct GroupsPlugin;
+
+impl Plugin for GroupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ControlGroupEvent>()
+            .add_event::<SplitSelectionEvent>()
+            .add_event::<CycleGroupTypeEvent>()
+            .add_event::<BalanceGroupsEvent>()
+            .add_event::<ToggleGroupAlertEvent>()
+            .add_event::<GroupAlertEvent>()
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                InputSchedule,
+                update_groups
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<ControlGroupEvent>())
+                    .in_set(GroupsSet::Update)
+                    .before(SelectionSet::Update),
+            )
+            .add_systems(
+                InputSchedule,
+                split_selection
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<SplitSelectionEvent>())
+                    .in_set(GroupsSet::Update),
+            )
+            .add_systems(
+                InputSchedule,
+                balance_groups
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<BalanceGroupsEvent>())
+                    .in_set(GroupsSet::Update),
+            )
+            .add_systems(
+                InputSchedule,
+                cycle_group_type
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<CycleGroupTypeEvent>())
+                    .in_set(GroupsSet::Update)
+                    .after(update_groups)
+                    .before(SelectionSet::Update),
+            )
+            .add_systems(
+                InputSchedule,
+                toggle_alerts
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<ToggleGroupAlertEvent>())
+                    .in_set(GroupsSet::Update),
+            )
+            .add_systems(
+                PostUpdate,
+                (check_alerts, notify_alerts.after(check_alerts))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum GroupsSet {
+    Update,
+}
+
+/// Send this event to modify or recall a control group.
+#[derive(Event)]
+pub(crate) struct ControlGroupEvent {
+    group: usize,
+    action: GroupAction,
+}
+
+impl ControlGroupEvent {
+    /// # Panics
+    ///
+    /// Panics if `group` is not smaller than [`GROUP_COUNT`].
+    pub(crate) fn new(group: usize, action: GroupAction) -> Self {
+        assert!(group < GROUP_COUNT);
+        Self { group, action }
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn action(&self) -> GroupAction {
+        self.action
+    }
+}
+
+/// Send this event to split selected entities to spatial clusters. The
+/// clusters, ordered by decreasing size, are assigned to control groups 1, 2,
+/// ..., 9 and 0. Clusters which do not fit are not assigned.
+#[derive(Event)]
+pub(crate) struct SplitSelectionEvent;
+
+/// Send this event to select entities of the next object type from the most
+/// recently recalled control group. Object types are cycled in the order of
+/// their first entity (by ID) in the group, the whole group is selected after
+/// the last type.
+#[derive(Event)]
+pub(crate) struct CycleGroupTypeEvent;
+
+/// Send this event to redistribute members of all control groups containing
+/// a selected entity so that the sizes of the groups differ by at most one.
+/// Members which are no longer controlled by the player are dropped and
+/// members of several of the groups are kept only in one of them.
+#[derive(Event)]
+pub(crate) struct BalanceGroupsEvent;
+
+/// Send this event to flag a control group to alert the player (see
+/// [`GroupAlertEvent`]), or to remove the flag if it is already set.
+#[derive(Event)]
+pub(crate) struct ToggleGroupAlertEvent(usize);
+
+impl ToggleGroupAlertEvent {
+    /// # Panics
+    ///
+    /// Panics if `group` is not smaller than [`GROUP_COUNT`].
+    pub(crate) fn new(group: usize) -> Self {
+        assert!(group < GROUP_COUNT);
+        Self(group)
+    }
+
+    fn group(&self) -> usize {
+        self.0
+    }
+}
+
+/// This event is sent when all members of a flagged control group become
+/// idle, i.e. not moving and with no queued orders, or when the group comes
+/// under attack (see [`DAMAGE_ALERT_WINDOW`]).
+#[derive(Event)]
+pub(crate) struct GroupAlertEvent(usize);
+
+impl GroupAlertEvent {
+    fn new(group: usize) -> Self {
+        Self(group)
+    }
+
+    pub(crate) fn group(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum GroupAction {
+    /// Replace the group with currently selected entities.
+    Assign,
+    /// Add currently selected entities to the group.
+    Append,
+    /// Select entities of the group.
+    Recall,
+}
+
+#[derive(Resource, Default)]
+struct ControlGroups([AHashSet<Entity>; GROUP_COUNT]);
+
+impl ControlGroups {
+    fn get(&self, group: usize) -> &AHashSet<Entity> {
+        &self.0[group]
+    }
+
+    fn assign(&mut self, group: usize, entities: impl Iterator<Item = Entity>) {
+        let group = &mut self.0[group];
+        group.clear();
+        group.extend(entities);
+    }
+
+    fn append(&mut self, group: usize, entities: impl Iterator<Item = Entity>) {
+        self.0[group].extend(entities);
+    }
+
+    /// Redistributes members of the given groups so that the group sizes
+    /// differ by at most one. Entities are moved only from groups above
+    /// their balanced size to groups below it.
+    ///
+    /// # Arguments
+    ///
+    /// * `groups` - distinct groups to balance.
+    ///
+    /// * `keep` - members for which this returns false are removed from the
+    ///   groups.
+    fn balance(&mut self, groups: &[usize], keep: impl Fn(Entity) -> bool) {
+        if groups.is_empty() {
+            return;
+        }
+
+        // Each entity is kept only in the first of the groups it is a member
+        // of.
+        let mut seen = AHashSet::new();
+        let mut members: Vec<Vec<Entity>> = groups
+            .iter()
+            .map(|&group| {
+                let mut entities: Vec<Entity> = self.0[group]
+                    .iter()
+                    .copied()
+                    .filter(|&entity| keep(entity))
+                    .collect();
+                entities.sort_unstable();
+                entities.retain(|&entity| seen.insert(entity));
+                entities
+            })
+            .collect();
+
+        let total = seen.len();
+        let sizes: Vec<usize> = (0..groups.len())
+            .map(|i| total / groups.len() + usize::from(i < total % groups.len()))
+            .collect();
+
+        let mut surplus = Vec::new();
+        for (entities, &size) in members.iter_mut().zip(&sizes) {
+            if entities.len() > size {
+                surplus.extend(entities.drain(size..));
+            }
+        }
+        for (entities, &size) in members.iter_mut().zip(&sizes) {
+            while entities.len() < size {
+                entities.push(surplus.pop().unwrap());
+            }
+        }
+
+        for (&group, entities) in groups.iter().zip(members) {
+            self.assign(group, entities.into_iter());
+        }
+    }
+}
+
+/// The most recently recalled control group and object type to which the
+/// selection of its entities is restricted.
+#[derive(Resource, Default)]
+struct LastRecall(Option<(usize, Option<ObjectType>)>);
+
+/// Control groups flagged to alert the player.
+#[derive(Resource, Default)]
+struct GroupAlerts([Option<AlertState>; GROUP_COUNT]);
+
+/// State of a flagged control group during the last check. It is kept so that
+/// the alert is not repeated while the group stays idle or under attack.
+#[derive(Clone, Copy)]
+struct AlertState {
+    idle: bool,
+    damaged: bool,
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<ControlGroups>();
+    commands.init_resource::<LastRecall>();
+    commands.init_resource::<GroupAlerts>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ControlGroups>();
+    commands.remove_resource::<LastRecall>();
+    commands.remove_resource::<GroupAlerts>();
+}
+
+fn update_groups(
+    mut groups: ResMut<ControlGroups>,
+    mut last_recall: ResMut<LastRecall>,
+    selected: Query<Entity, With<Selected>>,
+    playable: Query<(), With<Playable>>,
+    mut in_events: EventReader<ControlGroupEvent>,
+    mut out_events: EventWriter<SelectEvent>,
+) {
+    for event in in_events.iter() {
+        match event.action() {
+            GroupAction::Assign => groups.assign(event.group(), selected.iter()),
+            GroupAction::Append => groups.append(event.group(), selected.iter()),
+            GroupAction::Recall => {
+                // Despawned entities and entities which are no longer
+                // controlled by the player are skipped.
+                let entities = groups
+                    .get(event.group())
+                    .iter()
+                    .cloned()
+                    .filter(|&entity| playable.contains(entity))
+                    .collect();
+                out_events.send(SelectEvent::many(entities, SelectionMode::Replace));
+                last_recall.0 = Some((event.group(), None));
+            }
+        }
+    }
+}
+
+fn cycle_group_type(
+    groups: Res<ControlGroups>,
+    mut last_recall: ResMut<LastRecall>,
+    objects: Query<&ObjectTypeComponent, With<Playable>>,
+    mut in_events: EventReader<CycleGroupTypeEvent>,
+    mut out_events: EventWriter<SelectEvent>,
+) {
+    let steps = in_events.iter().count();
+    let Some((group, filter)) = last_recall.0.as_mut() else {
+        return;
+    };
+
+    let mut members: Vec<(Entity, ObjectType)> = groups
+        .get(*group)
+        .iter()
+        .filter_map(|&entity| objects.get(entity).ok().map(|&object| (entity, *object)))
+        .collect();
+    members.sort_unstable_by_key(|&(entity, _)| entity);
+
+    let mut types: Vec<ObjectType> = Vec::new();
+    for &(_, object_type) in members.iter() {
+        if !types.contains(&object_type) {
+            types.push(object_type);
+        }
+    }
+
+    // Position 0 corresponds to the whole group, position i + 1 to types[i].
+    let current = filter
+        .and_then(|filter| types.iter().position(|&t| t == filter))
+        .map_or(0, |index| index + 1);
+    let next = (current + steps) % (types.len() + 1);
+    *filter = next.checked_sub(1).map(|index| types[index]);
+
+    let entities = members
+        .into_iter()
+        .filter(|&(_, object_type)| filter.map_or(true, |filter| filter == object_type))
+        .map(|(entity, _)| entity)
+        .collect();
+    out_events.send(SelectEvent::many(entities, SelectionMode::Replace));
+}
+
+fn balance_groups(
+    mut groups: ResMut<ControlGroups>,
+    selected: Query<(), With<Selected>>,
+    playable: Query<(), With<Playable>>,
+) {
+    let balanced: Vec<usize> = (0..GROUP_COUNT)
+        .filter(|&group| {
+            groups
+                .get(group)
+                .iter()
+                .any(|&entity| selected.contains(entity))
+        })
+        .collect();
+    groups.balance(&balanced, |entity| playable.contains(entity));
+}
+
+fn toggle_alerts(mut alerts: ResMut<GroupAlerts>, mut events: EventReader<ToggleGroupAlertEvent>) {
+    for event in events.iter() {
+        let alert = &mut alerts.0[event.group()];
+        // A freshly flagged group is considered idle so that the player is
+        // not alerted about a group which is already idle.
+        *alert = match alert {
+            Some(_) => None,
+            None => Some(AlertState {
+                idle: true,
+                damaged: false,
+            }),
+        };
+    }
+}
+
+fn check_alerts(
+    time: Res<Time>,
+    groups: Res<ControlGroups>,
+    mut alerts: ResMut<GroupAlerts>,
+    members: Query<(&CommandQueue, Has<PathTarget>), With<Playable>>,
+    damaged: Query<&RecentlyDamaged, With<Playable>>,
+    mut events: EventWriter<GroupAlertEvent>,
+) {
+    for (group, alert) in alerts.0.iter_mut().enumerate() {
+        let Some(state) = alert.as_mut() else {
+            continue;
+        };
+
+        let entities = groups.get(group);
+        let mut active = entities
+            .iter()
+            .filter_map(|&entity| members.get(entity).ok())
+            .peekable();
+        // Groups without any remaining units are not considered idle.
+        let idle =
+            active.peek().is_some() && active.all(|(queue, moving)| queue.is_empty() && !moving);
+        let damage = entities.iter().any(|&entity| {
+            damaged
+                .get(entity)
+                .is_ok_and(|damaged| damaged.within(time.elapsed(), DAMAGE_ALERT_WINDOW))
+        });
+
+        if (damage && !state.damaged) || (idle && !state.idle) {
+            events.send(GroupAlertEvent::new(group));
+        }
+        *state = AlertState {
+            idle,
+            damaged: damage,
+        };
+    }
+}
+
+fn notify_alerts(
+    mut alert_events: EventReader<GroupAlertEvent>,
+    mut toast_events: EventWriter<ToastEvent>,
+) {
+    for event in alert_events.iter() {
+        toast_events.send(ToastEvent::new(format!(
+            "Control group {} requires attention.",
+            event.group()
+        )));
+    }
+}
+
+fn split_selection(
+    mut groups: ResMut<ControlGroups>,
+    selected: Query<(Entity, &Transform), With<Selected>>,
+) {
+    let entities: Vec<(Entity, Vec2)> = selected
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.to_flat()))
+        .collect();
+
+    for (i, cluster) in clusters(&entities)
+        .into_iter()
+        .take(GROUP_COUNT)
+        .enumerate()
+    {
+        groups.assign((i + 1) % GROUP_COUNT, cluster.into_iter());
+    }
+}
+
+/// Splits entities to clusters, i.e. groups of entities connected by chains
+/// of entities closer than [`CLUSTER_DISTANCE`] to each other. The clusters
+/// are ordered by decreasing size.
+fn clusters(entities: &[(Entity, Vec2)]) -> Vec<Vec<Entity>> {
+    let max_distance_sq = CLUSTER_DISTANCE * CLUSTER_DISTANCE;
+    let mut visited = vec![false; entities.len()];
+    let mut clusters = Vec::new();
+
+    for seed in 0..entities.len() {
+        if visited[seed] {
+            continue;
+        }
+        visited[seed] = true;
+
+        let mut cluster = Vec::new();
+        let mut stack = vec![seed];
+        while let Some(current) = stack.pop() {
+            let (entity, position) = entities[current];
+            cluster.push(entity);
+
+            for (other, &(_, other_position)) in entities.iter().enumerate() {
+                if !visited[other] && position.distance_squared(other_position) < max_distance_sq {
+                    visited[other] = true;
+                    stack.push(other);
+                }
+            }
+        }
+        clusters.push(cluster);
+    }
+
+    // The sort is stable, thus the order of equally sized clusters is
+    // deterministic.
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::ecs::system::SystemState;
+    use de_behaviour::Order;
+    use de_types::objects::{ActiveObjectType, BuildingType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_append() {
+        let mut app = App::new();
+        app.init_resource::<ControlGroups>()
+            .init_resource::<LastRecall>()
+            .add_event::<ControlGroupEvent>()
+            .add_event::<SelectEvent>()
+            .add_systems(Update, update_groups);
+
+        let a = app.world.spawn((Playable, Selected)).id();
+        let b = app.world.spawn((Playable, Selected)).id();
+        let c = app.world.spawn(Playable).id();
+
+        app.world
+            .send_event(ControlGroupEvent::new(3, GroupAction::Assign));
+        app.update();
+        assert_eq!(
+            app.world.resource::<ControlGroups>().get(3),
+            &AHashSet::from_iter([a, b])
+        );
+
+        app.world.entity_mut(a).remove::<Selected>();
+        app.world.entity_mut(c).insert(Selected);
+        app.world
+            .send_event(ControlGroupEvent::new(3, GroupAction::Append));
+        app.update();
+        assert_eq!(
+            app.world.resource::<ControlGroups>().get(3),
+            &AHashSet::from_iter([a, b, c])
+        );
+
+        app.world
+            .send_event(ControlGroupEvent::new(3, GroupAction::Assign));
+        app.update();
+        assert_eq!(
+            app.world.resource::<ControlGroups>().get(3),
+            &AHashSet::from_iter([b, c])
+        );
+        assert!(app.world.resource::<ControlGroups>().get(2).is_empty());
+    }
+
+    #[test]
+    fn test_cycle_group_type() {
+        let mut app = App::new();
+        app.init_resource::<ControlGroups>()
+            .init_resource::<LastRecall>()
+            .add_event::<ControlGroupEvent>()
+            .add_event::<CycleGroupTypeEvent>()
+            .add_event::<SelectEvent>()
+            .add_systems(
+                Update,
+                (
+                    update_groups,
+                    cycle_group_type
+                        .run_if(on_event::<CycleGroupTypeEvent>())
+                        .after(update_groups),
+                ),
+            );
+
+        // There is only a single unit type, thus a building type stands in
+        // for the second type.
+        let tank_type = ObjectTypeComponent::from(ObjectType::Active(ActiveObjectType::Unit(
+            UnitType::Attacker,
+        )));
+        let infantry_type = ObjectTypeComponent::from(ObjectType::Active(
+            ActiveObjectType::Building(BuildingType::PowerHub),
+        ));
+        let tanks: Vec<Entity> = (0..2)
+            .map(|_| app.world.spawn((Playable, Selected, tank_type)).id())
+            .collect();
+        let infantry: Vec<Entity> = (0..3)
+            .map(|_| app.world.spawn((Playable, Selected, infantry_type)).id())
+            .collect();
+        let mut all = tanks.clone();
+        all.extend(infantry.iter().copied());
+
+        app.world
+            .send_event(ControlGroupEvent::new(5, GroupAction::Assign));
+        app.update();
+
+        let mut state = SystemState::<EventReader<SelectEvent>>::new(&mut app.world);
+        let mut selections = |app: &mut App| -> Vec<AHashSet<Entity>> {
+            state
+                .get_mut(&mut app.world)
+                .iter()
+                .map(|event| event.entities().iter().copied().collect())
+                .collect()
+        };
+        assert!(selections(&mut app).is_empty());
+
+        app.world
+            .send_event(ControlGroupEvent::new(5, GroupAction::Recall));
+        app.update();
+        assert_eq!(selections(&mut app), vec![AHashSet::from_iter(all.clone())]);
+
+        app.world.send_event(CycleGroupTypeEvent);
+        app.update();
+        assert_eq!(selections(&mut app), vec![AHashSet::from_iter(tanks)]);
+
+        app.world.send_event(CycleGroupTypeEvent);
+        app.update();
+        assert_eq!(selections(&mut app), vec![AHashSet::from_iter(infantry)]);
+
+        app.world.send_event(CycleGroupTypeEvent);
+        app.update();
+        assert_eq!(selections(&mut app), vec![AHashSet::from_iter(all)]);
+    }
+
+    #[test]
+    fn test_balance() {
+        let entities: Vec<Entity> = (0..7).map(Entity::from_raw).collect();
+        let dead = entities[6];
+
+        let mut groups = ControlGroups::default();
+        groups.assign(2, entities[..5].iter().copied());
+        groups.assign(7, [entities[5], dead].into_iter());
+        groups.assign(8, entities[..2].iter().copied());
+
+        groups.balance(&[2, 7], |entity| entity != dead);
+        assert_eq!(groups.get(2).len(), 3);
+        assert_eq!(groups.get(7).len(), 3);
+        assert!(groups.get(7).contains(&entities[5]));
+        assert!(groups.get(2).is_disjoint(groups.get(7)));
+
+        let mut all: AHashSet<Entity> = groups.get(2).union(groups.get(7)).copied().collect();
+        assert!(all.remove(&entities[5]));
+        assert_eq!(all, AHashSet::from_iter(entities[..5].iter().copied()));
+        // Other groups are left intact.
+        assert_eq!(
+            groups.get(8),
+            &AHashSet::from_iter(entities[..2].iter().copied())
+        );
+    }
+
+    #[test]
+    fn test_idle_alert() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ControlGroups>()
+            .init_resource::<GroupAlerts>()
+            .add_event::<ToggleGroupAlertEvent>()
+            .add_event::<GroupAlertEvent>()
+            .add_systems(Update, (toggle_alerts, check_alerts.after(toggle_alerts)));
+
+        let busy = || CommandQueue::from_iter([Order::Move(Vec2::new(10., 20.))]);
+        let units = [
+            app.world.spawn((Playable, busy())).id(),
+            app.world.spawn((Playable, busy())).id(),
+        ];
+        app.world
+            .resource_mut::<ControlGroups>()
+            .assign(4, units.into_iter());
+        app.world.send_event(ToggleGroupAlertEvent::new(4));
+
+        let mut state = SystemState::<EventReader<GroupAlertEvent>>::new(&mut app.world);
+        let mut alerts = |app: &mut App| -> Vec<usize> {
+            state
+                .get_mut(&mut app.world)
+                .iter()
+                .map(|event| event.group())
+                .collect()
+        };
+
+        app.update();
+        assert!(alerts(&mut app).is_empty());
+
+        app.world
+            .entity_mut(units[0])
+            .insert(CommandQueue::default());
+        app.update();
+        assert!(alerts(&mut app).is_empty());
+
+        app.world
+            .entity_mut(units[1])
+            .insert(CommandQueue::default());
+        app.update();
+        assert_eq!(alerts(&mut app), vec![4]);
+
+        app.update();
+        app.update();
+        assert!(alerts(&mut app).is_empty());
+    }
+
+    #[test]
+    fn test_damage_alert() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ControlGroups>()
+            .init_resource::<GroupAlerts>()
+            .add_event::<ToggleGroupAlertEvent>()
+            .add_event::<GroupAlertEvent>()
+            .add_systems(Update, (toggle_alerts, check_alerts.after(toggle_alerts)));
+
+        let busy = || CommandQueue::from_iter([Order::Move(Vec2::new(10., 20.))]);
+        let units = [
+            app.world.spawn((Playable, busy())).id(),
+            app.world.spawn((Playable, busy())).id(),
+        ];
+        app.world
+            .resource_mut::<ControlGroups>()
+            .assign(2, units.into_iter());
+        app.world.send_event(ToggleGroupAlertEvent::new(2));
+
+        let start = Instant::now();
+        let mut state = SystemState::<EventReader<GroupAlertEvent>>::new(&mut app.world);
+        let mut step = |app: &mut App, secs: u64| -> Vec<usize> {
+            app.world
+                .resource_mut::<Time>()
+                .update_with_instant(start + Duration::from_secs(secs));
+            app.update();
+            state
+                .get_mut(&mut app.world)
+                .iter()
+                .map(|event| event.group())
+                .collect()
+        };
+        let damage = |app: &mut App, unit: Entity| {
+            let now = app.world.resource::<Time>().elapsed();
+            app.world.entity_mut(unit).insert(RecentlyDamaged::new(now));
+        };
+
+        assert!(step(&mut app, 0).is_empty());
+
+        damage(&mut app, units[0]);
+        assert_eq!(step(&mut app, 1), vec![2]);
+
+        // Repeated damage of the group under attack is not alerted.
+        damage(&mut app, units[0]);
+        assert!(step(&mut app, 2).is_empty());
+        damage(&mut app, units[1]);
+        assert!(step(&mut app, 5).is_empty());
+        assert!(step(&mut app, 10).is_empty());
+
+        // The group is alerted again once it was not damaged for a while.
+        assert!(step(&mut app, 30).is_empty());
+        damage(&mut app, units[1]);
+        assert_eq!(step(&mut app, 31), vec![2]);
+    }
+
+    #[test]
+    fn test_split_selection() {
+        let mut app = App::new();
+        app.init_resource::<ControlGroups>()
+            .add_systems(Update, split_selection);
+
+        let mut spawn = |x: f32, z: f32| {
+            app.world
+                .spawn((Selected, Transform::from_xyz(x, 0., z)))
+                .id()
+        };
+        let small = [spawn(100., 100.), spawn(105., 95.)];
+        let large = [spawn(0., 0.), spawn(10., 5.), spawn(-5., 12.)];
+        app.world.spawn(Transform::from_xyz(2., 0., 2.));
+
+        app.update();
+        let groups = app.world.resource::<ControlGroups>();
+        assert_eq!(groups.get(1), &AHashSet::from_iter(large));
+        assert_eq!(groups.get(2), &AHashSet::from_iter(small));
+        assert!(groups.get(3).is_empty());
+        assert!(groups.get(0).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+//! This module implements hiding of circle markers drawn below units. Hidden
+//! markers are removed from their entities and re-inserted once the markers
+//! are shown again, thus selection of the entities is not affected.
+//!
+//! Circle markers of selected units are tinted by the units' health.
+
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, schedule::InputSchedule, state::AppState};
+use de_objects::Health;
+use de_terrain::CircleMarker;
+
+use super::Selected;
+
+/// Units with health fraction above this are marked as healthy (green).
+const HEALTHY_FRACTION: f32 = 2. / 3.;
+/// Units with health fraction above this (and not healthy) are marked as
+/// damaged (yellow). Units with lower health are marked as critical (red).
+const DAMAGED_FRACTION: f32 = 1. / 3.;
+
+pub(super) struct MarkersPlugin;
+
+impl Plugin for MarkersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ToggleMarkersEvent>()
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                InputSchedule,
+                (
+                    toggle_markers
+                        .run_if(on_event::<ToggleMarkersEvent>())
+                        .in_set(MarkersSet::Toggle),
+                    update_markers.after(MarkersSet::Toggle),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, tint_markers.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum MarkersSet {
+    Toggle,
+}
+
+/// Send this event to toggle visibility of circle markers.
+#[derive(Event)]
+pub(crate) struct ToggleMarkersEvent;
+
+/// Whether circle markers are hidden. While hidden, circle markers inserted
+/// to new entities are hidden as well.
+#[derive(Resource, Default)]
+struct MarkersHidden(bool);
+
+/// Circle marker removed from its entity while markers are hidden.
+#[derive(Component)]
+struct HiddenMarker(CircleMarker);
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<MarkersHidden>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<MarkersHidden>();
+}
+
+fn toggle_markers(mut hidden: ResMut<MarkersHidden>) {
+    hidden.0 = !hidden.0;
+}
+
+fn update_markers(
+    mut commands: Commands,
+    hidden: Res<MarkersHidden>,
+    shown: Query<(Entity, &CircleMarker)>,
+    stashed: Query<(Entity, &HiddenMarker)>,
+) {
+    if hidden.0 {
+        for (entity, &marker) in shown.iter() {
+            commands
+                .entity(entity)
+                .remove::<CircleMarker>()
+                .insert(HiddenMarker(marker));
+        }
+    } else {
+        for (entity, marker) in stashed.iter() {
+            commands
+                .entity(entity)
+                .remove::<HiddenMarker>()
+                .insert(marker.0);
+        }
+    }
+}
+
+/// Returns marker color corresponding to a given health fraction.
+fn health_color(fraction: f32) -> Color {
+    if fraction > HEALTHY_FRACTION {
+        Color::GREEN
+    } else if fraction > DAMAGED_FRACTION {
+        Color::YELLOW
+    } else {
+        Color::RED
+    }
+}
+
+fn tint_markers(mut markers: Query<(&Health, &mut CircleMarker), With<Selected>>) {
+    for (health, mut marker) in markers.iter_mut() {
+        let color = health_color(health.fraction());
+        // Avoid triggering change detection when the color is unchanged.
+        if marker.color() != color {
+            marker.set_color(color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_objects::InitialHealths;
+    use de_types::objects::{ActiveObjectType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_toggle_markers() {
+        let mut app = App::new();
+        app.init_resource::<MarkersHidden>()
+            .add_event::<ToggleMarkersEvent>()
+            .add_systems(
+                Update,
+                (
+                    toggle_markers.run_if(on_event::<ToggleMarkersEvent>()),
+                    update_markers.after(toggle_markers),
+                ),
+            );
+
+        let selected = app.world.spawn((Selected, CircleMarker::new(2.))).id();
+        let other = app.world.spawn(CircleMarker::new(3.)).id();
+
+        app.update();
+        assert!(app.world.get::<CircleMarker>(selected).is_some());
+        assert!(app.world.get::<CircleMarker>(other).is_some());
+
+        app.world.send_event(ToggleMarkersEvent);
+        app.update();
+        assert!(app.world.resource::<MarkersHidden>().0);
+        assert!(app.world.get::<CircleMarker>(selected).is_none());
+        assert!(app.world.get::<CircleMarker>(other).is_none());
+        assert!(app.world.get::<Selected>(selected).is_some());
+        assert!(app.world.get::<Selected>(other).is_none());
+
+        // Markers inserted while hidden are hidden as well.
+        let new = app.world.spawn(CircleMarker::new(1.)).id();
+        app.update();
+        assert!(app.world.get::<CircleMarker>(new).is_none());
+
+        app.world.send_event(ToggleMarkersEvent);
+        app.update();
+        assert!(!app.world.resource::<MarkersHidden>().0);
+        for entity in [selected, other, new] {
+            assert!(app.world.get::<CircleMarker>(entity).is_some());
+            assert!(app.world.get::<HiddenMarker>(entity).is_none());
+        }
+        assert!(app.world.get::<Selected>(selected).is_some());
+        assert!(app.world.get::<Selected>(other).is_none());
+    }
+
+    #[test]
+    fn test_tint_markers() {
+        let mut app = App::new();
+        app.add_systems(Update, tint_markers);
+
+        let full = InitialHealths::default()
+            .health(ActiveObjectType::Unit(UnitType::Attacker))
+            .clone();
+        let mut damaged = full.clone();
+        damaged.update(-7.);
+        assert!((damaged.fraction() - 0.3).abs() < 1e-6);
+
+        let healthy = app
+            .world
+            .spawn((Selected, full.clone(), CircleMarker::new(1.)))
+            .id();
+        let critical = app
+            .world
+            .spawn((Selected, damaged, CircleMarker::new(1.)))
+            .id();
+        let unselected = app.world.spawn((full, CircleMarker::new(1.))).id();
+        app.update();
+
+        assert_eq!(
+            app.world.get::<CircleMarker>(healthy).unwrap().color(),
+            Color::GREEN
+        );
+        assert_eq!(
+            app.world.get::<CircleMarker>(critical).unwrap().color(),
+            Color::RED
+        );
+        assert_eq!(
+            app.world.get::<CircleMarker>(unselected).unwrap().color(),
+            Color::WHITE
+        );
+    }
+}
@@ -1,16 +1,44 @@
 use area::AreaPlugin;
-pub(crate) use area::{AreaSelectSet, SelectInRectEvent};
+pub(crate) use area::{
+    AreaSelectSet, SelectInPolygonEvent, SelectInRectEvent, SelectSameTypeEvent,
+};
 use bevy::prelude::*;
 use bookkeeping::BookkeepingPlugin;
-pub(crate) use bookkeeping::{SelectEvent, Selected, SelectionMode, SelectionSet};
+pub use bookkeeping::{PersistSelection, SelectionDiff, SelectionMode};
+pub(crate) use bookkeeping::{SelectEvent, Selected, SelectionSet};
+use brush::BrushPlugin;
+pub(crate) use brush::BRUSH_KEY;
+pub(crate) use current::CurrentSelection;
+use groups::GroupsPlugin;
+pub(crate) use groups::{
+    BalanceGroupsEvent, ControlGroupEvent, CycleGroupTypeEvent, GroupAction, GroupsSet,
+    SplitSelectionEvent, ToggleGroupAlertEvent, GROUP_COUNT,
+};
+use markers::MarkersPlugin;
+pub(crate) use markers::{MarkersSet, ToggleMarkersEvent};
+use primary::PrimaryPlugin;
+pub(crate) use primary::{CyclePrimaryEvent, PrimarySelected, PrimarySet};
 
 mod area;
+mod bitset;
 mod bookkeeping;
+mod brush;
+mod current;
+mod groups;
+mod markers;
+mod primary;
 
 pub(crate) struct SelectionPlugin;
 
 impl Plugin for SelectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((BookkeepingPlugin, AreaPlugin));
+        app.add_plugins((
+            BookkeepingPlugin,
+            AreaPlugin,
+            BrushPlugin,
+            GroupsPlugin,
+            MarkersPlugin,
+            PrimaryPlugin,
+        ));
     }
 }
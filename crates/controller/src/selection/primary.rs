@@ -0,0 +1,114 @@
+//! This module keeps a single primary entity within the selection. The
+//! primary entity is the one the HUD focuses on.
+
+use bevy::{ecs::query::Has, prelude::*};
+use de_core::{gamestate::GameState, objects::MovableSolid, schedule::InputSchedule};
+
+use super::{Selected, SelectionSet};
+
+pub(super) struct PrimaryPlugin;
+
+impl Plugin for PrimaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CyclePrimaryEvent>().add_systems(
+            InputSchedule,
+            update_primary
+                .run_if(in_state(GameState::Playing))
+                .in_set(PrimarySet::Update)
+                .after(SelectionSet::Update),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum PrimarySet {
+    Update,
+}
+
+/// Send this event to make the next selected entity the primary one.
+#[derive(Event)]
+pub(crate) struct CyclePrimaryEvent;
+
+/// Exactly one selected entity carries this component whenever the selection
+/// is not empty.
+#[derive(Component)]
+pub(crate) struct PrimarySelected;
+
+/// Moves [`PrimarySelected`] from deselected entities to a selected entity.
+/// Units are preferred to buildings when a new primary entity is chosen.
+///
+/// Selected entities are ordered by their ID when the primary entity is
+/// cycled.
+fn update_primary(
+    mut commands: Commands,
+    mut events: EventReader<CyclePrimaryEvent>,
+    selected: Query<(Entity, Has<PrimarySelected>, Has<MovableSolid>), With<Selected>>,
+    deselected: Query<Entity, (With<PrimarySelected>, Without<Selected>)>,
+) {
+    for entity in deselected.iter() {
+        commands.entity(entity).remove::<PrimarySelected>();
+    }
+
+    let mut candidates: Vec<(Entity, bool, bool)> = selected.iter().collect();
+    if candidates.is_empty() {
+        return;
+    }
+    candidates.sort_unstable_by_key(|&(entity, _, _)| entity);
+
+    let current = candidates.iter().position(|&(_, primary, _)| primary);
+    let base = current.unwrap_or_else(|| {
+        candidates
+            .iter()
+            .position(|&(_, _, movable)| movable)
+            .unwrap_or(0)
+    });
+    let new = (base + events.iter().count()) % candidates.len();
+
+    if current == Some(new) {
+        return;
+    }
+    if let Some(current) = current {
+        commands
+            .entity(candidates[current].0)
+            .remove::<PrimarySelected>();
+    }
+    commands.entity(candidates[new].0).insert(PrimarySelected);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn primary(world: &mut World) -> Vec<Entity> {
+        world
+            .query_filtered::<Entity, With<PrimarySelected>>()
+            .iter(world)
+            .collect()
+    }
+
+    #[test]
+    fn test_primary() {
+        let mut app = App::new();
+        app.add_event::<CyclePrimaryEvent>()
+            .add_systems(Update, update_primary);
+
+        let building = app.world.spawn(Selected).id();
+        let first = app.world.spawn((Selected, MovableSolid)).id();
+        let second = app.world.spawn((Selected, MovableSolid)).id();
+
+        app.update();
+        assert_eq!(primary(&mut app.world), vec![first]);
+
+        app.world.send_event(CyclePrimaryEvent);
+        app.update();
+        assert_eq!(primary(&mut app.world), vec![second]);
+
+        app.world.send_event(CyclePrimaryEvent);
+        app.update();
+        assert_eq!(primary(&mut app.world), vec![building]);
+
+        app.world.entity_mut(building).remove::<Selected>();
+        app.update();
+        assert_eq!(primary(&mut app.world), vec![first]);
+    }
+}
@@ -2,7 +2,7 @@ use bevy::{
     prelude::{GlobalTransform, Transform},
     render::primitives::{Aabb, Frustum, Sphere},
 };
-use glam::Vec3;
+use glam::{Vec3, Vec3A};
 use parry3d::bounding_volume::Aabb as AabbP;
 
 /// See [`intersects_bevy`].
@@ -32,3 +32,103 @@ pub fn intersects_bevy(frustum: &Frustum, transform: &GlobalTransform, aabb: &Aa
     frustum.intersects_sphere(&model_sphere, false)
         && frustum.intersects_obb(aabb, &model, false, true)
 }
+
+/// Position of an object relative to a frustum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrustumPosition {
+    /// The object is fully inside the frustum.
+    Inside,
+    /// The object might be partially inside the frustum.
+    Intersecting,
+    /// The object is fully outside the frustum.
+    Outside,
+}
+
+/// See [`classify_bevy`].
+pub fn classify_parry(frustum: &Frustum, transform: Transform, aabb: &AabbP) -> FrustumPosition {
+    let transform = GlobalTransform::from(transform);
+    let aabb = Aabb::from_min_max(Vec3::from(aabb.mins), Vec3::from(aabb.maxs));
+    classify_bevy(frustum, &transform, &aabb)
+}
+
+/// Classifies object space `aabb` transformed by `transform` with respect to
+/// the given `frustum`. The near plane of the frustum is ignored, similarly
+/// to [`intersects_bevy`].
+///
+/// The classification is conservative: objects outside the frustum but close
+/// to its edges might be classified as [`FrustumPosition::Intersecting`].
+///
+/// # Arguments
+///
+/// * `frustum` - frustum to be tested against.
+///
+/// * `transform` - transformation of the tested object.
+///
+/// * `aabb` - object space AABB.
+pub fn classify_bevy(
+    frustum: &Frustum,
+    transform: &GlobalTransform,
+    aabb: &Aabb,
+) -> FrustumPosition {
+    let model = transform.compute_matrix();
+    let center = model.transform_point3a(aabb.center).extend(1.);
+    let axes = [
+        Vec3A::from(model.x_axis),
+        Vec3A::from(model.y_axis),
+        Vec3A::from(model.z_axis),
+    ];
+
+    let mut position = FrustumPosition::Inside;
+    for (i, half_space) in frustum.half_spaces.iter().enumerate() {
+        if i == 4 {
+            continue;
+        }
+
+        let distance = half_space.normal_d().dot(center);
+        let radius = aabb.relative_radius(&half_space.normal(), &axes);
+        if distance + radius <= 0. {
+            return FrustumPosition::Outside;
+        }
+        if distance - radius < 0. {
+            position = FrustumPosition::Intersecting;
+        }
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::primitives::HalfSpace;
+    use glam::Vec4;
+
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        // A box frustum spanning from -10 to 10 along each axis.
+        let half_spaces = [
+            Vec4::new(1., 0., 0., 10.),
+            Vec4::new(-1., 0., 0., 10.),
+            Vec4::new(0., 1., 0., 10.),
+            Vec4::new(0., -1., 0., 10.),
+            Vec4::new(0., 0., 1., 10.),
+            Vec4::new(0., 0., -1., 10.),
+        ]
+        .map(HalfSpace::new);
+        let frustum = Frustum { half_spaces };
+        let aabb = AabbP::new([-1., -1., -1.].into(), [1., 1., 1.].into());
+
+        assert_eq!(
+            classify_parry(&frustum, Transform::from_xyz(2., -3., 4.), &aabb),
+            FrustumPosition::Inside
+        );
+        assert_eq!(
+            classify_parry(&frustum, Transform::from_xyz(9.5, 0., 0.), &aabb),
+            FrustumPosition::Intersecting
+        );
+        assert_eq!(
+            classify_parry(&frustum, Transform::from_xyz(0., 12., 0.), &aabb),
+            FrustumPosition::Outside
+        );
+    }
+}
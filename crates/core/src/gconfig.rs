@@ -11,6 +11,7 @@ pub struct GameConfig {
     map_path: PathBuf,
     multiplayer: bool,
     locals: LocalPlayers,
+    allies: ArrayVec<[Player; Player::MAX_PLAYERS]>,
 }
 
 impl GameConfig {
@@ -19,9 +20,21 @@ impl GameConfig {
             map_path: map_path.into(),
             multiplayer,
             locals,
+            allies: ArrayVec::new(),
         }
     }
 
+    /// Sets players allied with the playable player. The playable player is
+    /// never its own ally.
+    pub fn with_allies(mut self, allies: &[Player]) -> Self {
+        self.allies = allies
+            .iter()
+            .copied()
+            .filter(|&player| !self.locals.is_playable(player))
+            .collect();
+        self
+    }
+
     pub fn map_path(&self) -> &Path {
         self.map_path.as_path()
     }
@@ -33,6 +46,19 @@ impl GameConfig {
     pub fn locals(&self) -> &LocalPlayers {
         &self.locals
     }
+
+    /// Returns true if the player is allied with the playable player.
+    pub fn is_ally(&self, player: Player) -> bool {
+        self.allies.contains(&player)
+    }
+
+    /// Returns true if the players are hostile to each other, i.e. they are
+    /// different players which are not both members of the alliance of the
+    /// playable player.
+    pub fn are_enemies(&self, first: Player, second: Player) -> bool {
+        let allied = |player| self.locals.is_playable(player) || self.is_ally(player);
+        first != second && !(allied(first) && allied(second))
+    }
 }
 
 /// Info about players directly controlled or simulated on this computer.
@@ -112,5 +138,18 @@ mod tests {
             LocalPlayers::from_max_player(Player::Player1, Player::Player4),
         );
         assert_eq!(config.map_path().to_string_lossy(), "/some/path");
+        assert!(!config.is_ally(Player::Player2));
+
+        let config = config.with_allies(&[Player::Player1, Player::Player3]);
+        assert!(!config.is_ally(Player::Player1));
+        assert!(!config.is_ally(Player::Player2));
+        assert!(config.is_ally(Player::Player3));
+
+        assert!(!config.are_enemies(Player::Player1, Player::Player3));
+        assert!(!config.are_enemies(Player::Player3, Player::Player1));
+        assert!(!config.are_enemies(Player::Player2, Player::Player2));
+        assert!(config.are_enemies(Player::Player1, Player::Player2));
+        assert!(config.are_enemies(Player::Player2, Player::Player3));
+        assert!(config.are_enemies(Player::Player2, Player::Player4));
     }
 }
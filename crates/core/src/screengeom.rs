@@ -83,6 +83,54 @@ impl ScreenRect {
     }
 }
 
+/// A closed polygon on the screen, for example a lasso drawn by the player.
+///
+/// Point coordinates follow the same convention as [`ScreenRect`]: the
+/// bottom-left corner of the screen is [-1, -1] and the top-right corner is
+/// [1, 1].
+#[derive(Clone, Debug)]
+pub struct ScreenPolygon(Vec<Vec2>);
+
+impl ScreenPolygon {
+    /// # Arguments
+    ///
+    /// * `vertices` - polygon vertices in order. The polygon is implicitly
+    ///   closed, i.e. the last vertex is connected to the first one. The
+    ///   polygon may be self-intersecting.
+    pub fn new(vertices: Vec<Vec2>) -> Self {
+        Self(vertices)
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        self.0.as_slice()
+    }
+
+    /// Returns true if the point lies inside the polygon.
+    ///
+    /// Self-intersecting polygons are handled with the even-odd rule: a point
+    /// is inside if a ray cast from it crosses polygon edges an odd number of
+    /// times. Polygons with fewer than three vertices contain no points.
+    pub fn contains(&self, point: Vec2) -> bool {
+        if self.0.len() < 3 {
+            return false;
+        }
+
+        let mut inside = false;
+        let mut previous = self.0[self.0.len() - 1];
+        for &current in self.0.iter() {
+            if (current.y > point.y) != (previous.y > point.y) {
+                let t = (point.y - current.y) / (previous.y - current.y);
+                let x = current.x + t * (previous.x - current.x);
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+            previous = current;
+        }
+        inside
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,4 +143,38 @@ mod tests {
         assert_eq!(rect.bottom(), 0.2);
         assert_eq!(rect.top(), 0.3);
     }
+
+    #[test]
+    fn test_polygon_contains() {
+        let triangle = ScreenPolygon::new(vec![
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(0.5, -0.5),
+            Vec2::new(0., 0.5),
+        ]);
+        assert!(triangle.contains(Vec2::new(0., 0.)));
+        assert!(triangle.contains(Vec2::new(0.3, -0.4)));
+        assert!(!triangle.contains(Vec2::new(0.4, 0.3)));
+        assert!(!triangle.contains(Vec2::new(0., -0.6)));
+        assert!(!triangle.contains(Vec2::new(0.9, 0.)));
+
+        // A pentagram: the central pentagon is crossed twice by the polygon
+        // and thus lies outside according to the even-odd rule.
+        let pentagram = ScreenPolygon::new(
+            [0, 2, 4, 1, 3]
+                .iter()
+                .map(|&i| {
+                    let angle =
+                        std::f32::consts::FRAC_PI_2 + i as f32 * 2. * std::f32::consts::PI / 5.;
+                    0.8 * Vec2::new(angle.cos(), angle.sin())
+                })
+                .collect(),
+        );
+        assert!(!pentagram.contains(Vec2::ZERO));
+        assert!(pentagram.contains(Vec2::new(0., 0.6)));
+        assert!(!pentagram.contains(Vec2::new(0., 0.9)));
+        assert!(!pentagram.contains(Vec2::new(0.5, 0.5)));
+
+        let line = ScreenPolygon::new(vec![Vec2::ZERO, Vec2::ONE]);
+        assert!(!line.contains(Vec2::splat(0.5)));
+    }
 }
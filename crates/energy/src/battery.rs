@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::MovementSpeed;
+
 pub(crate) struct BatteryPlugin;
 
 impl Plugin for BatteryPlugin {
@@ -30,7 +32,7 @@ impl Default for Battery {
 }
 
 impl Battery {
-    fn new(capacity: f64, energy: f64) -> Self {
+    pub(crate) fn new(capacity: f64, energy: f64) -> Self {
         debug_assert!(capacity.is_finite());
         debug_assert!(capacity > 0.);
         debug_assert!(energy.is_finite());
@@ -50,6 +52,14 @@ impl Battery {
         self.energy
     }
 
+    /// Sets the energy level of the battery, clamped to the capacity.
+    pub(crate) fn set_energy(&mut self, energy: f64) {
+        debug_assert!(energy.is_finite());
+        debug_assert!(energy >= 0.);
+
+        self.energy = energy.min(self.capacity);
+    }
+
     /// Directly changes the energy level of the battery by the given amount of energy.
     fn change(&mut self, delta: f64) {
         debug_assert!(delta.is_finite());
@@ -64,17 +74,22 @@ impl Battery {
 ///
 /// * `time` - The time since the last update.
 ///
-/// * `battery` - The battery.
-pub(crate) fn discharge_battery(time: Res<Time>, mut battery: Query<&mut Battery>) {
+/// * `battery` - The battery and optional speed tier (running units discharge
+///   faster).
+pub(crate) fn discharge_battery(
+    time: Res<Time>,
+    mut battery: Query<(&mut Battery, Option<&MovementSpeed>)>,
+) {
     let delta = time.delta_seconds();
     let discharge_delta = DISCHARGE_RATE * delta as f64;
-    for mut battery in battery.iter_mut() {
+    for (mut battery, speed) in battery.iter_mut() {
         let energy = battery.energy();
         if energy == 0. {
             continue;
         }
 
-        battery.change(-discharge_delta);
+        let factor = speed.map_or(1., |speed| speed.discharge_factor());
+        battery.change(-factor * discharge_delta);
     }
 }
 
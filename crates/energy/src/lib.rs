@@ -1,14 +1,22 @@
 mod battery;
+mod speed;
+mod syncing;
+mod throttle;
 
 pub use battery::Battery;
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+pub use speed::{MovementSpeed, SpeedTier};
+pub use throttle::{Throttle, UnitStalledEvent};
 
-use crate::battery::BatteryPlugin;
+use crate::{battery::BatteryPlugin, syncing::SyncingPlugin, throttle::ThrottlePlugin};
 
 pub struct EnergyPluginGroup;
 
 impl PluginGroup for EnergyPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(BatteryPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(BatteryPlugin)
+            .add(ThrottlePlugin)
+            .add(SyncingPlugin)
     }
 }
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+/// Movement speed of running units relative to walking units.
+const RUN_FACTOR: f32 = 1.5;
+/// Battery discharge rate of running units relative to walking units.
+const RUN_DISCHARGE_FACTOR: f64 = 2.;
+
+/// Movement speed tier of a unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedTier {
+    /// Nominal (quiet) movement speed.
+    #[default]
+    Walk,
+    /// Fast movement at the cost of faster battery discharge.
+    Run,
+}
+
+/// Movement speed tier selected for a unit. Units without this component
+/// walk.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MovementSpeed(SpeedTier);
+
+impl MovementSpeed {
+    pub fn new(tier: SpeedTier) -> Self {
+        Self(tier)
+    }
+
+    pub fn tier(&self) -> SpeedTier {
+        self.0
+    }
+
+    /// Returns the factor by which the maximum movement speed of the unit is
+    /// scaled.
+    pub fn factor(&self) -> f32 {
+        match self.0 {
+            SpeedTier::Walk => 1.,
+            SpeedTier::Run => RUN_FACTOR,
+        }
+    }
+
+    /// Returns the factor by which battery discharge rate of the unit is
+    /// scaled.
+    pub(crate) fn discharge_factor(&self) -> f64 {
+        match self.0 {
+            SpeedTier::Walk => 1.,
+            SpeedTier::Run => RUN_DISCHARGE_FACTOR,
+        }
+    }
+}
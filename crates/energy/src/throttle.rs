@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::{battery::discharge_battery, Battery};
+
+/// Below this fraction of battery capacity, movement capability of a unit
+/// decreases linearly with the energy level.
+const LOW_ENERGY_FRACTION: f64 = 0.1;
+
+pub(crate) struct ThrottlePlugin;
+
+impl Plugin for ThrottlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UnitStalledEvent>()
+            .add_systems(Update, update_throttles.after(discharge_battery));
+    }
+}
+
+/// Fraction (between 0 and 1) of the maximum movement capability of a unit
+/// limited by the energy level of its battery.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Throttle(f32);
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+impl Throttle {
+    fn from_battery(battery: &Battery) -> Self {
+        let fraction = battery.energy() / (LOW_ENERGY_FRACTION * battery.capacity());
+        Self(fraction.clamp(0., 1.) as f32)
+    }
+
+    /// Returns the factor by which the maximum movement speed of the unit is
+    /// scaled.
+    pub fn factor(&self) -> f32 {
+        self.0
+    }
+}
+
+/// This event is sent when a unit stops because its battery got fully
+/// depleted.
+#[derive(Event)]
+pub struct UnitStalledEvent(Entity);
+
+impl UnitStalledEvent {
+    fn new(entity: Entity) -> Self {
+        Self(entity)
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+fn update_throttles(
+    mut units: Query<(Entity, &Battery, &mut Throttle), Changed<Battery>>,
+    mut events: EventWriter<UnitStalledEvent>,
+) {
+    for (entity, battery, mut throttle) in units.iter_mut() {
+        let new = Throttle::from_battery(battery);
+        if *throttle == new {
+            continue;
+        }
+
+        if new.factor() == 0. {
+            events.send(UnitStalledEvent::new(entity));
+        }
+        *throttle = new;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_stalled() {
+        let mut app = App::new();
+        app.add_event::<UnitStalledEvent>()
+            .add_systems(Update, update_throttles);
+
+        let full = app
+            .world
+            .spawn((Battery::new(100., 100.), Throttle::default()))
+            .id();
+        let low = app
+            .world
+            .spawn((Battery::new(100., 5.), Throttle::default()))
+            .id();
+        let empty = app
+            .world
+            .spawn((Battery::new(100., 0.), Throttle::default()))
+            .id();
+
+        let mut state = SystemState::<EventReader<UnitStalledEvent>>::new(&mut app.world);
+
+        app.update();
+        assert_eq!(app.world.get::<Throttle>(full).unwrap().factor(), 1.);
+        assert_eq!(app.world.get::<Throttle>(low).unwrap().factor(), 0.5);
+        assert_eq!(app.world.get::<Throttle>(empty).unwrap().factor(), 0.);
+        let stalled: Vec<Entity> = state
+            .get_mut(&mut app.world)
+            .iter()
+            .map(|event| event.entity())
+            .collect();
+        assert_eq!(stalled, vec![empty]);
+
+        // The battery is still empty but the event is not repeated.
+        app.world.get_mut::<Battery>(empty).unwrap().set_changed();
+        app.update();
+        assert_eq!(app.world.get::<Throttle>(empty).unwrap().factor(), 0.);
+        assert!(state.get_mut(&mut app.world).iter().next().is_none());
+    }
+}
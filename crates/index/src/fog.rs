@@ -0,0 +1,320 @@
+//! This module implements a per-player fog of war layer. Tiles within vision
+//! distance of any active entity of a player are visible to the player. Tiles
+//! which were visible at any point in the past stay explored.
+//!
+//! The layer is built from tiles of [`crate::EntityIndex`] and updated
+//! incrementally, see [`crate::tracker`].
+
+use ahash::{AHashMap, AHashSet};
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, schedule::PostMovement, state::AppState};
+use de_types::player::Player;
+use glam::{IVec2, Vec2};
+use parry2d::bounding_volume::Aabb;
+
+use crate::{
+    tracker::{TileChanges, TileLayer, TileTracker},
+    PreciseIndexSet,
+};
+
+/// Distance (in meters) up to which an entity sees. The distance is measured
+/// between tile centers.
+const VISION_DISTANCE: f32 = 40.;
+
+pub(crate) struct FogPlugin;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                PostMovement,
+                update
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(FogSet::Update)
+                    .after(PreciseIndexSet::Index),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, SystemSet)]
+pub enum FogSet {
+    Update,
+}
+
+/// Per player map of visible and explored tiles.
+#[derive(Resource, Default)]
+pub struct FogMap {
+    tracker: TileTracker,
+    tiles: FogTiles,
+}
+
+#[derive(Default)]
+struct FogTiles(AHashMap<Player, PlayerFog>);
+
+#[derive(Default)]
+struct PlayerFog {
+    /// Number of entities seeing each visible tile.
+    visible: AHashMap<IVec2, u32>,
+    explored: AHashSet<IVec2>,
+}
+
+impl FogMap {
+    /// Returns the tile containing a given point of the map.
+    pub fn tile(&self, point: Vec2) -> IVec2 {
+        self.tracker.tile(point)
+    }
+
+    /// Returns true if the tile is currently seen by an active entity of the
+    /// player.
+    pub fn is_visible(&self, player: Player, tile: IVec2) -> bool {
+        self.tiles
+            .0
+            .get(&player)
+            .is_some_and(|fog| fog.visible.contains_key(&tile))
+    }
+
+    /// Returns true if the tile has been seen by the player at any point.
+    pub fn is_explored(&self, player: Player, tile: IVec2) -> bool {
+        self.tiles
+            .0
+            .get(&player)
+            .is_some_and(|fog| fog.explored.contains(&tile))
+    }
+
+    /// Returns center of the tile nearest to a point which has not been
+    /// explored by the player yet. The returned point is clamped to the
+    /// bounds. None is returned if all tiles within the bounds are explored.
+    pub fn nearest_unexplored(&self, player: Player, point: Vec2, bounds: &Aabb) -> Option<Vec2> {
+        let mins = Vec2::from(bounds.mins);
+        let maxs = Vec2::from(bounds.maxs);
+        let min_tile = self.tile(mins);
+        let max_tile = self.tile(maxs);
+        let start = self.tile(point).clamp(min_tile, max_tile);
+        let start_distance = point.distance(self.center(start));
+        let max_radius = (start - min_tile).max(max_tile - start).max_element();
+
+        let mut nearest: Option<(f32, Vec2)> = None;
+        for radius in 0..=max_radius {
+            // Tile centers in the ring are at least this far from the point.
+            let ring_distance = radius as f32 * self.tracker.tile_size() - start_distance;
+            if nearest.is_some_and(|(distance, _)| distance < ring_distance) {
+                break;
+            }
+
+            for tile in ring(start, radius) {
+                if tile.cmplt(min_tile).any()
+                    || tile.cmpgt(max_tile).any()
+                    || self.is_explored(player, tile)
+                {
+                    continue;
+                }
+
+                let center = self.center(tile).clamp(mins, maxs);
+                let distance = point.distance(center);
+                if nearest.map_or(true, |(best, _)| distance < best) {
+                    nearest = Some((distance, center));
+                }
+            }
+        }
+
+        nearest.map(|(_, center)| center)
+    }
+
+    fn center(&self, tile: IVec2) -> Vec2 {
+        (tile.as_vec2() + 0.5) * self.tracker.tile_size()
+    }
+}
+
+impl TileLayer for FogTiles {
+    fn insert(&mut self, player: Player, tile: IVec2, tile_size: f32) {
+        let fog = self.0.entry(player).or_default();
+        for tile in vision(tile, tile_size) {
+            *fog.visible.entry(tile).or_default() += 1;
+            fog.explored.insert(tile);
+        }
+    }
+
+    fn remove(&mut self, player: Player, tile: IVec2, tile_size: f32) {
+        let Some(fog) = self.0.get_mut(&player) else {
+            return;
+        };
+        for tile in vision(tile, tile_size) {
+            let Some(count) = fog.visible.get_mut(&tile) else {
+                continue;
+            };
+            *count -= 1;
+            if *count == 0 {
+                fog.visible.remove(&tile);
+            }
+        }
+    }
+
+    /// Explored tiles are converted to the new tile size. A new tile is
+    /// explored if it overlaps any explored old tile.
+    fn rescale(&mut self, old: f32, new: f32) {
+        for fog in self.0.values_mut() {
+            fog.explored = fog
+                .explored
+                .iter()
+                .flat_map(|&tile| {
+                    let start = (tile.as_vec2() * old / new).floor().as_ivec2();
+                    let stop = ((tile + IVec2::ONE).as_vec2() * old / new)
+                        .ceil()
+                        .as_ivec2();
+                    (start.x..stop.x)
+                        .flat_map(move |x| (start.y..stop.y).map(move |y| IVec2::new(x, y)))
+                })
+                .collect();
+        }
+    }
+}
+
+/// Returns all tiles seen by an entity at a given tile.
+fn vision(center: IVec2, tile_size: f32) -> impl Iterator<Item = IVec2> {
+    let radius = (VISION_DISTANCE / tile_size).floor() as i32;
+    (-radius..=radius)
+        .flat_map(move |x| (-radius..=radius).map(move |y| IVec2::new(x, y)))
+        .filter(move |offset| (offset.as_vec2() * tile_size).length() <= VISION_DISTANCE)
+        .map(move |offset| center + offset)
+}
+
+/// Returns all tiles whose Chebyshev distance from the center is equal to
+/// the radius, i.e. the center for zero radius and the `8 * radius` tiles on
+/// the perimeter of the square otherwise.
+fn ring(center: IVec2, radius: i32) -> impl Iterator<Item = IVec2> {
+    // Each side yields 2 * radius tiles starting at its corner, thus the
+    // sides are empty for zero radius.
+    let top = (-radius..radius).map(move |x| IVec2::new(x, -radius));
+    let right = (-radius..radius).map(move |y| IVec2::new(radius, y));
+    let bottom = (-radius..radius).map(move |x| IVec2::new(-x, radius));
+    let left = (-radius..radius).map(move |y| IVec2::new(-radius, -y));
+
+    (radius == 0)
+        .then_some(IVec2::ZERO)
+        .into_iter()
+        .chain(top)
+        .chain(right)
+        .chain(bottom)
+        .chain(left)
+        .map(move |offset| center + offset)
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<FogMap>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<FogMap>();
+}
+
+fn update(mut map: ResMut<FogMap>, mut changes: TileChanges) {
+    let map = &mut *map;
+    changes.sync(&mut map.tracker, &mut map.tiles);
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use parry3d::math::Isometry;
+
+    use super::*;
+    use crate::{precise::testing::cube, EntityIndex};
+
+    fn sync(map: &mut FogMap, index: &EntityIndex, entity: Entity, owner: Option<Player>) {
+        map.tracker.sync(&mut map.tiles, index, [entity], |_| owner);
+    }
+
+    #[test]
+    fn test_fog() {
+        let mut map = FogMap::default();
+        let mut index = EntityIndex::new();
+        let entity = Entity::from_raw(1);
+        let start = map.tile(Vec2::new(15., 15.));
+        index.insert(entity, cube(1., Vec3::new(15., 0., -15.)));
+        sync(&mut map, &index, entity, Some(Player::Player1));
+
+        for tile in [start, start + IVec2::new(4, 0), start + IVec2::new(2, -3)] {
+            assert!(map.is_visible(Player::Player1, tile));
+            assert!(map.is_explored(Player::Player1, tile));
+            assert!(!map.is_visible(Player::Player2, tile));
+        }
+        let far = start + IVec2::new(4, 4);
+        assert!(!map.is_visible(Player::Player1, far));
+        assert!(!map.is_explored(Player::Player1, far));
+
+        index
+            .update(entity, Isometry::translation(215., 0., -15.))
+            .unwrap();
+        sync(&mut map, &index, entity, Some(Player::Player1));
+        assert!(!map.is_visible(Player::Player1, start));
+        assert!(map.is_explored(Player::Player1, start));
+        let end = map.tile(Vec2::new(215., 15.));
+        assert!(map.is_visible(Player::Player1, end));
+
+        // Explored tiles are kept when the index tile size changes.
+        index.rebuild(20.);
+        sync(&mut map, &index, entity, Some(Player::Player1));
+        assert_eq!(map.tile(Vec2::new(215., 15.)), IVec2::new(10, 0));
+        assert!(map.is_visible(Player::Player1, IVec2::new(10, 0)));
+        assert!(map.is_visible(Player::Player1, IVec2::new(12, 0)));
+        assert!(!map.is_visible(Player::Player1, IVec2::new(13, 0)));
+        assert!(map.is_explored(Player::Player1, map.tile(Vec2::new(15., 15.))));
+        assert!(!map.is_explored(Player::Player1, map.tile(Vec2::new(15., 95.))));
+
+        sync(&mut map, &index, entity, None);
+        assert!(!map.is_visible(Player::Player1, IVec2::new(10, 0)));
+        assert!(map.is_explored(Player::Player1, IVec2::new(10, 0)));
+    }
+
+    #[test]
+    fn test_nearest_unexplored() {
+        let bounds = Aabb::new([-100., -100.].into(), [100., 100.].into());
+        let mut map = FogMap::default();
+        assert_eq!(
+            map.nearest_unexplored(Player::Player1, Vec2::new(12., 13.), &bounds),
+            Some(Vec2::new(15., 15.))
+        );
+
+        let mut index = EntityIndex::new();
+        let entity = Entity::from_raw(1);
+        index.insert(entity, cube(1., Vec3::new(15., 0., -15.)));
+        sync(&mut map, &index, entity, Some(Player::Player1));
+        // Tiles at offsets (±4, ±1), (±1, ±4) and (±3, ±3) are the nearest
+        // tiles outside of the vision radius.
+        assert_eq!(
+            map.nearest_unexplored(Player::Player1, Vec2::new(18., 17.), &bounds),
+            Some(Vec2::new(55., 25.))
+        );
+        assert_eq!(
+            map.nearest_unexplored(Player::Player1, Vec2::new(12., 17.), &bounds),
+            Some(Vec2::new(-25., 25.))
+        );
+        assert_eq!(
+            map.nearest_unexplored(Player::Player2, Vec2::new(15., 15.), &bounds),
+            Some(Vec2::new(15., 15.))
+        );
+
+        let small = Aabb::new([0., 0.].into(), [20., 20.].into());
+        assert_eq!(
+            map.nearest_unexplored(Player::Player1, Vec2::new(15., 15.), &small),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ring() {
+        let center = IVec2::new(3, -2);
+        assert_eq!(ring(center, 0).collect::<Vec<_>>(), vec![center]);
+
+        for radius in 1..5 {
+            let tiles: Vec<IVec2> = ring(center, radius).collect();
+            assert_eq!(tiles.len(), 8 * radius as usize);
+            let unique: AHashSet<IVec2> = tiles.iter().copied().collect();
+            assert_eq!(unique.len(), tiles.len());
+            for tile in tiles {
+                assert_eq!((tile - center).abs().max_element(), radius);
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! This module implements a per-player influence map. Each active entity
+//! contributes linearly decaying influence to tiles in its neighborhood. The
+//! map is usable for AI positioning or visualization of contested areas.
+//!
+//! The map is built from tiles of [`crate::EntityIndex`] and updated
+//! incrementally, see [`crate::tracker`].
+
+use ahash::AHashMap;
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, schedule::PostMovement, state::AppState};
+use de_types::player::Player;
+use glam::{IVec2, Vec2};
+
+use crate::{
+    tracker::{TileChanges, TileLayer, TileTracker},
+    PreciseIndexSet,
+};
+
+/// Distance (in tiles) at which influence of an entity drops to zero.
+const DECAY_RADIUS: i32 = 3;
+
+pub(crate) struct InfluencePlugin;
+
+impl Plugin for InfluencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                PostMovement,
+                update
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(InfluenceSet::Update)
+                    .after(PreciseIndexSet::Index),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, SystemSet)]
+pub enum InfluenceSet {
+    Update,
+}
+
+/// Per player map of influence of active entities.
+#[derive(Resource, Default)]
+pub struct InfluenceMap {
+    tracker: TileTracker,
+    tiles: InfluenceTiles,
+}
+
+/// Number of active entities of each player in each tile.
+#[derive(Default)]
+struct InfluenceTiles(AHashMap<Player, AHashMap<IVec2, u32>>);
+
+impl InfluenceMap {
+    /// Returns influence of a player at a given point of the map. The value is
+    /// a sum of contributions of all active entities of the player.
+    pub fn influence(&self, player: Player, point: Vec2) -> f32 {
+        let Some(tiles) = self.tiles.0.get(&player) else {
+            return 0.;
+        };
+
+        let center = self.tracker.tile(point);
+        let mut influence = 0.;
+        for x in -DECAY_RADIUS..=DECAY_RADIUS {
+            for y in -DECAY_RADIUS..=DECAY_RADIUS {
+                let offset = IVec2::new(x, y);
+                if let Some(&count) = tiles.get(&(center + offset)) {
+                    influence += count as f32 * decay(offset);
+                }
+            }
+        }
+        influence
+    }
+}
+
+impl TileLayer for InfluenceTiles {
+    fn insert(&mut self, player: Player, tile: IVec2, _tile_size: f32) {
+        *self.0.entry(player).or_default().entry(tile).or_default() += 1;
+    }
+
+    fn remove(&mut self, player: Player, tile: IVec2, _tile_size: f32) {
+        let Some(tiles) = self.0.get_mut(&player) else {
+            return;
+        };
+        if let Some(count) = tiles.get_mut(&tile) {
+            *count -= 1;
+            if *count == 0 {
+                tiles.remove(&tile);
+            }
+        }
+    }
+}
+
+/// Returns influence of an entity at a tile offset from the entity's tile.
+fn decay(offset: IVec2) -> f32 {
+    (1. - offset.as_vec2().length() / DECAY_RADIUS as f32).max(0.)
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<InfluenceMap>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<InfluenceMap>();
+}
+
+fn update(mut map: ResMut<InfluenceMap>, mut changes: TileChanges) {
+    let map = &mut *map;
+    changes.sync(&mut map.tracker, &mut map.tiles);
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use parry3d::math::Isometry;
+
+    use super::*;
+    use crate::{precise::testing::cube, EntityIndex};
+
+    #[test]
+    fn test_influence() {
+        let mut map = InfluenceMap::default();
+        let mut index = EntityIndex::new();
+        let entity = Entity::from_raw(1);
+        let sync = |map: &mut InfluenceMap, index: &EntityIndex, owner: Option<Player>| {
+            let map = &mut *map;
+            map.tracker.sync(&mut map.tiles, index, [entity], |_| owner);
+        };
+
+        index.insert(entity, cube(1., Vec3::new(15., 0., -15.)));
+        sync(&mut map, &index, Some(Player::Player1));
+
+        let peak = map.influence(Player::Player1, Vec2::new(12., 18.));
+        assert_eq!(peak, 1.);
+        let adjacent = map.influence(Player::Player1, Vec2::new(25., 15.));
+        assert!(adjacent > 0. && adjacent < peak);
+        let diagonal = map.influence(Player::Player1, Vec2::new(25., 25.));
+        assert!(diagonal > 0. && diagonal < adjacent);
+        assert_eq!(map.influence(Player::Player1, Vec2::new(45., 15.)), 0.);
+        assert_eq!(map.influence(Player::Player1, Vec2::new(-25., 15.)), 0.);
+        assert_eq!(map.influence(Player::Player2, Vec2::new(15., 15.)), 0.);
+
+        index
+            .update(entity, Isometry::translation(55., 0., -15.))
+            .unwrap();
+        sync(&mut map, &index, Some(Player::Player1));
+        assert_eq!(map.influence(Player::Player1, Vec2::new(15., 15.)), 0.);
+        assert_eq!(map.influence(Player::Player1, Vec2::new(55., 15.)), 1.);
+
+        // Given away entities count for the new owner only.
+        sync(&mut map, &index, Some(Player::Player2));
+        assert_eq!(map.influence(Player::Player1, Vec2::new(55., 15.)), 0.);
+        assert_eq!(map.influence(Player::Player2, Vec2::new(55., 15.)), 1.);
+
+        sync(&mut map, &index, None);
+        assert_eq!(map.influence(Player::Player2, Vec2::new(55., 15.)), 0.);
+        assert!(map.tiles.0[&Player::Player2].is_empty());
+    }
+}
@@ -2,22 +2,34 @@
 //! This crate implements spatial indexing and various spatial queries of game
 //! entities.
 
+mod fog;
+mod influence;
 mod precise;
+mod tracker;
 
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+use fog::FogPlugin;
+pub use fog::{FogMap, FogSet};
+use influence::InfluencePlugin;
+pub use influence::{InfluenceMap, InfluenceSet};
 use precise::PreciseIndexPlugin;
 pub use precise::{
-    ColliderWithCache, EntityIndex, LocalCollider, PreciseIndexSet, QueryCollider,
-    RayEntityIntersection, SpatialQuery,
+    CircleQueryCache, ColliderWithCache, EntityCluster, EntityIndex, IndexError,
+    IndexUpdateInterval, LocalCollider, PreciseIndexSet, QueryCollider, RayEntityIntersection,
+    SpatialQuery, SpatialSnapshot, TileRegions, TileSizeTuning, TileStats, Unindexed,
 };
 
-/// Size (in world-space) of a single square tile where entities are kept.
+/// Default size (in world-space) of a single square tile where entities are
+/// kept.
 const TILE_SIZE: f32 = 10.;
 
 pub struct IndexPluginGroup;
 
 impl PluginGroup for IndexPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(PreciseIndexPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(PreciseIndexPlugin)
+            .add(InfluencePlugin)
+            .add(FogPlugin)
     }
 }
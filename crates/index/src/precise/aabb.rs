@@ -19,7 +19,7 @@ impl<'a> AabbCandidates<'a> {
     pub(super) fn new(grid: &'a TileGrid, aabb: &Aabb) -> Self {
         Self {
             grid,
-            tiles: TileRange::from_aabb(aabb),
+            tiles: grid.tile_range(aabb),
             row: None,
             prev_row: AHashSet::new(),
             current_row: AHashSet::new(),
@@ -89,7 +89,7 @@ mod tests {
             Point::new(TILE_SIZE * 20., 0.5, TILE_SIZE * 20.2),
         );
 
-        let mut grid = TileGrid::new();
+        let mut grid = TileGrid::new(TILE_SIZE);
         grid.insert(entity_a, &aabb_a);
         grid.insert(entity_b, &aabb_b);
         grid.insert(entity_c, &aabb_c);
@@ -0,0 +1,74 @@
+//! This module implements optional memoization of circle queries issued via
+//! [`super::SpatialQuery::entities_in_circle`].
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use ahash::{AHashMap, AHashSet};
+use bevy::prelude::*;
+use glam::{IVec2, Vec2};
+
+/// Circle centers and radii are quantized to multiples of this distance (in
+/// meters) when used as cache keys.
+const KEY_QUANTUM: f32 = 0.01;
+
+/// Insert this resource to memoize results of circle queries. Identical
+/// queries (up to [`KEY_QUANTUM`]) are computed only once until the index
+/// changes. The cache is cleared whenever the index is updated, therefore it
+/// never returns stale results.
+#[derive(Resource, Default)]
+pub struct CircleQueryCache {
+    results: Mutex<AHashMap<CircleKey, AHashSet<Entity>>>,
+    hits: AtomicUsize,
+}
+
+impl CircleQueryCache {
+    /// Returns number of queries served from the cache since the cache was
+    /// created.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns a cached result of a query or computes (and caches) it with
+    /// `query`.
+    pub(super) fn get_or_insert(
+        &self,
+        center: Vec2,
+        radius: f32,
+        query: impl FnOnce() -> AHashSet<Entity>,
+    ) -> AHashSet<Entity> {
+        let key = CircleKey::new(center, radius);
+        if let Some(result) = self.results.lock().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return result.clone();
+        }
+
+        // The lock is not held during the query so that other systems are
+        // not blocked. Concurrently issued identical queries might be thus
+        // computed more than once.
+        let result = query();
+        self.results.lock().unwrap().insert(key, result.clone());
+        result
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.results.get_mut().unwrap().clear();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct CircleKey {
+    center: IVec2,
+    radius: i32,
+}
+
+impl CircleKey {
+    fn new(center: Vec2, radius: f32) -> Self {
+        Self {
+            center: (center / KEY_QUANTUM).round().as_ivec2(),
+            radius: (radius / KEY_QUANTUM).round() as i32,
+        }
+    }
+}
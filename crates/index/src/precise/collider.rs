@@ -17,6 +17,7 @@ pub trait ColliderWithCache {
 
 /// Entity collider with cached entity-space and world-space AABBs for fast
 /// query pre-filtering.
+#[derive(Clone)]
 pub struct LocalCollider {
     object_collider: ObjectCollider,
     /// World-space position of the collider.
@@ -3,7 +3,7 @@
 
 use ahash::{AHashMap, AHashSet};
 use bevy::prelude::Entity;
-use glam::IVec2;
+use glam::{IVec2, Vec2};
 use parry3d::bounding_volume::Aabb;
 
 use super::range::TileRange;
@@ -13,18 +13,53 @@ use super::range::TileRange;
 /// Only non-empty sets are kept (a hash map mapping 2D tile coordinates to
 /// Entity sets is used under the hood). Each set contains entities whose
 /// absolute AABB intersects with the tile.
+#[derive(Clone)]
 pub(super) struct TileGrid {
+    tile_size: f32,
     tiles: AHashMap<IVec2, AHashSet<Entity>>,
 }
 
 impl TileGrid {
     /// Creates a new empty grid.
-    pub(super) fn new() -> Self {
+    ///
+    /// # Arguments
+    ///
+    /// * `tile_size` - size (in world-space) of a single square tile. It must
+    ///   be positive.
+    pub(super) fn new(tile_size: f32) -> Self {
+        debug_assert!(tile_size > 0.);
         Self {
+            tile_size,
             tiles: AHashMap::new(),
         }
     }
 
+    pub(super) fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+
+    /// Returns coordinates of the tile containing a point given in map
+    /// coordinates.
+    pub(super) fn tile(&self, point: Vec2) -> IVec2 {
+        (point / self.tile_size).floor().as_ivec2()
+    }
+
+    /// Returns the minimum tile range covering a world-space bounding box.
+    pub(super) fn tile_range(&self, aabb: &Aabb) -> TileRange {
+        TileRange::from_aabb(aabb, self.tile_size)
+    }
+
+    /// Returns occupancy statistics of the grid.
+    pub(super) fn stats(&self) -> TileStats {
+        let mut stats = TileStats::default();
+        for entities in self.tiles.values() {
+            stats.occupied += 1;
+            stats.entries += entities.len();
+            stats.max = stats.max.max(entities.len());
+        }
+        stats
+    }
+
     /// Inserts an entity to the grid.
     ///
     /// # Arguments
@@ -37,7 +72,7 @@ impl TileGrid {
     ///
     /// Might panic if the entity is already present in the grid.
     pub(super) fn insert(&mut self, entity: Entity, aabb: &Aabb) {
-        for tile in TileRange::from_aabb(aabb) {
+        for tile in self.tile_range(aabb) {
             self.insert_to_tile(entity, tile);
         }
     }
@@ -57,12 +92,13 @@ impl TileGrid {
     /// Might panic if the entity is not stored in the grid or if the last used
     /// update / insertion AABB differs from the one passed as an argument.
     pub(super) fn remove(&mut self, entity: Entity, aabb: &Aabb) {
-        for tile in TileRange::from_aabb(aabb) {
+        for tile in self.tile_range(aabb) {
             self.remove_from_tile(entity, tile);
         }
     }
 
-    /// Update bounding box of an entity.
+    /// Update bounding box of an entity. Returns true if the set of tiles
+    /// intersected by the entity has changed.
     ///
     /// # Arguments
     ///
@@ -77,13 +113,13 @@ impl TileGrid {
     ///
     /// Might panic if the entity is not present in the grid or if `old_aabb`
     /// differs from the last used update / insert AABB.
-    pub(super) fn update(&mut self, entity: Entity, old_aabb: &Aabb, new_aabb: &Aabb) {
-        let old_tiles = TileRange::from_aabb(old_aabb);
-        let new_tiles = TileRange::from_aabb(new_aabb);
+    pub(super) fn update(&mut self, entity: Entity, old_aabb: &Aabb, new_aabb: &Aabb) -> bool {
+        let old_tiles = self.tile_range(old_aabb);
+        let new_tiles = self.tile_range(new_aabb);
 
         // Most of the time entities move withing the some tile range.
         if old_tiles == new_tiles {
-            return;
+            return false;
         }
 
         let intersection = old_tiles.intersection(&new_tiles);
@@ -97,6 +133,8 @@ impl TileGrid {
                 self.insert_to_tile(entity, tile);
             }
         }
+
+        true
     }
 
     /// Returns entities intersecting a tile.
@@ -132,6 +170,37 @@ impl TileGrid {
     }
 }
 
+/// Occupancy statistics of non-empty tiles of the entity index, see
+/// [`crate::EntityIndex::tile_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TileStats {
+    occupied: usize,
+    entries: usize,
+    max: usize,
+}
+
+impl TileStats {
+    /// Number of tiles intersected by at least one entity.
+    pub fn occupied(&self) -> usize {
+        self.occupied
+    }
+
+    /// Maximum number of entities intersecting a single tile.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Mean number of entities intersecting an occupied tile. Zero is
+    /// returned if there are no occupied tiles.
+    pub fn mean(&self) -> f32 {
+        if self.occupied == 0 {
+            0.
+        } else {
+            self.entries as f32 / self.occupied as f32
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ahash::AHashSet;
@@ -142,7 +211,7 @@ mod tests {
 
     #[test]
     fn test_grid() {
-        let mut grid = TileGrid::new();
+        let mut grid = TileGrid::new(TILE_SIZE);
 
         let entity_a = Entity::from_raw(1);
         let aabb_a = Aabb::new(
@@ -233,7 +302,7 @@ mod tests {
             Point::new(-TILE_SIZE * 0.5, -100.5, -TILE_SIZE * 4.5),
             Point::new(TILE_SIZE * 1., 3.5, -TILE_SIZE * 3.5),
         );
-        let tiles: Vec<IVec2> = TileRange::from_aabb(&aabb).collect();
+        let tiles: Vec<IVec2> = TileRange::from_aabb(&aabb, TILE_SIZE).collect();
         assert_eq!(
             tiles,
             vec![
@@ -1,9 +1,14 @@
 //! This module contains implementation of spatial index of entities and
 //! various system parameters to retrieve entities based on spatial queries.
 
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    f32::consts::{FRAC_PI_2, FRAC_PI_4, PI},
+    ops::Deref,
+    sync::Arc,
+};
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use bevy::{
     ecs::{
         query::{ReadOnlyWorldQuery, WorldQuery},
@@ -11,34 +16,67 @@ use bevy::{
     },
     prelude::*,
 };
+use de_core::player::PlayerComponent;
+use de_types::{
+    player::Player,
+    projection::{ToAltitude, ToFlat},
+};
+use glam::{IVec2, Vec2};
+use parry2d::{bounding_volume::Aabb as Aabb2D, math::Point as Point2D, query::PointQuery};
 use parry3d::{
     bounding_volume::{Aabb, BoundingVolume},
-    math::{Isometry, Point},
-    query::Ray,
+    math::{Isometry, Point, Vector},
+    query::{PointQuery as _, Ray},
     shape::Segment,
 };
+use thiserror::Error;
 
 use super::{
-    aabb::AabbCandidates, collider::ColliderWithCache, collider::LocalCollider, grid::TileGrid,
+    aabb::AabbCandidates,
+    cache::CircleQueryCache,
+    collider::ColliderWithCache,
+    collider::LocalCollider,
+    grid::{TileGrid, TileStats},
+    regions::TileRegions,
     segment::SegmentCandidates,
 };
+use crate::TILE_SIZE;
+
+/// Altitude (above mean sea level) at which line of sight is tested, see
+/// [`SpatialQuery::has_line_of_sight`].
+const LINE_OF_SIGHT_ALTITUDE: f32 = 1.;
+/// Candidate flanking positions, given as angles (in radians) relative to the
+/// facing of the flanked target, see [`EntityIndex::flanking_position`]. The
+/// target's front is avoided.
+const FLANK_ANGLES: [f32; 5] = [FRAC_PI_2, 3. * FRAC_PI_4, PI, -3. * FRAC_PI_4, -FRAC_PI_2];
+/// Half of the apex angle of the cone of view of a threat, see
+/// [`EntityIndex::flanking_position`].
+const THREAT_CONE_HALF_ANGLE: f32 = FRAC_PI_4;
 
 /// 2D rectangular grid based spatial index of entities.
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct EntityIndex {
     grid: TileGrid,
     world_bounds: Aabb,
     colliders: AHashMap<Entity, LocalCollider>,
+    tile_changes: AHashSet<Entity>,
 }
 
 impl EntityIndex {
     /// Creates a new empty index.
     // Needs to be public because it is used in a benchmark.
     pub fn new() -> Self {
+        Self::with_tile_size(TILE_SIZE)
+    }
+
+    /// Creates a new empty index with a custom size (in world-space) of grid
+    /// tiles. The size must be positive.
+    pub fn with_tile_size(tile_size: f32) -> Self {
         Self {
-            grid: TileGrid::new(),
+            grid: TileGrid::new(tile_size),
             world_bounds: Aabb::new(Point::origin(), Point::origin()),
             colliders: AHashMap::new(),
+            tile_changes: AHashSet::new(),
         }
     }
 
@@ -49,26 +87,537 @@ impl EntityIndex {
         self.colliders.insert(entity, collider);
     }
 
-    pub(super) fn remove(&mut self, entity: Entity) {
+    /// Removes an entity from the index.
+    ///
+    /// Automatically indexed entities are removed once they are despawned.
+    /// This is meant only for entities inserted with [`Self::insert`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IndexError::EntityNotIndexed`] if the entity is not in the
+    /// index.
+    pub fn remove(&mut self, entity: Entity) -> Result<(), IndexError> {
         let collider = self
             .colliders
             .remove(&entity)
-            .expect("Tried to remove non-existent entity.");
+            .ok_or(IndexError::EntityNotIndexed(entity))?;
         self.grid.remove(entity, collider.world_aabb());
+        self.tile_changes.remove(&entity);
+        Ok(())
+    }
+
+    /// Same as [`Self::remove`] but errors are logged and otherwise ignored.
+    pub(crate) fn remove_or_ignore(&mut self, entity: Entity) {
+        if let Err(error) = self.remove(entity) {
+            warn!("Failed to remove entity from the index: {error}");
+        }
     }
 
-    pub(super) fn update(&mut self, entity: Entity, position: Isometry<f32>) {
+    /// Updates position of an already indexed entity.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IndexError::NonFinitePosition`] if the new position is not
+    /// finite and [`IndexError::EntityNotIndexed`] if the entity is not in the
+    /// index. The index is not modified in case of an error.
+    pub fn update(&mut self, entity: Entity, position: Isometry<f32>) -> Result<(), IndexError> {
+        if !position.translation.vector.iter().all(|v| v.is_finite())
+            || !position.rotation.coords.iter().all(|v| v.is_finite())
+        {
+            return Err(IndexError::NonFinitePosition(entity));
+        }
+
         let collider = self
             .colliders
             .get_mut(&entity)
-            .expect("Tried to update non-existent entity.");
+            .ok_or(IndexError::EntityNotIndexed(entity))?;
 
         let old_aabb = *collider.world_aabb();
         collider.update_position(position);
         let new_aabb = collider.world_aabb();
 
         self.world_bounds.merge(new_aabb);
-        self.grid.update(entity, &old_aabb, new_aabb);
+        if self.grid.update(entity, &old_aabb, new_aabb) {
+            self.tile_changes.insert(entity);
+        }
+        Ok(())
+    }
+
+    /// Same as [`Self::update`] but errors are logged and otherwise ignored.
+    pub(crate) fn update_or_ignore(&mut self, entity: Entity, position: Isometry<f32>) {
+        if let Err(error) = self.update(entity, position) {
+            warn!("Failed to update entity in the index: {error}");
+        }
+    }
+
+    /// Returns all entities whose set of intersected tiles changed due to an
+    /// update during the current frame, i.e. entities which crossed a tile
+    /// boundary.
+    pub fn tile_changes(&self) -> &AHashSet<Entity> {
+        &self.tile_changes
+    }
+
+    /// Clears the set of entities returned by [`Self::tile_changes`].
+    pub(super) fn clear_tile_changes(&mut self) {
+        self.tile_changes.clear();
+    }
+
+    /// Returns size (in world-space) of a single square tile of the grid.
+    pub fn tile_size(&self) -> f32 {
+        self.grid.tile_size()
+    }
+
+    /// Returns occupancy statistics of the grid tiles.
+    pub fn tile_stats(&self) -> TileStats {
+        self.grid.stats()
+    }
+
+    /// Returns all indexed entities, each exactly once, ordered by the tile
+    /// containing the center of their bounding box. Tiles are visited row by
+    /// row, thus entities from the same tile are visited consecutively and
+    /// spatially close entities tend to be visited shortly after each other.
+    ///
+    /// This is useful for cache friendly batch processing of entities which
+    /// interact with their neighbors.
+    pub fn entities_by_tile(&self) -> impl Iterator<Item = Entity> {
+        let mut entities: Vec<(IVec2, Entity)> = self
+            .colliders
+            .iter()
+            .map(|(&entity, collider)| (self.collider_tile(collider), entity))
+            .collect();
+        entities.sort_unstable_by_key(|&(tile, entity)| (tile.y, tile.x, entity));
+        entities.into_iter().map(|(_, entity)| entity)
+    }
+
+    /// Returns the tile containing the center of the map projected bounding
+    /// box of an entity (see [`Self::entities_by_tile`]) or None if the entity
+    /// is not in the index.
+    pub fn entity_tile(&self, entity: Entity) -> Option<IVec2> {
+        self.colliders
+            .get(&entity)
+            .map(|collider| self.collider_tile(collider))
+    }
+
+    fn collider_tile(&self, collider: &LocalCollider) -> IVec2 {
+        let center: Vec2 = collider.world_aabb().to_flat().center().into();
+        self.grid.tile(center)
+    }
+
+    /// Re-inserts all entities to a new grid with a different tile size. All
+    /// entities are reported by [`Self::tile_changes`] afterwards.
+    ///
+    /// The rebuild takes time linear to the number of indexed entities.
+    pub fn rebuild(&mut self, tile_size: f32) {
+        self.grid = TileGrid::new(tile_size);
+        for (&entity, collider) in self.colliders.iter() {
+            self.grid.insert(entity, collider.world_aabb());
+            self.tile_changes.insert(entity);
+        }
+    }
+
+    /// Returns an immutable copy of the current state of the index. See
+    /// [`SpatialSnapshot`].
+    ///
+    /// This is a full (deep) copy of the index: it allocates and takes time
+    /// linear to the number of indexed entities. Thus it should be created
+    /// sparingly and shared (by cloning the snapshot) rather than re-created.
+    pub fn to_snapshot(&self) -> SpatialSnapshot {
+        SpatialSnapshot(Arc::new(self.clone()))
+    }
+
+    /// Returns all entities whose map projected bounding box intersects a
+    /// circle on the map.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - center of the circle in map coordinates.
+    ///
+    /// * `radius` - radius of the circle. It must be non-negative.
+    pub fn entities_in_circle(&self, center: Vec2, radius: f32) -> AHashSet<Entity> {
+        debug_assert!(radius >= 0.);
+        self.circle_candidates(center, radius)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// Returns number of entities whose map projected bounding box intersects
+    /// a circle on the map. This is equivalent to (but cheaper than) the size
+    /// of [`Self::entities_in_circle`] because the entities are not collected.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - center of the circle in map coordinates.
+    ///
+    /// * `radius` - radius of the circle. It must be non-negative.
+    pub fn count_in_circle(&self, center: Vec2, radius: f32) -> usize {
+        debug_assert!(radius >= 0.);
+        self.circle_candidates(center, radius).count()
+    }
+
+    /// Returns all entities whose map projected bounding box distance from a
+    /// point on the map is between an inner and an outer radius (inclusive).
+    /// No entities are returned if the inner radius is larger than the outer
+    /// radius.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - center of the ring in map coordinates.
+    ///
+    /// * `inner` - inner radius of the ring. It must be non-negative. A zero
+    ///   inner radius is equivalent to [`Self::entities_in_circle`].
+    ///
+    /// * `outer` - outer radius of the ring. It must be non-negative.
+    pub fn entities_in_ring(&self, center: Vec2, inner: f32, outer: f32) -> AHashSet<Entity> {
+        debug_assert!(inner >= 0.);
+        debug_assert!(outer >= 0.);
+        if inner > outer {
+            return AHashSet::new();
+        }
+
+        self.circle_candidates(center, outer)
+            .filter_map(|(entity, distance)| (distance >= inner).then_some(entity))
+            .collect()
+    }
+
+    /// Returns entities around a point on the map bucketed by distance bands.
+    /// Distance of an entity is the distance of its map projected bounding
+    /// box from the point.
+    ///
+    /// The i-th bucket contains entities whose distance is in the half-open
+    /// interval `[edges[i], edges[i + 1])`, thus `edges.len() - 1` buckets
+    /// are returned (none if there are less than two edges).
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - center of the bands in map coordinates.
+    ///
+    /// * `edges` - non-negative band edges in non-decreasing order.
+    pub fn entities_in_bands(&self, center: Vec2, edges: &[f32]) -> Vec<AHashSet<Entity>> {
+        debug_assert!(edges.iter().all(|&edge| edge >= 0.));
+        debug_assert!(edges.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        if edges.len() < 2 {
+            return Vec::new();
+        }
+        let (first, last) = (edges[0], edges[edges.len() - 1]);
+        let mut buckets = vec![AHashSet::new(); edges.len() - 1];
+
+        for (entity, distance) in self.circle_candidates(center, last) {
+            if distance < first || distance >= last {
+                continue;
+            }
+            // Index of the first edge larger than the distance is at least 1
+            // because `first <= distance`.
+            let band = edges.partition_point(|&edge| edge <= distance) - 1;
+            buckets[band].insert(entity);
+        }
+        buckets
+    }
+
+    /// Returns all entities whose bounding box intersects a ball. Unlike
+    /// [`Self::entities_in_circle`], altitude of the entities is taken into
+    /// account.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - center of the ball in world coordinates.
+    ///
+    /// * `radius` - radius of the ball. It must be non-negative.
+    pub fn entities_in_sphere(&self, center: Vec3, radius: f32) -> AHashSet<Entity> {
+        debug_assert!(radius >= 0.);
+        let bounds = Aabb::new(Point::from(center - radius), Point::from(center + radius));
+        let center = Point::from(center);
+
+        self.query_aabb(&bounds)
+            .flatten()
+            .filter(|&entity| {
+                let aabb = self.get_collider(entity).world_aabb();
+                aabb.distance_to_local_point(&center, true) <= radius
+            })
+            .collect()
+    }
+
+    /// Returns up to `k` entities nearest to a point on the map, ordered by
+    /// increasing distance. Entities in `exclude` are skipped, i.e. less than
+    /// `k` entities are returned only if there are not enough other entities
+    /// in the index.
+    ///
+    /// Distance of an entity is the distance of its map projected bounding
+    /// box.
+    pub fn nearest_excluding(
+        &self,
+        point: Vec2,
+        k: usize,
+        exclude: &AHashSet<Entity>,
+    ) -> Vec<Entity> {
+        self.nearest_filtered(point, k, |entity| !exclude.contains(&entity))
+    }
+
+    /// Returns up to `k` entities nearest to a point on the map, ordered by
+    /// increasing distance. Entities for which `filter` returns false are
+    /// skipped, see [`Self::nearest_excluding`]. No entities are returned for
+    /// a non-finite point.
+    pub fn nearest_filtered(
+        &self,
+        point: Vec2,
+        k: usize,
+        filter: impl Fn(Entity) -> bool,
+    ) -> Vec<Entity> {
+        if k == 0 || self.colliders.is_empty() || !point.is_finite() {
+            return Vec::new();
+        }
+
+        // All indexed entities are within this distance from the point.
+        let max_radius = {
+            let bounds = self.world_bounds.to_flat();
+            let center: Vec2 = bounds.center().into();
+            let half_extents: Vec2 = bounds.half_extents().into();
+            ((point - center).abs() + half_extents).length()
+        };
+
+        let mut radius = self.tile_size();
+        loop {
+            let mut candidates: Vec<(Entity, f32)> = self
+                .circle_candidates(point, radius)
+                .filter(|&(entity, _)| filter(entity))
+                .collect();
+
+            // Entities further than the radius may be missing, therefore the
+            // search must continue unless there are enough candidates.
+            if candidates.len() >= k || radius >= max_radius || radius.is_infinite() {
+                candidates.sort_unstable_by(|(_, a), (_, b)| a.total_cmp(b));
+                return candidates
+                    .into_iter()
+                    .take(k)
+                    .map(|(entity, _)| entity)
+                    .collect();
+            }
+
+            radius *= 2.;
+        }
+    }
+
+    /// Returns all entities (together with their distance) whose map
+    /// projected bounding box is within `radius` from `center`.
+    fn circle_candidates(
+        &self,
+        center: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = (Entity, f32)> + '_ {
+        let bounds = Aabb2D::new(
+            Point2D::from(center - radius),
+            Point2D::from(center + radius),
+        )
+        .to_msl();
+        let center = Point2D::from(center);
+
+        self.query_aabb(&bounds)
+            .flatten()
+            .filter_map(move |entity| {
+                let aabb = self.get_collider(entity).world_aabb().to_flat();
+                let distance = aabb.distance_to_local_point(&center, true);
+                (distance <= radius).then_some((entity, distance))
+            })
+    }
+
+    /// Labels connected regions of passable tiles within a map area, see
+    /// [`TileRegions`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - the labeled area in map coordinates. All tiles
+    ///   intersecting the area are labeled.
+    ///
+    /// * `is_obstacle` - tiles intersecting the bounding box of an entity for
+    ///   which this returns true are impassable.
+    pub fn passable_regions(
+        &self,
+        bounds: &Aabb2D,
+        is_obstacle: impl Fn(Entity) -> bool,
+    ) -> TileRegions {
+        let start = self.grid.tile(bounds.mins.into());
+        let stop = self.grid.tile(bounds.maxs.into());
+        TileRegions::flood_fill(start, stop, self.tile_size(), |tile| {
+            self.grid.get_tile_entities(tile).map_or(false, |entities| {
+                entities.iter().any(|&entity| is_obstacle(entity))
+            })
+        })
+    }
+
+    /// Returns the densest cluster of entities, i.e. the tile with the
+    /// highest number of entities. None is returned if there is no matching
+    /// entity.
+    ///
+    /// Each entity is counted only in the tile containing the center of its
+    /// map projected bounding box.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - only entities for which this returns true are counted.
+    pub fn densest_cluster(&self, filter: impl Fn(Entity) -> bool) -> Option<EntityCluster> {
+        let mut tiles: AHashMap<IVec2, (usize, Vec2)> = AHashMap::new();
+        for (&entity, collider) in self.colliders.iter() {
+            if !filter(entity) {
+                continue;
+            }
+
+            let center: Vec2 = collider.world_aabb().to_flat().center().into();
+            let tile = self.grid.tile(center);
+            let (count, sum) = tiles.entry(tile).or_default();
+            *count += 1;
+            *sum += center;
+        }
+
+        tiles
+            .into_iter()
+            // Ties are broken by tile coordinates so that the result does not
+            // depend on hash map ordering.
+            .max_by(|(tile_a, (count_a, _)), (tile_b, (count_b, _))| {
+                count_a
+                    .cmp(count_b)
+                    .then_with(|| (tile_b.x, tile_b.y).cmp(&(tile_a.x, tile_a.y)))
+            })
+            .map(|(_, (count, sum))| EntityCluster::new(sum / count as f32, count))
+    }
+
+    /// Groups entities into density based clusters (DBSCAN). An entity is a
+    /// core entity if at least `min_pts` entities (itself included) are
+    /// within `eps` of the center of its map projected bounding box. Clusters
+    /// are formed by core entities within reach of each other together with
+    /// the non-core entities they reach. All other entities are noise.
+    ///
+    /// Returns the clusters and the noise entities. Entities within each
+    /// cluster as well as the noise entities are sorted, clusters are ordered
+    /// by the tile of their first visited entity, see
+    /// [`Self::entities_by_tile`].
+    ///
+    /// # Arguments
+    ///
+    /// * `eps` - neighborhood radius. It must be non-negative.
+    ///
+    /// * `min_pts` - minimum size of the neighborhood of a core entity.
+    pub fn cluster(&self, eps: f32, min_pts: usize) -> (Vec<Vec<Entity>>, Vec<Entity>) {
+        debug_assert!(eps >= 0.);
+
+        let neighbors = |entity: Entity| -> Vec<Entity> {
+            let center: Vec2 = self
+                .get_collider(entity)
+                .world_aabb()
+                .to_flat()
+                .center()
+                .into();
+            self.circle_candidates(center, eps)
+                .map(|(neighbor, _)| neighbor)
+                .collect()
+        };
+
+        // None marks noise, i.e. a visited entity which is not (yet) known
+        // to belong to any cluster.
+        let mut labels: AHashMap<Entity, Option<usize>> = AHashMap::new();
+        let mut clusters: Vec<Vec<Entity>> = Vec::new();
+
+        for entity in self.entities_by_tile() {
+            if labels.contains_key(&entity) {
+                continue;
+            }
+            let mut stack = neighbors(entity);
+            if stack.len() < min_pts {
+                labels.insert(entity, None);
+                continue;
+            }
+
+            let label = clusters.len();
+            let mut cluster = vec![entity];
+            labels.insert(entity, Some(label));
+
+            while let Some(neighbor) = stack.pop() {
+                match labels.get(&neighbor) {
+                    Some(Some(_)) => continue,
+                    // Noise reachable from a core entity is a border entity.
+                    Some(None) => {
+                        labels.insert(neighbor, Some(label));
+                        cluster.push(neighbor);
+                    }
+                    None => {
+                        labels.insert(neighbor, Some(label));
+                        cluster.push(neighbor);
+                        let reachable = neighbors(neighbor);
+                        if reachable.len() >= min_pts {
+                            stack.extend(reachable);
+                        }
+                    }
+                }
+            }
+
+            cluster.sort_unstable();
+            clusters.push(cluster);
+        }
+
+        let mut noise: Vec<Entity> = labels
+            .into_iter()
+            .filter_map(|(entity, label)| label.is_none().then_some(entity))
+            .collect();
+        noise.sort_unstable();
+        (clusters, noise)
+    }
+
+    /// Returns a position at the side or rear of a target which is outside
+    /// of the cones of view of all threats. The position closest to the unit
+    /// is returned among several candidates on a circle around the target.
+    ///
+    /// Facing of an entity is the +X axis of its collider. A cone of view
+    /// extends from the center of a threat up to `threat_range` along its
+    /// facing.
+    ///
+    /// None is returned if the target or any of the threats is not indexed or
+    /// if all candidate positions are in view of a threat.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - position of the flanking unit in map coordinates.
+    ///
+    /// * `target` - the flanked entity.
+    ///
+    /// * `threats` - entities which shall not see the flanking unit. These
+    ///   are typically enemies near the target retrieved from the index.
+    ///
+    /// * `distance` - distance of the flanking position from the center of
+    ///   the target. It must be non-negative.
+    ///
+    /// * `threat_range` - range of the cones of view of the threats. It must
+    ///   be non-negative.
+    pub fn flanking_position(
+        &self,
+        unit: Vec2,
+        target: Entity,
+        threats: &[Entity],
+        distance: f32,
+        threat_range: f32,
+    ) -> Option<Vec2> {
+        debug_assert!(distance >= 0.);
+        debug_assert!(threat_range >= 0.);
+
+        let (center, facing) = self.colliders.get(&target).map(flat_pose)?;
+        let threats: Vec<(Vec2, Vec2)> = threats
+            .iter()
+            .map(|threat| self.colliders.get(threat).map(flat_pose))
+            .collect::<Option<_>>()?;
+        let min_cos = THREAT_CONE_HALF_ANGLE.cos();
+
+        FLANK_ANGLES
+            .iter()
+            .map(|&angle| center + distance * Vec2::from_angle(angle).rotate(facing))
+            .filter(|&candidate| {
+                threats.iter().all(|&(threat, threat_facing)| {
+                    let offset = candidate - threat;
+                    let length = offset.length();
+                    length > threat_range || offset.dot(threat_facing) < min_cos * length
+                })
+            })
+            .min_by(|a, b| {
+                a.distance_squared(unit)
+                    .total_cmp(&b.distance_squared(unit))
+            })
     }
 
     /// Returns an iterator of potentially intersecting entities.
@@ -106,6 +655,66 @@ impl Default for EntityIndex {
     }
 }
 
+/// Returns the center of the map projected bounding box and the (normalized)
+/// map projected facing of a collider.
+fn flat_pose(collider: &LocalCollider) -> (Vec2, Vec2) {
+    let center: Vec2 = collider.world_aabb().to_flat().center().into();
+    let facing = collider.position().rotation * Vector::x();
+    let facing = Vec3::new(facing.x, facing.y, facing.z).to_flat();
+    (center, facing.normalize_or_zero())
+}
+
+/// Immutable copy of [`EntityIndex`] from a moment in the past, see
+/// [`EntityIndex::to_snapshot`].
+///
+/// Creating a snapshot copies the whole index, but the snapshot itself is
+/// cheap to clone and it may be sent to other threads, which makes it
+/// suitable for spatial queries done outside of the ECS schedule (e.g. in
+/// long running AI planning tasks). It is not affected by later changes of
+/// the index.
+#[derive(Clone)]
+pub struct SpatialSnapshot(Arc<EntityIndex>);
+
+impl Deref for SpatialSnapshot {
+    type Target = EntityIndex;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+/// A group of spatially close entities, see
+/// [`EntityIndex::densest_cluster`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityCluster {
+    centroid: Vec2,
+    count: usize,
+}
+
+impl EntityCluster {
+    fn new(centroid: Vec2, count: usize) -> Self {
+        Self { centroid, count }
+    }
+
+    /// Mean position (in map coordinates) of the clustered entities.
+    pub fn centroid(&self) -> Vec2 {
+        self.centroid
+    }
+
+    /// Number of entities in the cluster.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum IndexError {
+    #[error("entity {0:?} is not indexed")]
+    EntityNotIndexed(Entity),
+    #[error("position of entity {0:?} is not finite")]
+    NonFinitePosition(Entity),
+}
+
 /// System parameter implementing various spatial queries.
 ///
 /// Only entities automatically indexed by systems from
@@ -117,6 +726,7 @@ where
     F: ReadOnlyWorldQuery + Sync + Send + 'static,
 {
     index: Res<'w, EntityIndex>,
+    cache: Option<Res<'w, CircleQueryCache>>,
     entities: Query<'w, 's, Q, F>,
 }
 
@@ -174,15 +784,77 @@ where
         None
     }
 
+    /// Returns true if no queried entity, as indexed by
+    /// [`super::PreciseIndexPlugin`], blocks line of sight between two points
+    /// on the map.
+    ///
+    /// Line of sight is tested along a line segment placed
+    /// [`LINE_OF_SIGHT_ALTITUDE`] above mean sea level.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - start of the line of sight in map coordinates.
+    ///
+    /// * `to` - end of the line of sight in map coordinates.
+    ///
+    /// * `ignore` - entities which do not block line of sight. These
+    ///   typically include the observer and the observed entity.
+    pub fn has_line_of_sight(&self, from: Vec2, to: Vec2, ignore: &AHashSet<Entity>) -> bool {
+        let origin = from.to_altitude(LINE_OF_SIGHT_ALTITUDE);
+        let dir = to.to_altitude(LINE_OF_SIGHT_ALTITUDE) - origin;
+        let ray = Ray::new(origin.into(), dir.into());
+
+        let Some(candidate_sets) = self.index.cast_ray(&ray, 1.) else {
+            return true;
+        };
+
+        !candidate_sets.flatten().any(|candidate| {
+            !ignore.contains(&candidate)
+                && self.entities.contains(candidate)
+                && self
+                    .index
+                    .get_collider(candidate)
+                    .cast_ray(&ray, 1.)
+                    .is_some()
+        })
+    }
+
     /// Returns true if queried solid object on the map, as indexed by
     /// [`super::PreciseIndexPlugin`], intersects with the given collider.
-    pub fn collides(&self, collider: &impl ColliderWithCache) -> bool {
+    ///
+    /// # Arguments
+    ///
+    /// * `collider` - collider to be tested.
+    ///
+    /// * `ignore` - if not None, this entity is not included in the possible
+    ///   intersections.
+    pub fn collides(&self, collider: &impl ColliderWithCache, ignore: Option<Entity>) -> bool {
         let candidate_sets = self.index.query_aabb(collider.world_aabb());
-        candidate_sets.flatten().any(|candidate| {
-            self.entities.get(candidate).map_or(false, |_| {
-                self.index.get_collider(candidate).intersects(collider)
+        candidate_sets
+            .flatten()
+            .filter(|&candidate| ignore.map_or(true, |ignore| candidate != ignore))
+            .any(|candidate| {
+                self.entities.get(candidate).map_or(false, |_| {
+                    self.index.get_collider(candidate).intersects(collider)
+                })
             })
-        })
+    }
+
+    /// Returns all queried entities whose map projected bounding box
+    /// intersects a circle on the map, see
+    /// [`EntityIndex::entities_in_circle`].
+    ///
+    /// The unfiltered index result is memoized if [`CircleQueryCache`]
+    /// resource exists.
+    pub fn entities_in_circle(&self, center: Vec2, radius: f32) -> AHashSet<Entity> {
+        let mut entities = match self.cache {
+            Some(ref cache) => cache.get_or_insert(center, radius, || {
+                self.index.entities_in_circle(center, radius)
+            }),
+            None => self.index.entities_in_circle(center, radius),
+        };
+        entities.retain(|&entity| self.entities.contains(entity));
+        entities
     }
 
     pub fn query_aabb<'a, 'b>(
@@ -194,6 +866,40 @@ where
     }
 }
 
+impl<'w, 's, F> SpatialQuery<'w, 's, &'static PlayerComponent, F>
+where
+    F: ReadOnlyWorldQuery + Sync + Send + 'static,
+{
+    /// Returns up to `k` entities owned by a player nearest to a point on the
+    /// map, ordered by increasing distance. See
+    /// [`EntityIndex::nearest_excluding`].
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - a point on the map.
+    ///
+    /// * `k` - maximum number of returned entities.
+    ///
+    /// * `player` - only entities owned by this player are returned.
+    ///
+    /// * `predicate` - only entities for which this returns true are
+    ///   returned.
+    pub fn nearest_owned(
+        &self,
+        point: Vec2,
+        k: usize,
+        player: Player,
+        predicate: impl Fn(Entity) -> bool,
+    ) -> Vec<Entity> {
+        self.index.nearest_filtered(point, k, |entity| {
+            self.entities
+                .get(entity)
+                .map_or(false, |owner| **owner == player)
+                && predicate(entity)
+        })
+    }
+}
+
 pub struct RayEntityIntersection<T> {
     entity: Entity,
     toi: f32,
@@ -325,38 +1031,33 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
     use ahash::AHashSet;
-    use de_objects::ObjectCollider;
     use parry3d::{
         bounding_volume::Aabb,
         math::{Isometry, Point, Vector},
-        shape::{Cuboid, TriMesh, TriMeshFlags},
     };
 
     use super::*;
+    use crate::precise::testing::{cube, cuboid};
 
     #[test]
     fn test_entity_index() {
         let entity_a = Entity::from_raw(1);
-        let mut trimesh_a: TriMesh = Cuboid::new(Vector::new(1., 2., 3.)).into();
-        trimesh_a.set_flags(TriMeshFlags::ORIENTED).unwrap();
         let collider_a = LocalCollider::new(
-            ObjectCollider::from(trimesh_a),
+            cuboid(Vector::new(1., 2., 3.)),
             Isometry::new(Vector::new(7., 0., 0.), Vector::new(0., 0., 0.)),
         );
         let entity_b = Entity::from_raw(2);
-        let mut trimesh_b: TriMesh = Cuboid::new(Vector::new(2., 1., 2.)).into();
-        trimesh_b.set_flags(TriMeshFlags::ORIENTED).unwrap();
         let collider_b = LocalCollider::new(
-            ObjectCollider::from(trimesh_b),
+            cuboid(Vector::new(2., 1., 2.)),
             Isometry::new(Vector::new(7., 1000., 0.), Vector::new(0.1, 0., 0.)),
         );
         let position_b_2 = Isometry::new(Vector::new(7., 1000., -200.), Vector::new(0., 0., 0.));
         let entity_c = Entity::from_raw(3);
-        let mut trimesh_c: TriMesh = Cuboid::new(Vector::new(2., 1., 2.)).into();
-        trimesh_c.set_flags(TriMeshFlags::ORIENTED).unwrap();
         let collider_c = LocalCollider::new(
-            ObjectCollider::from(trimesh_c),
+            cuboid(Vector::new(2., 1., 2.)),
             Isometry::new(Vector::new(7., 1000., 1000.), Vector::new(0.1, 0., 0.)),
         );
 
@@ -378,7 +1079,7 @@ mod tests {
             index.cast_ray(&ray_a, 120.).unwrap().flatten().collect();
         assert_eq!(entities_a, AHashSet::from_iter(vec![entity_a, entity_b]));
 
-        index.update(entity_b, position_b_2);
+        index.update(entity_b, position_b_2).unwrap();
         assert_eq!(
             index.get_collider(entity_b).world_aabb(),
             &Aabb::new(Point::new(5., 999., -202.), Point::new(9., 1001., -198.))
@@ -387,7 +1088,7 @@ mod tests {
             index.cast_ray(&ray_a, 120.).unwrap().flatten().collect();
         assert_eq!(entities_b, AHashSet::from_iter(vec![entity_a]));
 
-        index.remove(entity_a);
+        index.remove(entity_a).unwrap();
         let entities_c: AHashSet<Entity> =
             index.cast_ray(&ray_a, 120.).unwrap().flatten().collect();
         assert_eq!(entities_c, AHashSet::new());
@@ -395,11 +1096,657 @@ mod tests {
         assert!(index.cast_ray(&ray_b, 120.).is_none());
     }
 
+    #[test]
+    fn test_snapshot() {
+        let collider = cuboid(Vector::new(1., 1., 1.));
+
+        let mut index = EntityIndex::new();
+        for i in 0..10 {
+            index.insert(
+                Entity::from_raw(i),
+                LocalCollider::new(
+                    collider.clone(),
+                    Isometry::translation(3. * i as f32, 0., -2.),
+                ),
+            );
+        }
+
+        let center = Vec2::new(8., 1.);
+        let expected = index.entities_in_circle(center, 6.);
+        let snapshot = index.to_snapshot();
+
+        index
+            .update(Entity::from_raw(3), Isometry::translation(100., 0., 0.))
+            .unwrap();
+        index.remove(Entity::from_raw(2)).unwrap();
+        index.insert(
+            Entity::from_raw(20),
+            LocalCollider::new(collider, Isometry::translation(8., 0., -1.)),
+        );
+        assert_ne!(index.entities_in_circle(center, 6.), expected);
+
+        let found = std::thread::spawn(move || snapshot.entities_in_circle(center, 6.))
+            .join()
+            .unwrap();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_entities_by_tile() {
+        let collider = cuboid(Vector::new(0.5, 0.5, 0.5));
+
+        let mut index = EntityIndex::with_tile_size(10.);
+        let mut tiles = AHashMap::new();
+        for i in 0..40 {
+            // Pseudo-random positions spread over a few tiles.
+            let x = ((i * 17) % 37) as f32 + 0.5;
+            let z = -(((i * 23) % 31) as f32 + 0.5);
+            let entity = Entity::from_raw(i);
+            index.insert(
+                entity,
+                LocalCollider::new(collider.clone(), Isometry::translation(x, 0., z)),
+            );
+            tiles.insert(entity, (Vec2::new(x, -z) / 10.).floor().as_ivec2());
+        }
+
+        for (&entity, &tile) in tiles.iter() {
+            assert_eq!(index.entity_tile(entity), Some(tile));
+        }
+        assert_eq!(index.entity_tile(Entity::from_raw(40)), None);
+
+        let visited: Vec<Entity> = index.entities_by_tile().collect();
+        assert_eq!(visited.len(), 40);
+        let unique: AHashSet<Entity> = visited.iter().copied().collect();
+        assert_eq!(unique.len(), 40);
+
+        // Once the iteration leaves a tile, it never returns to it.
+        let mut finished = AHashSet::new();
+        let mut current = tiles[&visited[0]];
+        for entity in visited {
+            let tile = tiles[&entity];
+            if tile != current {
+                assert!(finished.insert(current));
+                current = tile;
+            }
+            assert!(!finished.contains(&tile));
+        }
+    }
+
+    #[test]
+    fn test_tile_changes() {
+        let entity = Entity::from_raw(1);
+        let collider = cube(1., Vec3::new(4., 0., -4.));
+
+        let mut index = EntityIndex::new();
+        index.insert(entity, collider);
+        assert!(index.tile_changes().is_empty());
+
+        index
+            .update(entity, Isometry::translation(5., 0., -5.))
+            .unwrap();
+        assert!(index.tile_changes().is_empty());
+
+        index
+            .update(entity, Isometry::translation(15., 0., -5.))
+            .unwrap();
+        assert_eq!(index.tile_changes(), &AHashSet::from_iter([entity]));
+
+        index.clear_tile_changes();
+        assert!(index.tile_changes().is_empty());
+        index
+            .update(entity, Isometry::translation(15.5, 0., -4.5))
+            .unwrap();
+        assert!(index.tile_changes().is_empty());
+    }
+
+    #[test]
+    fn test_entities_in_circle() {
+        let mut index = EntityIndex::new();
+        for (i, x) in [0., 8., 20., 45.].iter().enumerate() {
+            let collider = cube(1., Vec3::new(*x, 0., -5.));
+            index.insert(Entity::from_raw(i as u32), collider);
+        }
+
+        let entities = index.entities_in_circle(Vec2::new(2., 5.), 7.);
+        assert_eq!(
+            entities,
+            AHashSet::from_iter(vec![Entity::from_raw(0), Entity::from_raw(1)])
+        );
+        let entities = index.entities_in_circle(Vec2::new(2., 5.), 18.);
+        assert_eq!(
+            entities,
+            AHashSet::from_iter(vec![
+                Entity::from_raw(0),
+                Entity::from_raw(1),
+                Entity::from_raw(2)
+            ])
+        );
+        assert!(index.entities_in_circle(Vec2::new(30., 30.), 5.).is_empty());
+    }
+
+    #[test]
+    fn test_count_in_circle() {
+        let collider = cuboid(Vector::new(1., 1., 1.));
+
+        let mut index = EntityIndex::new();
+        for i in 0..30 {
+            let x = ((i * 13) % 50) as f32;
+            let z = -(((i * 7) % 40) as f32);
+            index.insert(
+                Entity::from_raw(i),
+                LocalCollider::new(collider.clone(), Isometry::translation(x, 0., z)),
+            );
+        }
+
+        let center = Vec2::new(20., 15.);
+        for radius in [0., 1., 5., 12.5, 30., 100.] {
+            assert_eq!(
+                index.count_in_circle(center, radius),
+                index.entities_in_circle(center, radius).len()
+            );
+        }
+        assert_eq!(index.count_in_circle(center, 100.), 30);
+    }
+
+    #[test]
+    fn test_large_collider() {
+        let entity = Entity::from_raw(1);
+        // The bounding box spans tiles (0, 0), (1, 0), (0, 1) and (1, 1).
+        let collider = LocalCollider::new(
+            cuboid(Vector::new(8., 2., 8.)),
+            Isometry::translation(10., 0., -10.),
+        );
+
+        let mut index = EntityIndex::new();
+        index.insert(entity, collider);
+        for corner in [
+            Vec2::new(3., 3.),
+            Vec2::new(17., 3.),
+            Vec2::new(3., 17.),
+            Vec2::new(17., 17.),
+        ] {
+            assert_eq!(
+                index.entities_in_circle(corner, 0.5),
+                AHashSet::from_iter([entity])
+            );
+        }
+
+        // The entity is moved to span tiles (1, 0), (2, 0), (1, 1) and (2, 1).
+        index
+            .update(entity, Isometry::translation(20., 0., -10.))
+            .unwrap();
+        assert!(index.entities_in_circle(Vec2::new(3., 3.), 0.5).is_empty());
+        assert_eq!(
+            index.entities_in_circle(Vec2::new(27., 17.), 0.5),
+            AHashSet::from_iter([entity])
+        );
+
+        index.remove(entity).unwrap();
+        assert!(index
+            .entities_in_circle(Vec2::new(27., 17.), 0.5)
+            .is_empty());
+        assert!(index.entities_in_circle(Vec2::new(13., 7.), 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_entities_in_ring() {
+        let mut index = EntityIndex::new();
+        // Bounding boxes of the entities are 2, 7 and 12 meters from the
+        // origin.
+        for (i, x) in [3., 8., 13.].iter().enumerate() {
+            let collider = cube(1., Vec3::new(*x, 0., 0.));
+            index.insert(Entity::from_raw(i as u32), collider);
+        }
+
+        assert_eq!(
+            index.entities_in_ring(Vec2::ZERO, 5., 10.),
+            AHashSet::from_iter(vec![Entity::from_raw(1)])
+        );
+        assert_eq!(
+            index.entities_in_ring(Vec2::ZERO, 0., 10.),
+            index.entities_in_circle(Vec2::ZERO, 10.)
+        );
+        assert_eq!(
+            index.entities_in_ring(Vec2::ZERO, 7., 12.),
+            AHashSet::from_iter(vec![Entity::from_raw(1), Entity::from_raw(2)])
+        );
+        assert!(index.entities_in_ring(Vec2::ZERO, 10., 5.).is_empty());
+    }
+
+    #[test]
+    fn test_entities_in_bands() {
+        let mut index = EntityIndex::new();
+        // Bounding boxes of the entities are 1, 3 and 7 meters from the
+        // origin.
+        for (i, x) in [2., 4., 8.].iter().enumerate() {
+            let collider = cube(1., Vec3::new(*x, 0., 0.));
+            index.insert(Entity::from_raw(i as u32), collider);
+        }
+
+        let buckets = index.entities_in_bands(Vec2::ZERO, &[0., 2., 5., 10.]);
+        assert_eq!(
+            buckets,
+            vec![
+                AHashSet::from_iter([Entity::from_raw(0)]),
+                AHashSet::from_iter([Entity::from_raw(1)]),
+                AHashSet::from_iter([Entity::from_raw(2)]),
+            ]
+        );
+
+        let buckets = index.entities_in_bands(Vec2::ZERO, &[2., 3., 7.]);
+        assert_eq!(
+            buckets,
+            vec![AHashSet::new(), AHashSet::from_iter([Entity::from_raw(1)])]
+        );
+        assert!(index.entities_in_bands(Vec2::ZERO, &[5.]).is_empty());
+    }
+
+    #[test]
+    fn test_entities_in_sphere() {
+        let mut index = EntityIndex::new();
+        // The entities differ only in their altitude.
+        for (i, y) in [0., 30.].iter().enumerate() {
+            let collider = cube(1., Vec3::new(10., *y, -5.));
+            index.insert(Entity::from_raw(i as u32), collider);
+        }
+
+        assert_eq!(
+            index.entities_in_circle(Vec2::new(10., 5.), 3.),
+            AHashSet::from_iter(vec![Entity::from_raw(0), Entity::from_raw(1)])
+        );
+        assert_eq!(
+            index.entities_in_sphere(Vec3::new(10., 0., -5.), 3.),
+            AHashSet::from_iter(vec![Entity::from_raw(0)])
+        );
+        assert_eq!(
+            index.entities_in_sphere(Vec3::new(12., 32., -5.), 3.),
+            AHashSet::from_iter(vec![Entity::from_raw(1)])
+        );
+        assert_eq!(
+            index.entities_in_sphere(Vec3::new(10., 15., -5.), 20.),
+            AHashSet::from_iter(vec![Entity::from_raw(0), Entity::from_raw(1)])
+        );
+        assert!(index
+            .entities_in_sphere(Vec3::new(10., 15., -5.), 5.)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_nearest_excluding() {
+        let mut index = EntityIndex::new();
+        for (i, x) in [0., 8., 20., 45., 300.].iter().enumerate() {
+            let collider = cube(1., Vec3::new(*x, 0., -5.));
+            index.insert(Entity::from_raw(i as u32), collider);
+        }
+
+        let none = AHashSet::new();
+        assert_eq!(
+            index.nearest_excluding(Vec2::new(2., 5.), 2, &none),
+            vec![Entity::from_raw(0), Entity::from_raw(1)]
+        );
+
+        let exclude = AHashSet::from_iter(vec![Entity::from_raw(0), Entity::from_raw(1)]);
+        assert_eq!(
+            index.nearest_excluding(Vec2::new(2., 5.), 2, &exclude),
+            vec![Entity::from_raw(2), Entity::from_raw(3)]
+        );
+        assert_eq!(
+            index.nearest_excluding(Vec2::new(2., 5.), 10, &exclude),
+            vec![
+                Entity::from_raw(2),
+                Entity::from_raw(3),
+                Entity::from_raw(4)
+            ]
+        );
+        assert!(index
+            .nearest_excluding(Vec2::new(2., 5.), 0, &exclude)
+            .is_empty());
+        assert!(index
+            .nearest_excluding(Vec2::new(f32::NAN, 5.), 2, &none)
+            .is_empty());
+        assert!(index
+            .nearest_excluding(Vec2::new(2., f32::INFINITY), 2, &none)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_nearest_owned() {
+        #[derive(Resource)]
+        struct Results(Vec<Vec<Entity>>);
+
+        fn check(query: SpatialQuery<&PlayerComponent>, mut results: ResMut<Results>) {
+            let point = Vec2::new(0., 5.);
+            results
+                .0
+                .push(query.nearest_owned(point, 3, Player::Player1, |_| true));
+            results
+                .0
+                .push(query.nearest_owned(point, 2, Player::Player2, |entity| {
+                    entity != Entity::from_raw(2)
+                }));
+        }
+
+        let mut world = World::new();
+        let mut index = EntityIndex::new();
+        let owners = [
+            Player::Player1,
+            Player::Player2,
+            Player::Player2,
+            Player::Player1,
+            Player::Player2,
+            Player::Player1,
+        ];
+        for (i, (x, player)) in [0., 3., 8., 20., 45., 300.]
+            .into_iter()
+            .zip(owners)
+            .enumerate()
+        {
+            let entity = world.spawn(PlayerComponent::from(player)).id();
+            assert_eq!(entity, Entity::from_raw(i as u32));
+
+            let collider = cube(1., Vec3::new(x, 0., -5.));
+            index.insert(entity, collider);
+        }
+        world.insert_resource(index);
+        world.insert_resource(Results(Vec::new()));
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(check);
+        schedule.run(&mut world);
+
+        assert_eq!(
+            world.resource::<Results>().0,
+            vec![
+                vec![
+                    Entity::from_raw(0),
+                    Entity::from_raw(3),
+                    Entity::from_raw(5)
+                ],
+                vec![Entity::from_raw(1), Entity::from_raw(4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        #[derive(Resource)]
+        struct Results(Vec<bool>);
+
+        fn check(query: SpatialQuery<()>, mut results: ResMut<Results>) {
+            let ignore = AHashSet::new();
+            results.0.push(query.has_line_of_sight(
+                Vec2::new(-20., 0.),
+                Vec2::new(20., 0.),
+                &ignore,
+            ));
+            results.0.push(query.has_line_of_sight(
+                Vec2::new(-20., 10.),
+                Vec2::new(20., 10.),
+                &ignore,
+            ));
+            let ignore = AHashSet::from_iter(vec![Entity::from_raw(0)]);
+            results.0.push(query.has_line_of_sight(
+                Vec2::new(-20., 0.),
+                Vec2::new(20., 0.),
+                &ignore,
+            ));
+        }
+
+        let mut world = World::new();
+        let wall = world.spawn_empty().id();
+        assert_eq!(wall, Entity::from_raw(0));
+
+        let mut index = EntityIndex::new();
+        index.insert(
+            wall,
+            LocalCollider::new(cuboid(Vector::new(1., 5., 5.)), Isometry::identity()),
+        );
+        world.insert_resource(index);
+        world.insert_resource(Results(Vec::new()));
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(check);
+        schedule.run(&mut world);
+
+        assert_eq!(world.resource::<Results>().0, vec![false, true, true]);
+    }
+
+    #[test]
+    fn test_cast_ray_shape() {
+        #[derive(Resource)]
+        struct Results(Vec<Option<Entity>>);
+
+        fn check(query: SpatialQuery<()>, mut results: ResMut<Results>) {
+            // Both rays point downwards and are within the collider AABB but
+            // only the first one intersects the collider shape.
+            for origin in [Point::new(5., 10., -5.), Point::new(5., 10., 5.)] {
+                let ray = Ray::new(origin, Vector::new(0., -1., 0.));
+                results.0.push(
+                    query
+                        .cast_ray(&ray, f32::INFINITY, None)
+                        .map(|intersection| intersection.entity()),
+                );
+            }
+        }
+
+        let mut world = World::new();
+        let rod = world.spawn_empty().id();
+
+        // A thin rod placed diagonally, i.e. its AABB is much larger than the
+        // shape itself.
+        let collider = LocalCollider::new(
+            cuboid(Vector::new(10., 1., 0.2)),
+            Isometry::new(Vector::zeros(), Vector::new(0., FRAC_PI_4, 0.)),
+        );
+        assert!(collider
+            .world_aabb()
+            .contains_local_point(&Point::new(5., 0., 5.)));
+
+        let mut index = EntityIndex::new();
+        index.insert(rod, collider);
+        world.insert_resource(index);
+        world.insert_resource(Results(Vec::new()));
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(check);
+        schedule.run(&mut world);
+
+        assert_eq!(world.resource::<Results>().0, vec![Some(rod), None]);
+    }
+
+    #[test]
+    fn test_passable_regions() {
+        let mut index = EntityIndex::new();
+        let wall = Entity::from_raw(1);
+        index.insert(
+            wall,
+            LocalCollider::new(
+                cuboid(Vector::new(3., 1., 20.)),
+                Isometry::translation(15., 0., -15.),
+            ),
+        );
+
+        let bounds = Aabb2D::new(Point2D::new(0., 0.), Point2D::new(49., 29.));
+        let regions = index.passable_regions(&bounds, |entity| entity == wall);
+        assert_eq!(regions.region_count(), 2);
+        assert_ne!(
+            regions.region(Vec2::new(5., 5.)),
+            regions.region(Vec2::new(35., 5.))
+        );
+        assert_eq!(regions.region(Vec2::new(15., 25.)), None);
+
+        let regions = index.passable_regions(&bounds, |_| false);
+        assert_eq!(regions.region_count(), 1);
+    }
+
+    #[test]
+    fn test_densest_cluster() {
+        let mut index = EntityIndex::new();
+        assert!(index.densest_cluster(|_| true).is_none());
+
+        let positions = [
+            // A sparse group spanning multiple tiles.
+            (-45., 5.),
+            (-35., 15.),
+            (-25., 25.),
+            // The densest tile: x in [20, 30), y in [40, 50).
+            (21., -41.),
+            (23., -44.),
+            (28., -48.),
+            (25., -42.),
+            // A filtered out cluster.
+            (101., -1.),
+            (102., -2.),
+            (103., -3.),
+            (104., -4.),
+            (105., -5.),
+        ];
+        for (i, &(x, z)) in positions.iter().enumerate() {
+            index.insert(
+                Entity::from_raw(i as u32),
+                LocalCollider::new(
+                    cuboid(Vector::new(0.5, 1., 0.5)),
+                    Isometry::translation(x, 0., z),
+                ),
+            );
+        }
+
+        let cluster = index.densest_cluster(|entity| entity.index() < 7).unwrap();
+        assert_eq!(cluster.count(), 4);
+        assert_eq!(cluster.centroid(), Vec2::new(24.25, 43.75));
+
+        let cluster = index.densest_cluster(|_| true).unwrap();
+        assert_eq!(cluster.count(), 5);
+        assert_eq!(cluster.centroid(), Vec2::new(103., 3.));
+    }
+
+    #[test]
+    fn test_cluster() {
+        let mut index = EntityIndex::new();
+        assert_eq!(index.cluster(3., 3), (Vec::new(), Vec::new()));
+
+        let positions = [
+            // The first clump.
+            (0., 0.),
+            (2., 0.),
+            (0., -2.),
+            (2., -2.),
+            // The second clump, far away from the first one.
+            (100., -50.),
+            (101., -52.),
+            (103., -50.),
+            // An isolated outlier.
+            (50., -90.),
+        ];
+        for (i, &(x, z)) in positions.iter().enumerate() {
+            index.insert(
+                Entity::from_raw(i as u32),
+                LocalCollider::new(
+                    cuboid(Vector::new(0.5, 1., 0.5)),
+                    Isometry::translation(x, 0., z),
+                ),
+            );
+        }
+
+        let (mut clusters, noise) = index.cluster(3., 3);
+        clusters.sort_unstable();
+        assert_eq!(
+            clusters,
+            vec![
+                (0..4).map(Entity::from_raw).collect::<Vec<_>>(),
+                (4..7).map(Entity::from_raw).collect::<Vec<_>>(),
+            ]
+        );
+        assert_eq!(noise, vec![Entity::from_raw(7)]);
+
+        // The second clump is too small for denser clusters.
+        let (clusters, noise) = index.cluster(3., 4);
+        assert_eq!(
+            clusters,
+            vec![(0..4).map(Entity::from_raw).collect::<Vec<_>>()]
+        );
+        assert_eq!(noise, (4..8).map(Entity::from_raw).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_flanking_position() {
+        let mut index = EntityIndex::new();
+        let mut insert = |id: u32, position: Vec2, facing: f32| {
+            let entity = Entity::from_raw(id);
+            index.insert(
+                entity,
+                LocalCollider::new(
+                    cuboid(Vector::new(1., 1., 1.)),
+                    Isometry::new(
+                        Vector::new(position.x, 0., -position.y),
+                        Vector::new(0., facing, 0.),
+                    ),
+                ),
+            );
+            entity
+        };
+
+        // The target faces +X, the threat is in front of it (to the front
+        // left) and faces the target.
+        let target = insert(1, Vec2::ZERO, 0.);
+        let threat = insert(2, Vec2::new(10., 10.), -3. * FRAC_PI_4);
+        let unit = Vec2::new(5., 5.);
+
+        assert!(index
+            .flanking_position(unit, Entity::from_raw(3), &[], 8., 15.)
+            .is_none());
+
+        // Without threats, the closest side is chosen.
+        let position = index.flanking_position(unit, target, &[], 8., 15.).unwrap();
+        assert!(position.abs_diff_eq(Vec2::new(0., 8.), 1e-4));
+
+        // The left side is in view of the threat, the rear left is out of its
+        // range.
+        let position = index
+            .flanking_position(unit, target, &[threat], 8., 15.)
+            .unwrap();
+        assert!(position.abs_diff_eq(Vec2::new(-8., 8.) / 2f32.sqrt(), 1e-4));
+
+        // The whole surroundings of the target are in view.
+        assert!(index
+            .flanking_position(unit, target, &[threat], 8., 100.)
+            .is_none());
+    }
+
+    #[test]
+    fn test_entity_index_errors() {
+        let entity = Entity::from_raw(1);
+        let collider = LocalCollider::new(cuboid(Vector::new(1., 2., 3.)), Isometry::identity());
+
+        let mut index = EntityIndex::new();
+        assert_eq!(
+            index.update(entity, Isometry::translation(1., 0., 0.)),
+            Err(IndexError::EntityNotIndexed(entity))
+        );
+        assert_eq!(
+            index.remove(entity),
+            Err(IndexError::EntityNotIndexed(entity))
+        );
+
+        index.insert(entity, collider);
+        assert_eq!(
+            index.update(entity, Isometry::translation(f32::NAN, 0., 0.)),
+            Err(IndexError::NonFinitePosition(entity))
+        );
+        assert_eq!(
+            index.get_collider(entity).world_aabb(),
+            &Aabb::new(Point::new(-1., -2., -3.), Point::new(1., 2., 3.))
+        );
+        assert_eq!(index.remove(entity), Ok(()));
+        assert_eq!(
+            index.remove(entity),
+            Err(IndexError::EntityNotIndexed(entity))
+        );
+    }
+
     #[test]
     fn test_entity_collider() {
-        let mut trimesh: TriMesh = Cuboid::new(Vector::new(1., 2., 3.)).into();
-        trimesh.set_flags(TriMeshFlags::ORIENTED).unwrap();
-        let object_collider = ObjectCollider::from(trimesh);
+        let object_collider = cuboid(Vector::new(1., 2., 3.));
         let position_a = Isometry::new(Vector::new(7., 0., 0.), Vector::new(0., 0., 0.));
         let position_b = Isometry::new(Vector::new(9., 0., 0.), Vector::new(0., 0., 0.));
         let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(1., 0., 0.));
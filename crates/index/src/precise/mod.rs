@@ -2,12 +2,14 @@
 //! various geometry based lookup (for example ray casting).
 //!
 //! The core structure is a square tile grid which points to Bevy ECS entities.
-//! Newly spawned entities are automatically added, despawned entities removed
-//! and moved entities updated by systems added by [`PreciseIndexPlugin`].
+//! Newly spawned entities are automatically added, despawned (or dying)
+//! entities removed and moved entities updated by systems added by
+//! [`PreciseIndexPlugin`].
+use ahash::AHashSet;
 use bevy::prelude::*;
 use de_core::{
     gamestate::GameState,
-    objects::{MovableSolid, ObjectTypeComponent, StaticSolid},
+    objects::{Active, MovableSolid, ObjectTypeComponent, StaticSolid},
     schedule::PostMovement,
     state::AppState,
 };
@@ -15,16 +17,33 @@ use de_objects::SolidObjects;
 use parry3d::math::Isometry;
 
 pub use self::{
+    cache::CircleQueryCache,
     collider::{ColliderWithCache, LocalCollider, QueryCollider},
-    index::{EntityIndex, RayEntityIntersection, SpatialQuery},
+    grid::TileStats,
+    index::{
+        EntityCluster, EntityIndex, IndexError, RayEntityIntersection, SpatialQuery,
+        SpatialSnapshot,
+    },
+    regions::TileRegions,
 };
 
 mod aabb;
+mod cache;
 mod collider;
 mod grid;
 mod index;
 mod range;
+mod regions;
 mod segment;
+#[cfg(test)]
+pub(crate) mod testing;
+
+/// Tiles with more entities on average (counting only non-empty tiles) are
+/// considered overcrowded, see [`TileSizeTuning`].
+const MAX_MEAN_OCCUPANCY: f32 = 8.;
+/// Tiles with less entities on average (counting only non-empty tiles) are
+/// considered too sparse, see [`TileSizeTuning`].
+const MIN_MEAN_OCCUPANCY: f32 = 1.5;
 
 type SolidEntityQuery<'w, 's> = Query<
     'w,
@@ -32,6 +51,7 @@ type SolidEntityQuery<'w, 's> = Query<
     (Entity, &'static ObjectTypeComponent, &'static Transform),
     (
         Without<Indexed>,
+        Without<Unindexed>,
         Or<(With<StaticSolid>, With<MovableSolid>)>,
     ),
 >;
@@ -54,17 +74,34 @@ pub(super) struct PreciseIndexPlugin;
 
 impl Plugin for PreciseIndexPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), setup)
+        app.init_resource::<IndexUpdateInterval>()
+            .add_systems(OnEnter(AppState::InGame), setup)
             .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
                 PostUpdate,
-                (insert, remove)
+                (
+                    insert,
+                    remove,
+                    clear_circle_cache
+                        .run_if(resource_exists::<CircleQueryCache>())
+                        .after(insert)
+                        .after(remove),
+                )
                     .run_if(in_state(GameState::Playing))
                     .in_set(PreciseIndexSet::Index),
             )
             .add_systems(
                 PostMovement,
-                update
+                (
+                    clear_tile_changes.before(update),
+                    update.run_if(update_due),
+                    tune_tile_size
+                        .run_if(resource_exists::<TileSizeTuning>())
+                        .after(update),
+                    clear_circle_cache
+                        .run_if(resource_exists::<CircleQueryCache>())
+                        .after(tune_tile_size),
+                )
                     .run_if(in_state(GameState::Playing))
                     .in_set(PreciseIndexSet::Index),
             );
@@ -76,9 +113,112 @@ pub enum PreciseIndexSet {
     Index,
 }
 
+/// Positions of moved entities are updated in the index only once in this
+/// many frames. Entities moved during skipped frames are updated during the
+/// next update, thus the index lags behind by less than this many frames.
+///
+/// Values greater than 1 are useful on very large maps where re-indexing
+/// during every frame is expensive. Insertion and removal of entities is
+/// not affected.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct IndexUpdateInterval(u32);
+
+impl IndexUpdateInterval {
+    /// # Panics
+    ///
+    /// Panics if `frames` is zero.
+    pub fn new(frames: u32) -> Self {
+        assert!(frames > 0);
+        Self(frames)
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Default for IndexUpdateInterval {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Insert this resource to periodically adjust size of the index tiles to
+/// the density of indexed entities. Tiles are halved when overcrowded and
+/// doubled when too sparse.
+///
+/// The whole index is rebuilt whenever the tile size changes, see
+/// [`EntityIndex::rebuild`]. Density is sampled only once in a given number
+/// of frames so that the rebuilds are rare.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct TileSizeTuning {
+    min_size: f32,
+    max_size: f32,
+    frames: u32,
+}
+
+impl TileSizeTuning {
+    /// # Arguments
+    ///
+    /// * `min_size` - minimum tile size (in world-space).
+    ///
+    /// * `max_size` - maximum tile size (in world-space).
+    ///
+    /// * `frames` - tile density is sampled once in this many frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_size` is not positive, if `min_size` is larger than
+    /// `max_size` or if `frames` is zero.
+    pub fn new(min_size: f32, max_size: f32, frames: u32) -> Self {
+        assert!(min_size > 0.);
+        assert!(min_size <= max_size);
+        assert!(frames > 0);
+        Self {
+            min_size,
+            max_size,
+            frames,
+        }
+    }
+
+    pub fn min_size(&self) -> f32 {
+        self.min_size
+    }
+
+    pub fn max_size(&self) -> f32 {
+        self.max_size
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Returns a better tile size for the given tile statistics or None if
+    /// the current size is good enough.
+    fn tune(&self, tile_size: f32, stats: TileStats) -> Option<f32> {
+        let mean = stats.mean();
+        let new_size = if mean > MAX_MEAN_OCCUPANCY {
+            0.5 * tile_size
+        } else if stats.occupied() > 0 && mean < MIN_MEAN_OCCUPANCY {
+            2. * tile_size
+        } else {
+            return None;
+        };
+
+        let new_size = new_size.clamp(self.min_size, self.max_size);
+        (new_size != tile_size).then_some(new_size)
+    }
+}
+
 #[derive(Component)]
 struct Indexed;
 
+/// Solid entities with this component are not indexed. They are removed from
+/// the index when the component is inserted and inserted back when it is
+/// removed.
+#[derive(Component)]
+pub struct Unindexed;
+
 fn setup(mut commands: Commands) {
     commands.insert_resource(EntityIndex::new());
 }
@@ -110,18 +250,333 @@ fn insert(
     }
 }
 
-fn remove(mut index: ResMut<EntityIndex>, mut removed: RemovedComponents<Indexed>) {
+fn remove(
+    mut commands: Commands,
+    mut index: ResMut<EntityIndex>,
+    indexed: Query<(), With<Indexed>>,
+    unindexed: Query<Entity, (With<Indexed>, Added<Unindexed>)>,
+    mut retired: Local<AHashSet<Entity>>,
+    mut deactivated: RemovedComponents<Active>,
+    mut removed: RemovedComponents<Indexed>,
+) {
     for entity in removed.iter() {
-        index.remove(entity);
+        if !retired.remove(&entity) {
+            index.remove_or_ignore(entity);
+        }
+    }
+
+    // Dying entities are deactivated a frame before they are despawned. They
+    // must not be found by spatial queries in the meantime.
+    let deactivated = deactivated
+        .iter()
+        .filter(|&entity| indexed.contains(entity));
+    for entity in deactivated.chain(unindexed.iter()) {
+        index.remove_or_ignore(entity);
+        commands.entity(entity).remove::<Indexed>();
+        retired.insert(entity);
     }
 }
 
+/// Returns true once in every [`IndexUpdateInterval`] frames.
+fn update_due(interval: Res<IndexUpdateInterval>, mut remaining: Local<u32>) -> bool {
+    if *remaining == 0 {
+        *remaining = interval.frames() - 1;
+        true
+    } else {
+        *remaining -= 1;
+        false
+    }
+}
+
+fn clear_tile_changes(mut index: ResMut<EntityIndex>) {
+    index.clear_tile_changes();
+}
+
+fn tune_tile_size(
+    tuning: Res<TileSizeTuning>,
+    mut index: ResMut<EntityIndex>,
+    mut remaining: Local<u32>,
+) {
+    if *remaining > 0 {
+        *remaining -= 1;
+        return;
+    }
+    *remaining = tuning.frames() - 1;
+
+    if let Some(tile_size) = tuning.tune(index.tile_size(), index.tile_stats()) {
+        index.rebuild(tile_size);
+    }
+}
+
+/// Cached query results are invalidated whenever the index might have
+/// changed.
+fn clear_circle_cache(mut cache: ResMut<CircleQueryCache>) {
+    cache.clear();
+}
+
+/// [`Changed`] filter of [`MovedQuery`] is relative to the last run of the
+/// system, therefore entities moved during frames skipped due to
+/// [`IndexUpdateInterval`] are included.
 fn update(mut index: ResMut<EntityIndex>, moved: MovedQuery) {
     for (entity, transform) in moved.iter() {
         let position = Isometry::new(
             transform.translation.into(),
             transform.rotation.to_scaled_axis().into(),
         );
-        index.update(entity, position);
+        index.update_or_ignore(entity, position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_types::{
+        objects::{ActiveObjectType, ObjectType, UnitType},
+        projection::ToFlat,
+    };
+    use glam::Vec2;
+
+    use super::*;
+    use crate::{precise::testing::cube, TILE_SIZE};
+
+    #[test]
+    fn test_update_interval() {
+        let mut app = App::new();
+        app.insert_resource(EntityIndex::new())
+            .insert_resource(IndexUpdateInterval::new(2))
+            .add_systems(Update, update.run_if(update_due));
+
+        let entity = app
+            .world
+            .spawn((Indexed, Transform::from_xyz(0., 0., 0.)))
+            .id();
+        app.world
+            .resource_mut::<EntityIndex>()
+            .insert(entity, cube(1., Vec3::ZERO));
+
+        let is_moved = |app: &App| {
+            app.world
+                .resource::<EntityIndex>()
+                .entities_in_circle(Vec2::new(50., 0.), 2.)
+                .contains(&entity)
+        };
+
+        app.update();
+        assert!(!is_moved(&app));
+
+        app.world
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x = 50.;
+        app.update();
+        assert!(!is_moved(&app));
+        app.update();
+        assert!(is_moved(&app));
+    }
+
+    #[test]
+    fn test_remove_deactivated() {
+        let mut app = App::new();
+        app.insert_resource(EntityIndex::new())
+            .add_systems(Update, remove);
+
+        let entity = app
+            .world
+            .spawn((Indexed, Active, Transform::from_xyz(0., 0., 0.)))
+            .id();
+        app.world
+            .resource_mut::<EntityIndex>()
+            .insert(entity, cube(1., Vec3::ZERO));
+
+        let is_indexed = |app: &App| {
+            app.world
+                .resource::<EntityIndex>()
+                .entities_in_circle(Vec2::ZERO, 2.)
+                .contains(&entity)
+        };
+
+        app.update();
+        assert!(is_indexed(&app));
+
+        // Dying entities lose Active.
+        app.world.entity_mut(entity).remove::<Active>();
+        app.update();
+        assert!(!is_indexed(&app));
+        assert!(!app.world.entity(entity).contains::<Indexed>());
+
+        app.world.despawn(entity);
+        app.update();
+        assert!(!is_indexed(&app));
+    }
+
+    #[test]
+    fn test_unindexed() {
+        let mut app = App::new();
+        app.insert_resource(EntityIndex::new())
+            .add_systems(Update, remove);
+
+        let entity = app
+            .world
+            .spawn((
+                Indexed,
+                MovableSolid,
+                ObjectTypeComponent::from(ObjectType::Active(ActiveObjectType::Unit(
+                    UnitType::Attacker,
+                ))),
+                Transform::from_xyz(0., 0., 0.),
+            ))
+            .id();
+        app.world
+            .resource_mut::<EntityIndex>()
+            .insert(entity, cube(1., Vec3::ZERO));
+
+        let is_indexed = |app: &App| {
+            app.world
+                .resource::<EntityIndex>()
+                .entities_in_circle(Vec2::ZERO, 2.)
+                .contains(&entity)
+        };
+
+        app.update();
+        assert!(is_indexed(&app));
+
+        app.world.entity_mut(entity).insert(Unindexed);
+        app.update();
+        assert!(!is_indexed(&app));
+        assert!(!app.world.entity(entity).contains::<Indexed>());
+        app.update();
+        assert!(!is_indexed(&app));
+
+        // Unindexed entities are not inserted back until the component is
+        // removed.
+        let mut state = SystemState::<SolidEntityQuery>::new(&mut app.world);
+        assert!(state.get(&app.world).is_empty());
+        app.world.entity_mut(entity).remove::<Unindexed>();
+        assert_eq!(state.get(&app.world).single().0, entity);
+    }
+
+    #[test]
+    fn test_circle_query_cache() {
+        #[derive(Resource, Default)]
+        struct Results(Vec<AHashSet<Entity>>);
+
+        fn check(query: SpatialQuery<()>, mut results: ResMut<Results>) {
+            for _ in 0..2 {
+                results
+                    .0
+                    .push(query.entities_in_circle(Vec2::new(1., -1.), 3.));
+            }
+        }
+
+        let mut app = App::new();
+        app.insert_resource(EntityIndex::new())
+            .init_resource::<CircleQueryCache>()
+            .init_resource::<Results>()
+            .add_systems(Update, (check, clear_circle_cache.after(check)));
+
+        let entity = app.world.spawn_empty().id();
+        app.world
+            .resource_mut::<EntityIndex>()
+            .insert(entity, cube(0.5, Vec3::new(2., 0., 1.)));
+
+        app.update();
+        assert_eq!(app.world.resource::<CircleQueryCache>().hits(), 1);
+        app.update();
+        assert_eq!(app.world.resource::<CircleQueryCache>().hits(), 2);
+
+        let results = &app.world.resource::<Results>().0;
+        assert_eq!(results.len(), 4);
+        for result in results {
+            assert_eq!(result, &AHashSet::from_iter([entity]));
+        }
+    }
+
+    #[test]
+    fn test_circle_query_filter() {
+        #[derive(Resource, Default)]
+        struct Results(Vec<AHashSet<Entity>>);
+
+        fn check(query: SpatialQuery<(), With<Active>>, mut results: ResMut<Results>) {
+            results
+                .0
+                .push(query.entities_in_circle(Vec2::new(1., -1.), 3.));
+        }
+
+        let mut app = App::new();
+        app.insert_resource(EntityIndex::new())
+            .init_resource::<Results>()
+            .add_systems(Update, check);
+
+        let active = app.world.spawn(Active).id();
+        let inactive = app.world.spawn_empty().id();
+        let mut index = app.world.resource_mut::<EntityIndex>();
+        index.insert(active, cube(0.5, Vec3::new(2., 0., 1.)));
+        index.insert(inactive, cube(0.5, Vec3::new(1., 0., 1.)));
+
+        app.update();
+        assert_eq!(
+            app.world.resource::<Results>().0,
+            vec![AHashSet::from_iter([active])]
+        );
+    }
+
+    #[test]
+    fn test_tile_size_tuning() {
+        let mut app = App::new();
+        app.insert_resource(EntityIndex::new())
+            .insert_resource(TileSizeTuning::new(2., 40., 1))
+            .add_systems(Update, tune_tile_size);
+
+        // A dense cluster of small entities.
+        let mut entities = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let entity = app.world.spawn_empty().id();
+                let position = Vec3::new(0.3 + 0.5 * i as f32, 0., 0.3 + 0.5 * j as f32);
+                app.world
+                    .resource_mut::<EntityIndex>()
+                    .insert(entity, cube(0.1, position));
+                entities.push((entity, position.to_flat()));
+            }
+        }
+
+        let circles = [
+            (Vec2::new(2., -2.), 1.),
+            (Vec2::new(0., 0.), 2.5),
+            (Vec2::new(4., -4.), 0.5),
+            (Vec2::new(-20., 0.), 100.),
+        ];
+        let results = |app: &App| -> Vec<AHashSet<Entity>> {
+            let index = app.world.resource::<EntityIndex>();
+            circles
+                .iter()
+                .map(|&(center, radius)| index.entities_in_circle(center, radius))
+                .collect()
+        };
+
+        let before = results(&app);
+        for _ in 0..5 {
+            app.update();
+        }
+
+        let index = app.world.resource::<EntityIndex>();
+        assert!(index.tile_size() < TILE_SIZE);
+        assert!(index.tile_size() >= 2.);
+        assert_eq!(index.tile_changes().len(), entities.len());
+        assert_eq!(results(&app), before);
+
+        // Entities are 0.1 m from their center in each direction.
+        for (&(center, radius), found) in circles.iter().zip(before) {
+            let expected: AHashSet<Entity> = entities
+                .iter()
+                .filter(|(_, position)| {
+                    let closest = center.clamp(*position - 0.1, *position + 0.1);
+                    closest.distance(center) <= radius
+                })
+                .map(|&(entity, _)| entity)
+                .collect();
+            assert_eq!(found, expected);
+        }
     }
 }
@@ -2,8 +2,6 @@ use de_types::projection::ToFlat;
 use glam::{IVec2, Vec2};
 use parry3d::bounding_volume::Aabb;
 
-use crate::TILE_SIZE;
-
 /// Iterable rectangular range of tiles.
 ///
 /// The tiles are iterated row-by-row, for example: (1, 1) -> (2, 1) -> (1, 2)
@@ -21,12 +19,18 @@ impl TileRange {
     ///
     /// Tiles are assumed to be topologically closed. In other words, both
     /// touching and intersecting tiles are included in the range.
-    pub(super) fn from_aabb(aabb: &Aabb) -> Self {
+    ///
+    /// # Arguments
+    ///
+    /// * `aabb` - the covered bounding box.
+    ///
+    /// * `tile_size` - size (in world-space) of a single square tile.
+    pub(super) fn from_aabb(aabb: &Aabb, tile_size: f32) -> Self {
         let aabb = aabb.to_flat();
         let min_flat: Vec2 = aabb.mins.into();
         let max_flat: Vec2 = aabb.maxs.into();
-        let start = (min_flat / TILE_SIZE).floor().as_ivec2();
-        let stop = (max_flat / TILE_SIZE).floor().as_ivec2();
+        let start = (min_flat / tile_size).floor().as_ivec2();
+        let stop = (max_flat / tile_size).floor().as_ivec2();
         Self::new(start, stop)
     }
 
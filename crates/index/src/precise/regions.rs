@@ -0,0 +1,133 @@
+//! This module implements flood-fill based detection of connected regions of
+//! passable tiles.
+
+use std::collections::VecDeque;
+
+use glam::{IVec2, Vec2};
+
+/// Connected regions of passable tiles within a rectangular range of tiles.
+///
+/// Two passable tiles are connected if they share an edge. Each region has an
+/// ID in the range from 0 to [`Self::region_count`] (exclusive).
+pub struct TileRegions {
+    tile_size: f32,
+    start: IVec2,
+    size: IVec2,
+    regions: Vec<Option<u32>>,
+    count: u32,
+}
+
+impl TileRegions {
+    /// Labels all connected regions of passable tiles.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - inclusive tile coordinates of the range start.
+    ///
+    /// * `stop` - inclusive tile coordinates of the range end.
+    ///
+    /// * `tile_size` - size (in world-space) of a single square tile.
+    ///
+    /// * `is_wall` - returns true for impassable tiles.
+    pub(super) fn flood_fill(
+        start: IVec2,
+        stop: IVec2,
+        tile_size: f32,
+        is_wall: impl Fn(IVec2) -> bool,
+    ) -> Self {
+        let size = (stop - start + IVec2::ONE).max(IVec2::ZERO);
+        let mut labels = Self {
+            tile_size,
+            start,
+            size,
+            regions: vec![None; (size.x * size.y) as usize],
+            count: 0,
+        };
+
+        let mut walls = vec![false; labels.regions.len()];
+        for (i, wall) in walls.iter_mut().enumerate() {
+            *wall = is_wall(labels.tile(i));
+        }
+
+        let mut queue = VecDeque::new();
+        for seed in 0..labels.regions.len() {
+            if walls[seed] || labels.regions[seed].is_some() {
+                continue;
+            }
+
+            let region = labels.count;
+            labels.count += 1;
+
+            labels.regions[seed] = Some(region);
+            queue.push_back(labels.tile(seed));
+
+            while let Some(tile) = queue.pop_front() {
+                for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                    let neighbour = tile + offset;
+                    let Some(index) = labels.index(neighbour) else {
+                        continue;
+                    };
+                    if walls[index] || labels.regions[index].is_some() {
+                        continue;
+                    }
+
+                    labels.regions[index] = Some(region);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// Returns number of distinct regions.
+    pub fn region_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Returns ID of the region of the tile containing a point given in map
+    /// coordinates. None is returned for impassable tiles and for points out
+    /// of the labeled range.
+    pub fn region(&self, point: Vec2) -> Option<u32> {
+        let tile = (point / self.tile_size).floor().as_ivec2();
+        self.index(tile).and_then(|index| self.regions[index])
+    }
+
+    fn index(&self, tile: IVec2) -> Option<usize> {
+        let relative = tile - self.start;
+        if relative.cmplt(IVec2::ZERO).any() || relative.cmpge(self.size).any() {
+            None
+        } else {
+            Some((relative.y * self.size.x + relative.x) as usize)
+        }
+    }
+
+    fn tile(&self, index: usize) -> IVec2 {
+        let index = index as i32;
+        self.start + IVec2::new(index % self.size.x, index / self.size.x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_fill() {
+        // The wall splits the range to two regions.
+        let walls = [IVec2::new(2, 0), IVec2::new(2, 1), IVec2::new(2, 2)];
+        let regions = TileRegions::flood_fill(IVec2::new(0, 0), IVec2::new(4, 2), 10., |tile| {
+            walls.contains(&tile)
+        });
+
+        assert_eq!(regions.region_count(), 2);
+        let left = regions.region(Vec2::new(5., 25.)).unwrap();
+        let right = regions.region(Vec2::new(45., 5.)).unwrap();
+        assert_ne!(left, right);
+        assert_eq!(regions.region(Vec2::new(15., 5.)), Some(left));
+        assert_eq!(regions.region(Vec2::new(35., 25.)), Some(right));
+        assert_eq!(regions.region(Vec2::new(25., 15.)), None);
+        assert_eq!(regions.region(Vec2::new(55., 15.)), None);
+        assert_eq!(regions.region(Vec2::new(5., -5.)), None);
+    }
+}
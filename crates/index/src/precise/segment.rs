@@ -7,7 +7,6 @@ use glam::{IVec2, Vec2};
 use parry3d::shape::Segment;
 
 use super::grid::TileGrid;
-use crate::TILE_SIZE;
 
 /// An iterator over sets of entities from tiles intersecting a given line
 /// segment.
@@ -29,7 +28,7 @@ impl<'a> SegmentCandidates<'a> {
     pub(super) fn new(grid: &'a TileGrid, segment: Segment) -> Self {
         Self {
             grid,
-            tiles: TileIterator::new(segment),
+            tiles: TileIterator::new(segment, grid.tile_size()),
             encountered: None,
         }
     }
@@ -71,6 +70,7 @@ impl<'a> Iterator for SegmentCandidates<'a> {
 
 /// Iterator over tiles intersecting a line segment.
 struct TileIterator {
+    tile_size: f32,
     point: Vec2,
     stop: Vec2,
     last_tile: IVec2,
@@ -84,39 +84,42 @@ impl TileIterator {
     ///
     /// * `segment` - a 2D line segment is created from orthographic projection
     ///   of this 3D line segment onto the map surface.
-    fn new(segment: Segment) -> Self {
+    ///
+    /// * `tile_size` - size (in world-space) of a single square tile.
+    fn new(segment: Segment, tile_size: f32) -> Self {
         let mut point = segment.a.to_flat();
         let stop = segment.b.to_flat();
 
         if point != stop {
             // First tile might be duplicated if direction is negative along
             // any axis. The following code fixes the issue.
-            let next_point = Self::next_point(point, stop);
-            if (next_point / TILE_SIZE).floor() == (point / TILE_SIZE).floor() {
+            let next_point = Self::next_point(point, stop, tile_size);
+            if (next_point / tile_size).floor() == (point / tile_size).floor() {
                 point = next_point;
             }
         }
 
         Self {
+            tile_size,
             point,
             stop,
-            last_tile: (stop / TILE_SIZE).floor().as_ivec2(),
+            last_tile: (stop / tile_size).floor().as_ivec2(),
             finished: false,
         }
     }
 
-    fn next_point(point: Vec2, stop: Vec2) -> Vec2 {
+    fn next_point(point: Vec2, stop: Vec2, tile_size: f32) -> Vec2 {
         let dir = stop - point;
         debug_assert!(dir != Vec2::ZERO);
 
-        let current_tile_float = point / TILE_SIZE;
-        let next_tile_x = TILE_SIZE
+        let current_tile_float = point / tile_size;
+        let next_tile_x = tile_size
             * if dir.x >= 0. {
                 current_tile_float.x.floor() + 1.
             } else {
                 current_tile_float.x.ceil() - 1.
             };
-        let next_tile_y = TILE_SIZE
+        let next_tile_y = tile_size
             * if dir.y >= 0. {
                 current_tile_float.y.floor() + 1.
             } else {
@@ -158,11 +161,11 @@ impl Iterator for TileIterator {
             return None;
         }
 
-        let current_tile = (self.point / TILE_SIZE).floor().as_ivec2();
+        let current_tile = (self.point / self.tile_size).floor().as_ivec2();
         if current_tile == self.last_tile {
             self.finished = true;
         } else {
-            self.point = Self::next_point(self.point, self.stop);
+            self.point = Self::next_point(self.point, self.stop, self.tile_size);
         }
         Some(current_tile)
     }
@@ -173,6 +176,7 @@ mod tests {
     use parry3d::{bounding_volume::Aabb, math::Point, shape::Segment};
 
     use super::*;
+    use crate::TILE_SIZE;
 
     #[test]
     fn test_segment_candidates() {
@@ -188,7 +192,7 @@ mod tests {
             Point::new(-TILE_SIZE * 0.6, 3.5, -TILE_SIZE * 3.2),
         );
 
-        let mut grid = TileGrid::new();
+        let mut grid = TileGrid::new(TILE_SIZE);
         grid.insert(entity_a, &aabb_a);
         grid.insert(entity_b, &aabb_b);
 
@@ -215,7 +219,7 @@ mod tests {
             Point::new(-2. * TILE_SIZE, 0., 3.1 * TILE_SIZE),
         );
 
-        let tiles: Vec<IVec2> = TileIterator::new(xy).collect();
+        let tiles: Vec<IVec2> = TileIterator::new(xy, TILE_SIZE).collect();
         assert_eq!(
             tiles,
             vec![
@@ -232,7 +236,7 @@ mod tests {
             ]
         );
 
-        let tiles_neg: Vec<IVec2> = TileIterator::new(xy_neg).collect();
+        let tiles_neg: Vec<IVec2> = TileIterator::new(xy_neg, TILE_SIZE).collect();
         assert_eq!(
             tiles_neg,
             vec![
@@ -256,14 +260,14 @@ mod tests {
             Point::new(1.1 * TILE_SIZE, 0., -3.1 * TILE_SIZE),
             Point::new(1.2 * TILE_SIZE, 0., -3.1 * TILE_SIZE),
         );
-        let tiles_short: Vec<IVec2> = TileIterator::new(short).collect();
+        let tiles_short: Vec<IVec2> = TileIterator::new(short, TILE_SIZE).collect();
         assert_eq!(tiles_short, vec![IVec2::new(1, 3)]);
 
         let empty = Segment::new(
             Point::new(0.1 * TILE_SIZE, 0., -3.1 * TILE_SIZE),
             Point::new(0.1 * TILE_SIZE, 0., -3.1 * TILE_SIZE),
         );
-        let tiles_empty: Vec<IVec2> = TileIterator::new(empty).collect();
+        let tiles_empty: Vec<IVec2> = TileIterator::new(empty, TILE_SIZE).collect();
         assert_eq!(tiles_empty, vec![IVec2::new(0, 3)]);
     }
 }
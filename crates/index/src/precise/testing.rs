@@ -0,0 +1,25 @@
+//! Collider constructors shared by tests of this module.
+
+use de_objects::ObjectCollider;
+use glam::Vec3;
+use parry3d::{
+    math::{Isometry, Vector},
+    shape::{Cuboid, TriMesh, TriMeshFlags},
+};
+
+use super::LocalCollider;
+
+/// Returns a collider of a box centered at the origin.
+pub(crate) fn cuboid(half_extents: Vector<f32>) -> ObjectCollider {
+    let mut trimesh: TriMesh = Cuboid::new(half_extents).into();
+    trimesh.set_flags(TriMeshFlags::ORIENTED).unwrap();
+    ObjectCollider::from(trimesh)
+}
+
+/// Returns a collider of an axis aligned cube centered at `position`.
+pub(crate) fn cube(half_extent: f32, position: Vec3) -> LocalCollider {
+    LocalCollider::new(
+        cuboid(Vector::repeat(half_extent)),
+        Isometry::translation(position.x, position.y, position.z),
+    )
+}
@@ -0,0 +1,241 @@
+//! This module implements tracking of owners and [`EntityIndex`] tiles of
+//! active entities. It is shared by per-player tile layers, for example
+//! [`crate::InfluenceMap`] and [`crate::FogMap`].
+//!
+//! The layers are updated incrementally: an entity is re-evaluated only when
+//! it crosses a tile boundary of the index, changes owner or is deactivated.
+//! An entity is tracked at the tile containing its center (see
+//! [`EntityIndex::entity_tile`]), which is thus updated once the entity's
+//! bounding box fully enters the new tile at the latest.
+//! Whenever the tile size of the index changes, all entities are removed from
+//! the layer and inserted back with the new tile size.
+
+use ahash::{AHashMap, AHashSet};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_core::{objects::Active, player::PlayerComponent};
+use de_types::player::Player;
+use glam::{IVec2, Vec2};
+
+use crate::{EntityIndex, TILE_SIZE};
+
+/// A per-player tile layer kept in sync with the index by [`TileTracker`].
+pub(crate) trait TileLayer {
+    /// An entity of the player started to occupy the tile.
+    fn insert(&mut self, player: Player, tile: IVec2, tile_size: f32);
+
+    /// An entity of the player, previously inserted to the tile, stopped
+    /// occupying it.
+    fn remove(&mut self, player: Player, tile: IVec2, tile_size: f32);
+
+    /// Tile size of the index changed. All entities are removed from the
+    /// layer before this is called and inserted back afterwards.
+    fn rescale(&mut self, _old: f32, _new: f32) {}
+}
+
+/// Owner and index tile of each tracked entity.
+pub(crate) struct TileTracker {
+    tile_size: f32,
+    entities: AHashMap<Entity, (Player, IVec2)>,
+    /// Active entities which are not in the index (yet). They are
+    /// re-evaluated during each sync.
+    pending: AHashSet<Entity>,
+}
+
+impl Default for TileTracker {
+    fn default() -> Self {
+        Self {
+            tile_size: TILE_SIZE,
+            entities: AHashMap::new(),
+            pending: AHashSet::new(),
+        }
+    }
+}
+
+impl TileTracker {
+    /// Size (in world-space) of tiles of the tracked entities.
+    pub(crate) fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+
+    /// Returns the tile containing a given point of the map.
+    pub(crate) fn tile(&self, point: Vec2) -> IVec2 {
+        (point / self.tile_size).floor().as_ivec2()
+    }
+
+    /// Brings the tracked entities and the layer up to date with the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - layer updated with all changes of the tracked entities.
+    ///
+    /// * `index` - the spatial index. Entities reported by
+    ///   [`EntityIndex::tile_changes`] are re-evaluated.
+    ///
+    /// * `changed` - other entities to be re-evaluated, e.g. entities which
+    ///   changed owner or were deactivated.
+    ///
+    /// * `owner` - returns the owner of an active entity or None if the
+    ///   entity is not active (or does not exist).
+    pub(crate) fn sync(
+        &mut self,
+        layer: &mut impl TileLayer,
+        index: &EntityIndex,
+        changed: impl IntoIterator<Item = Entity>,
+        owner: impl Fn(Entity) -> Option<Player>,
+    ) {
+        let old_size = self.tile_size;
+        let new_size = index.tile_size();
+        if old_size != new_size {
+            for (&entity, &(player, tile)) in self.entities.iter() {
+                layer.remove(player, tile, old_size);
+                self.pending.insert(entity);
+            }
+            self.entities.clear();
+            layer.rescale(old_size, new_size);
+            self.tile_size = new_size;
+        }
+
+        let candidates: Vec<Entity> = self
+            .pending
+            .drain()
+            .chain(index.tile_changes().iter().copied())
+            .chain(changed)
+            .collect();
+
+        for entity in candidates {
+            let new = owner(entity).map(|player| (player, index.entity_tile(entity)));
+            let old = match new {
+                Some((player, Some(tile))) => self.entities.insert(entity, (player, tile)),
+                Some((_, None)) => {
+                    self.pending.insert(entity);
+                    self.entities.remove(&entity)
+                }
+                None => {
+                    self.pending.remove(&entity);
+                    self.entities.remove(&entity)
+                }
+            };
+
+            let new = new.and_then(|(player, tile)| tile.map(|tile| (player, tile)));
+            if old == new {
+                continue;
+            }
+            if let Some((player, tile)) = old {
+                layer.remove(player, tile, new_size);
+            }
+            if let Some((player, tile)) = new {
+                layer.insert(player, tile, new_size);
+            }
+        }
+    }
+}
+
+/// System parameter used to sync a [`TileTracker`] with the index.
+#[derive(SystemParam)]
+pub(crate) struct TileChanges<'w, 's> {
+    index: Res<'w, EntityIndex>,
+    owners: Query<'w, 's, &'static PlayerComponent, With<Active>>,
+    owner_changes: Query<'w, 's, Entity, (With<Active>, Changed<PlayerComponent>)>,
+    deactivated: RemovedComponents<'w, 's, Active>,
+}
+
+impl<'w, 's> TileChanges<'w, 's> {
+    /// See [`TileTracker::sync`].
+    pub(crate) fn sync(&mut self, tracker: &mut TileTracker, layer: &mut impl TileLayer) {
+        let changed: Vec<Entity> = self
+            .owner_changes
+            .iter()
+            .chain(self.deactivated.iter())
+            .collect();
+        let owners = &self.owners;
+        tracker.sync(layer, &self.index, changed, |entity| {
+            owners.get(entity).ok().map(|&player| *player)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use parry3d::math::Isometry;
+
+    use super::*;
+    use crate::precise::testing::cube;
+
+    #[derive(Default)]
+    struct Log(Vec<(bool, Player, IVec2, f32)>);
+
+    impl TileLayer for Log {
+        fn insert(&mut self, player: Player, tile: IVec2, tile_size: f32) {
+            self.0.push((true, player, tile, tile_size));
+        }
+
+        fn remove(&mut self, player: Player, tile: IVec2, tile_size: f32) {
+            self.0.push((false, player, tile, tile_size));
+        }
+    }
+
+    #[test]
+    fn test_tracker() {
+        let entity = Entity::from_raw(1);
+        let mut index = EntityIndex::with_tile_size(10.);
+        let mut tracker = TileTracker::default();
+        let mut log = Log::default();
+
+        // Not indexed entities are kept pending.
+        tracker.sync(&mut log, &index, [entity], |_| Some(Player::Player1));
+        assert!(log.0.is_empty());
+
+        index.insert(entity, cube(1., Vec3::new(15., 0., -15.)));
+        tracker.sync(&mut log, &index, [], |_| Some(Player::Player1));
+        assert_eq!(
+            std::mem::take(&mut log.0),
+            vec![(true, Player::Player1, IVec2::new(1, 1), 10.)]
+        );
+
+        // Moves within a tile are ignored.
+        index
+            .update(entity, Isometry::translation(16., 0., -16.))
+            .unwrap();
+        tracker.sync(&mut log, &index, [], |_| Some(Player::Player1));
+        assert!(log.0.is_empty());
+
+        index
+            .update(entity, Isometry::translation(55., 0., -15.))
+            .unwrap();
+        tracker.sync(&mut log, &index, [], |_| Some(Player::Player1));
+        assert_eq!(
+            std::mem::take(&mut log.0),
+            vec![
+                (false, Player::Player1, IVec2::new(1, 1), 10.),
+                (true, Player::Player1, IVec2::new(5, 1), 10.)
+            ]
+        );
+
+        tracker.sync(&mut log, &index, [entity], |_| Some(Player::Player2));
+        assert_eq!(
+            std::mem::take(&mut log.0),
+            vec![
+                (false, Player::Player1, IVec2::new(5, 1), 10.),
+                (true, Player::Player2, IVec2::new(5, 1), 10.)
+            ]
+        );
+
+        index.rebuild(20.);
+        tracker.sync(&mut log, &index, [], |_| Some(Player::Player2));
+        assert_eq!(tracker.tile_size(), 20.);
+        assert_eq!(
+            std::mem::take(&mut log.0),
+            vec![
+                (false, Player::Player2, IVec2::new(5, 1), 10.),
+                (true, Player::Player2, IVec2::new(2, 0), 20.)
+            ]
+        );
+
+        tracker.sync(&mut log, &index, [entity], |_| None);
+        assert_eq!(
+            std::mem::take(&mut log.0),
+            vec![(false, Player::Player2, IVec2::new(2, 0), 20.)]
+        );
+    }
+}
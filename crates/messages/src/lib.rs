@@ -3,8 +3,8 @@
 
 pub use game::{FromGame, JoinError, Readiness, ToGame};
 pub use players::{
-    BorrowedFromPlayers, ChatMessage, ChatMessageError, EntityNet, FromPlayers, HealthDelta,
-    NetEntityIndex, NetProjectile, ToPlayers, MAX_CHAT_LEN,
+    BorrowedFromPlayers, ChatMessage, ChatMessageError, EnergyLevel, EntityNet, FromPlayers,
+    HealthDelta, NetEntityIndex, NetProjectile, ToPlayers, MAX_CHAT_LEN,
 };
 pub use server::{FromServer, GameOpenError, ToServer};
 
@@ -1,6 +1,9 @@
-use bincode::{Decode, Encode};
+use bincode::{de::Decoder, error::DecodeError, impl_borrow_decode, Decode, Encode};
 pub use chat::{ChatMessage, ChatMessageError, MAX_CHAT_LEN};
-use de_types::{objects::ActiveObjectType, player::Player};
+use de_types::{
+    objects::{ActiveObjectType, BuildingType},
+    player::Player,
+};
 pub use entity::{EntityNet, NetEntityIndex};
 pub use geom::{TransformNet, Vec2Net, Vec3Net, Vec4Net};
 pub use path::{PathError, PathNet};
@@ -89,6 +92,25 @@ pub enum ToPlayers {
     },
     /// Some kind of projectile was spawned (e.g. rocket, laser trail).
     Projectile(NetProjectile),
+    /// Transfer ownership of objects to another player. Simulation of the
+    /// objects moves to the computer of the new owner and the objects keep
+    /// their original entity IDs.
+    TransferOwnership {
+        entities: Vec<EntityNet>,
+        player: Player,
+    },
+    /// A building was placed on the map.
+    Build {
+        entity: EntityNet,
+        player: Player,
+        building_type: BuildingType,
+        transform: TransformNet,
+    },
+    /// Sets energy level of an entity's battery.
+    EnergyUpdate {
+        entity: EntityNet,
+        level: EnergyLevel,
+    },
 }
 
 #[derive(Debug, Encode, Decode)]
@@ -111,3 +133,60 @@ impl From<&HealthDelta> for f32 {
         delta.0
     }
 }
+
+/// Energy stored in a battery in joules.
+///
+/// The level is validated when decoded, thus a non-finite or negative level
+/// received over the network is rejected as a decoding error.
+#[derive(Debug, Encode)]
+pub struct EnergyLevel(f64);
+
+impl Decode for EnergyLevel {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Self::try_from(f64::decode(decoder)?).map_err(DecodeError::Other)
+    }
+}
+
+impl_borrow_decode!(EnergyLevel);
+
+impl TryFrom<f64> for EnergyLevel {
+    type Error = &'static str;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if !value.is_finite() {
+            Err("Got non-finite energy level.")
+        } else if value < 0. {
+            Err("Got negative energy level.")
+        } else {
+            Ok(Self(value))
+        }
+    }
+}
+
+impl From<&EnergyLevel> for f64 {
+    fn from(level: &EnergyLevel) -> f64 {
+        level.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::config;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_energy_level() {
+        let decode = |value: f64| {
+            let data = bincode::encode_to_vec(value, config::standard()).unwrap();
+            bincode::decode_from_slice::<EnergyLevel, _>(&data, config::standard())
+                .map(|(level, _)| f64::from(&level))
+        };
+
+        assert_eq!(decode(0.).unwrap(), 0.);
+        assert_eq!(decode(12.5).unwrap(), 12.5);
+        assert!(decode(-1.).is_err());
+        assert!(decode(f64::NAN).is_err());
+        assert!(decode(f64::INFINITY).is_err());
+    }
+}
@@ -7,6 +7,7 @@ use de_core::{
     schedule::{Movement, PreMovement},
     state::AppState,
 };
+use de_energy::{MovementSpeed, Throttle};
 use de_types::projection::ToAltitude;
 
 use crate::{
@@ -67,9 +68,9 @@ impl Kinematics {
         self.heading
     }
 
-    fn update_horizontal_speed(&mut self, delta: f32) {
+    fn update_horizontal_speed(&mut self, delta: f32, max_speed: f32) {
         debug_assert!(delta.is_finite());
-        self.horizontal_speed = (self.horizontal_speed + delta).clamp(0., MAX_H_SPEED);
+        self.horizontal_speed = (self.horizontal_speed + delta).clamp(0., max_speed);
     }
 
     fn update_vertical_speed(&mut self, delta: f32) {
@@ -104,20 +105,21 @@ fn setup_entities(mut commands: Commands, objects: Uninitialized) {
     }
 }
 
-fn kinematics(
-    time: Res<Time>,
-    mut objects: Query<(
-        &DesiredVelocity<RepulsionVelocity>,
-        &DesiredClimbing,
-        &mut Kinematics,
-        &mut ObjectVelocity,
-    )>,
-) {
+type KinematicsComponents<'a> = (
+    &'a DesiredVelocity<RepulsionVelocity>,
+    &'a DesiredClimbing,
+    Option<&'a Throttle>,
+    Option<&'a MovementSpeed>,
+    &'a mut Kinematics,
+    &'a mut ObjectVelocity,
+);
+
+fn kinematics(time: Res<Time>, mut objects: Query<KinematicsComponents>) {
     let time_delta = time.delta_seconds();
 
-    objects
-        .par_iter_mut()
-        .for_each_mut(|(movement, climbing, mut kinematics, mut velocity)| {
+    objects.par_iter_mut().for_each_mut(
+        |(movement, climbing, throttle, speed, mut kinematics, mut velocity)| {
+            let speed_limit = MAX_H_SPEED * speed.map_or(1., |speed| speed.factor());
             let desired_h_velocity = movement.velocity();
             let desired_heading = if desired_h_velocity == Vec2::ZERO {
                 kinematics.heading()
@@ -135,10 +137,12 @@ fn kinematics(
                 // Slow down if not going in roughly good direction.
                 -kinematics.horizontal_speed()
             } else {
-                desired_h_velocity.length() - kinematics.horizontal_speed()
+                // Units with depleted battery move slower or not at all.
+                let max_speed = speed_limit * throttle.map_or(1., |throttle| throttle.factor());
+                desired_h_velocity.length().min(max_speed) - kinematics.horizontal_speed()
             }
             .clamp(-max_h_speed_delta, max_h_speed_delta);
-            kinematics.update_horizontal_speed(h_speed_delta);
+            kinematics.update_horizontal_speed(h_speed_delta, speed_limit);
 
             let v_speed_delta = (climbing.speed() - kinematics.vertical_speed()).clamp(
                 -time_delta * G_ACCELERATION,
@@ -147,7 +151,8 @@ fn kinematics(
             kinematics.update_vertical_speed(v_speed_delta);
 
             velocity.update(kinematics.compute_velocity(), kinematics.heading());
-        });
+        },
+    );
 }
 
 fn normalize_angle(mut angle: f32) -> f32 {
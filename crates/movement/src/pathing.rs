@@ -4,6 +4,7 @@ use de_core::{
     schedule::{Movement, PreMovement},
     state::AppState,
 };
+use de_energy::MovementSpeed;
 use de_pathing::ScheduledPath;
 use de_types::projection::ToFlat;
 
@@ -63,17 +64,19 @@ fn follow_path(
     mut objects: Query<(
         &Transform,
         &mut ScheduledPath,
+        Option<&MovementSpeed>,
         &mut DesiredVelocity<PathVelocity>,
     )>,
 ) {
     objects
         .par_iter_mut()
-        .for_each_mut(|(transform, mut path, mut movement)| {
+        .for_each_mut(|(transform, mut path, speed, mut movement)| {
             let location = transform.translation.to_flat();
             let remaining = path.destination().distance(location);
             let advancement = path.advance(location, MAX_H_SPEED * 0.5);
             let direction = (advancement - location).normalize();
-            let desired_speed = MAX_H_SPEED.min((2. * remaining * MAX_H_ACCELERATION).sqrt());
+            let max_speed = MAX_H_SPEED * speed.map_or(1., |speed| speed.factor());
+            let desired_speed = max_speed.min((2. * remaining * MAX_H_ACCELERATION).sqrt());
             movement.update(desired_speed * direction);
         });
 }
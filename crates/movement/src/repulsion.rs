@@ -5,6 +5,7 @@ use de_core::{
     schedule::{Movement, PreMovement},
     state::AppState,
 };
+use de_energy::MovementSpeed;
 use de_map::size::MapBounds;
 use de_objects::{SolidObjects, EXCLUSION_OFFSET};
 use de_types::projection::ToFlat;
@@ -252,17 +253,19 @@ fn repel_bounds(
         });
 }
 
-fn apply(
-    mut objects: Query<(
-        &mut Repulsion,
-        &DesiredVelocity<PathVelocity>,
-        &mut DesiredVelocity<RepulsionVelocity>,
-    )>,
-) {
+type ApplyComponents<'a> = (
+    &'a mut Repulsion,
+    &'a DesiredVelocity<PathVelocity>,
+    Option<&'a MovementSpeed>,
+    &'a mut DesiredVelocity<RepulsionVelocity>,
+);
+
+fn apply(mut objects: Query<ApplyComponents>) {
     objects.par_iter_mut().for_each_mut(
-        |(mut repulsion, path_velocity, mut repulsion_velocity)| {
+        |(mut repulsion, path_velocity, speed, mut repulsion_velocity)| {
             let velocity = repulsion.apply(path_velocity.velocity());
-            repulsion_velocity.update(velocity.clamp_length_max(MAX_H_SPEED));
+            let max_speed = MAX_H_SPEED * speed.map_or(1., |speed| speed.factor());
+            repulsion_velocity.update(velocity.clamp_length_max(max_speed));
             repulsion.clear();
         },
     );
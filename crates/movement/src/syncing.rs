@@ -88,7 +88,7 @@ fn receive_transforms(
 fn send_transforms(
     time: Res<Time>,
     net_entities: NetEntities,
-    mut entities: Query<(Entity, &mut SyncTimer, &Transform)>,
+    mut entities: Query<(Entity, &mut SyncTimer, &Transform), With<Local>>,
     mut net_events: EventWriter<ToPlayersEvent>,
 ) {
     let time = time.elapsed();
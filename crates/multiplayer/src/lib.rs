@@ -24,9 +24,9 @@ pub use crate::{
     messages::{MessagesSet, ToPlayersEvent},
     netstate::NetState,
     playermsg::{
-        GameNetSet, NetEntities, NetEntityCommands, NetRecvDespawnActiveEvent, NetRecvHealthEvent,
-        NetRecvProjectileEvent, NetRecvSetPathEvent, NetRecvSpawnActiveEvent,
-        NetRecvTransformEvent,
+        GameNetSet, NetEntities, NetEntityCommands, NetRecvBuildEvent, NetRecvDespawnActiveEvent,
+        NetRecvEnergyEvent, NetRecvHealthEvent, NetRecvProjectileEvent, NetRecvSetPathEvent,
+        NetRecvSpawnActiveEvent, NetRecvTransferOwnershipEvent, NetRecvTransformEvent,
     },
 };
 use crate::{netstate::NetStatePlugin, network::NetworkPlugin};
@@ -129,6 +129,10 @@ impl ToPlayersEvent {
     pub fn new(message: ToPlayers) -> Self {
         Self { message }
     }
+
+    pub fn message(&self) -> &ToPlayers {
+        &self.message
+    }
 }
 
 impl ToMessage for ToPlayersEvent {
@@ -140,11 +144,14 @@ impl ToMessage for ToPlayersEvent {
         match self.message {
             ToPlayers::Chat(_) => Reliability::Unordered,
             ToPlayers::Spawn { .. } => Reliability::SemiOrdered,
+            ToPlayers::Build { .. } => Reliability::SemiOrdered,
             ToPlayers::Despawn { .. } => Reliability::SemiOrdered,
             ToPlayers::SetPath { .. } => Reliability::SemiOrdered,
             ToPlayers::Transform { .. } => Reliability::Unreliable,
             ToPlayers::ChangeHealth { .. } => Reliability::SemiOrdered,
+            ToPlayers::EnergyUpdate { .. } => Reliability::Unreliable,
             ToPlayers::Projectile(_) => Reliability::Unreliable,
+            ToPlayers::TransferOwnership { .. } => Reliability::SemiOrdered,
         }
     }
 
@@ -153,7 +160,7 @@ impl ToMessage for ToPlayersEvent {
     }
 }
 
-trait InMessageEvent
+pub(crate) trait InMessageEvent
 where
     Self: Event,
 {
@@ -5,9 +5,13 @@ use bevy::{
 };
 use de_core::{gconfig::GameConfig, schedule::PreMovement, state::AppState};
 use de_messages::{EntityNet, NetEntityIndex, NetProjectile, ToPlayers};
-use de_types::{objects::ActiveObjectType, path::Path, player::Player};
+use de_types::{
+    objects::{ActiveObjectType, BuildingType},
+    path::Path,
+    player::Player,
+};
 
-use crate::messages::{FromPlayersEvent, MessagesSet};
+use crate::messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent};
 
 /// This plugin handles incoming player messages during a multiplayer game.
 pub(crate) struct PlayerMsgPlugin;
@@ -15,11 +19,14 @@ pub(crate) struct PlayerMsgPlugin;
 impl Plugin for PlayerMsgPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<NetRecvSpawnActiveEvent>()
+            .add_event::<NetRecvBuildEvent>()
             .add_event::<NetRecvDespawnActiveEvent>()
             .add_event::<NetRecvHealthEvent>()
+            .add_event::<NetRecvEnergyEvent>()
             .add_event::<NetRecvTransformEvent>()
             .add_event::<NetRecvSetPathEvent>()
             .add_event::<NetRecvProjectileEvent>()
+            .add_event::<NetRecvTransferOwnershipEvent>()
             .add_systems(OnEnter(AppState::InGame), setup)
             .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
@@ -29,6 +36,13 @@ impl Plugin for PlayerMsgPlugin {
                     .run_if(in_state(AppState::InGame))
                     .in_set(GameNetSet::Messages)
                     .after(MessagesSet::RecvMessages),
+            )
+            .add_systems(
+                PostUpdate,
+                track_sent
+                    .run_if(on_event::<ToPlayersEvent>())
+                    .run_if(in_state(AppState::InGame))
+                    .before(MessagesSet::SendMessages),
             );
     }
 }
@@ -84,6 +98,52 @@ impl NetRecvSpawnActiveEvent {
     }
 }
 
+/// This event is sent when a non-local player places a new building. An
+/// empty ECS entity is spawned to obtain local entity ID. The rest is kept to
+/// the handling event systems.
+///
+/// This event is send during [`GameNetSet::Messages`] set.
+#[derive(Event)]
+pub struct NetRecvBuildEvent {
+    player: Player,
+    entity: Entity,
+    building_type: BuildingType,
+    transform: Transform,
+}
+
+impl NetRecvBuildEvent {
+    fn new(
+        player: Player,
+        entity: Entity,
+        building_type: BuildingType,
+        transform: Transform,
+    ) -> Self {
+        Self {
+            player,
+            entity,
+            building_type,
+            transform,
+        }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Local (empty) entity ID.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn building_type(&self) -> BuildingType {
+        self.building_type
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+}
+
 /// This event is sent when an active entity of a non-local player is to be
 /// despawned.
 ///
@@ -127,6 +187,36 @@ impl NetRecvHealthEvent {
     }
 }
 
+/// This event is sent when energy level of an entity's battery is received
+/// from another player.
+///
+/// This event is send during [`GameNetSet::Messages`] set.
+#[derive(Event)]
+pub struct NetRecvEnergyEvent {
+    entity: Entity,
+    level: f64,
+}
+
+impl NetRecvEnergyEvent {
+    /// # Panics
+    ///
+    /// Panics if level is not a finite non-negative number.
+    fn new(entity: Entity, level: f64) -> Self {
+        assert!(level.is_finite());
+        assert!(level >= 0.);
+        Self { entity, level }
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Energy level of the battery in joules.
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+}
+
 #[derive(Event)]
 pub struct NetRecvTransformEvent {
     entity: Entity,
@@ -170,6 +260,31 @@ impl NetRecvSetPathEvent {
 #[derive(Event, Deref)]
 pub struct NetRecvProjectileEvent(NetProjectile);
 
+/// This event is sent when ownership of a non-local entity is transferred to
+/// another player.
+///
+/// This event is send during [`GameNetSet::Messages`] set.
+#[derive(Event)]
+pub struct NetRecvTransferOwnershipEvent {
+    entity: Entity,
+    player: Player,
+}
+
+impl NetRecvTransferOwnershipEvent {
+    fn new(entity: Entity, player: Player) -> Self {
+        Self { entity, player }
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// The new owner of the entity.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+}
+
 #[derive(SystemParam)]
 pub struct NetEntities<'w> {
     config: Res<'w, GameConfig>,
@@ -191,10 +306,15 @@ impl<'w> NetEntities<'w> {
     /// Translates a local entity ID to a remote entity ID. This works only for
     /// locally simulated entities.
     ///
+    /// Entities whose simulation was handed over from another computer keep
+    /// their original remote entity ID.
+    ///
     /// It is assumed that the entity exists.
     pub fn local_net_id(&self, entity: Entity) -> EntityNet {
-        let player = self.config.locals().playable();
-        EntityNet::new(player, entity.into())
+        self.map.translate_local(entity).unwrap_or_else(|| {
+            let player = self.config.locals().playable();
+            EntityNet::new(player, entity.into())
+        })
     }
 }
 
@@ -350,17 +470,52 @@ fn cleanup(mut commands: Commands) {
     commands.remove_resource::<EntityIdMapRes>();
 }
 
+/// Keeps the entity ID mapping in sync with simulation hand overs initiated by
+/// this computer: entities transferred to a non-local player are no longer
+/// locally simulated and despawned entities are forgotten.
+fn track_sent(
+    config: Res<GameConfig>,
+    mut net_commands: NetEntityCommands,
+    mut events: EventReader<ToPlayersEvent>,
+) {
+    for event in events.iter() {
+        match event.message() {
+            ToPlayers::TransferOwnership { entities, player } => {
+                if config.locals().is_local(*player) {
+                    continue;
+                }
+
+                for &entity in entities {
+                    if net_commands.remote_local_id(entity).is_some() {
+                        continue;
+                    }
+                    if let Some(local) = net_commands.local_id(entity) {
+                        net_commands.register(entity, local);
+                    }
+                }
+            }
+            ToPlayers::Despawn { entity } if net_commands.remote_local_id(*entity).is_some() => {
+                net_commands.deregister(*entity);
+            }
+            _ => (),
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn recv_messages(
     mut commands: Commands,
     mut net_commands: NetEntityCommands,
     mut inputs: EventReader<FromPlayersEvent>,
     mut spawn_events: EventWriter<NetRecvSpawnActiveEvent>,
+    mut build_events: EventWriter<NetRecvBuildEvent>,
     mut despawn_events: EventWriter<NetRecvDespawnActiveEvent>,
     mut path_events: EventWriter<NetRecvSetPathEvent>,
     mut transform_events: EventWriter<NetRecvTransformEvent>,
     mut health_events: EventWriter<NetRecvHealthEvent>,
+    mut energy_events: EventWriter<NetRecvEnergyEvent>,
     mut projectile_events: EventWriter<NetRecvProjectileEvent>,
+    mut ownership_events: EventWriter<NetRecvTransferOwnershipEvent>,
 ) {
     for input in inputs.iter() {
         match input.message() {
@@ -380,6 +535,22 @@ fn recv_messages(
                     transform.into(),
                 ));
             }
+            ToPlayers::Build {
+                entity,
+                player,
+                building_type,
+                transform,
+            } => {
+                let local = commands.spawn_empty().id();
+                net_commands.register(*entity, local);
+
+                build_events.send(NetRecvBuildEvent::new(
+                    *player,
+                    local,
+                    *building_type,
+                    transform.into(),
+                ));
+            }
             ToPlayers::Despawn { entity } => {
                 if let Some(local) = net_commands.deregister(*entity) {
                     despawn_events.send(NetRecvDespawnActiveEvent::new(local));
@@ -409,10 +580,188 @@ fn recv_messages(
 
                 health_events.send(NetRecvHealthEvent::new(local, delta.into()));
             }
+            ToPlayers::EnergyUpdate { entity, level } => {
+                let Some(local) = net_commands.local_id(*entity) else {
+                    warn!("Received net energy update of unrecognized entity: {entity:?}");
+                    continue;
+                };
+
+                energy_events.send(NetRecvEnergyEvent::new(local, level.into()));
+            }
             ToPlayers::Projectile(projectile) => {
                 projectile_events.send(NetRecvProjectileEvent(*projectile));
             }
+            ToPlayers::TransferOwnership { entities, player } => {
+                for entity in entities {
+                    let Some(local) = net_commands.remote_local_id(*entity) else {
+                        warn!("Received ownership transfer of unrecognized entity: {entity:?}");
+                        continue;
+                    };
+
+                    ownership_events.send(NetRecvTransferOwnershipEvent::new(local, *player));
+                }
+            }
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::ecs::system::SystemState;
+    use de_core::gconfig::LocalPlayers;
+    use de_messages::{BorrowedFromPlayers, FromPlayers};
+
+    use super::*;
+    use crate::messages::InMessageEvent;
+
+    fn recv_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(EntityIdMapRes::new())
+            .add_event::<FromPlayersEvent>()
+            .add_event::<NetRecvSpawnActiveEvent>()
+            .add_event::<NetRecvBuildEvent>()
+            .add_event::<NetRecvDespawnActiveEvent>()
+            .add_event::<NetRecvSetPathEvent>()
+            .add_event::<NetRecvTransformEvent>()
+            .add_event::<NetRecvHealthEvent>()
+            .add_event::<NetRecvEnergyEvent>()
+            .add_event::<NetRecvProjectileEvent>()
+            .add_event::<NetRecvTransferOwnershipEvent>()
+            .add_systems(Update, recv_messages);
+        app
+    }
+
+    /// Sends a message (as if received from a player) through the
+    /// serialization round trip.
+    fn send_message(app: &mut App, source: Player, message: ToPlayers) {
+        let data = bincode::encode_to_vec(
+            BorrowedFromPlayers::new(source, &message),
+            bincode::config::standard(),
+        )
+        .unwrap();
+        let (message, _): (FromPlayers, usize) =
+            bincode::decode_from_slice(&data, bincode::config::standard()).unwrap();
+        app.world
+            .send_event(FromPlayersEvent::from_message(Instant::now(), message));
+    }
+
+    #[test]
+    fn test_recv_build() {
+        let mut app = recv_app();
+
+        let remote = EntityNet::new(Player::Player2, Entity::from_raw(42).into());
+        let transform = Transform::from_xyz(10., 0., -20.).with_rotation(Quat::from_rotation_y(1.));
+        send_message(
+            &mut app,
+            Player::Player2,
+            ToPlayers::Build {
+                entity: remote,
+                player: Player::Player2,
+                building_type: BuildingType::Base,
+                transform: transform.into(),
+            },
+        );
+
+        app.update();
+
+        let mut state = SystemState::<EventReader<NetRecvBuildEvent>>::new(&mut app.world);
+        let mut events = state.get_mut(&mut app.world);
+        let events: Vec<(Player, Entity, BuildingType, Transform)> = events
+            .iter()
+            .map(|event| {
+                (
+                    event.player(),
+                    event.entity(),
+                    event.building_type(),
+                    event.transform(),
+                )
+            })
+            .collect();
+        assert_eq!(events.len(), 1);
+        let (player, local, building_type, received_transform) = events[0];
+        assert_eq!(player, Player::Player2);
+        assert_eq!(building_type, BuildingType::Base);
+        assert_eq!(received_transform, transform);
+        assert_eq!(
+            app.world
+                .resource::<EntityIdMapRes>()
+                .translate_remote(remote),
+            Some(local)
+        );
+        assert!(app.world.get_entity(local).is_some());
+    }
+
+    #[test]
+    fn test_track_sent() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::new(
+            "/some/path",
+            true,
+            LocalPlayers::from_single(Player::Player1),
+        ))
+        .insert_resource(EntityIdMapRes::new())
+        .add_event::<ToPlayersEvent>()
+        .add_systems(Update, track_sent);
+
+        let kept = app.world.spawn_empty().id();
+        let released = app.world.spawn_empty().id();
+        let net_kept = EntityNet::new(Player::Player1, kept.into());
+        let net_released = EntityNet::new(Player::Player1, released.into());
+
+        app.world
+            .send_event(ToPlayersEvent::new(ToPlayers::TransferOwnership {
+                entities: vec![net_released],
+                player: Player::Player2,
+            }));
+        app.update();
+
+        let map = app.world.resource::<EntityIdMapRes>();
+        assert_eq!(map.translate_remote(net_released), Some(released));
+        assert_eq!(map.translate_local(released), Some(net_released));
+        assert_eq!(map.translate_local(kept), None);
+
+        app.world
+            .send_event(ToPlayersEvent::new(ToPlayers::Despawn {
+                entity: net_released,
+            }));
+        app.world
+            .send_event(ToPlayersEvent::new(ToPlayers::Despawn { entity: net_kept }));
+        app.update();
+
+        let map = app.world.resource::<EntityIdMapRes>();
+        assert_eq!(map.translate_remote(net_released), None);
+        assert_eq!(map.translate_local(released), None);
+    }
+
+    #[test]
+    fn test_recv_energy() {
+        let mut app = recv_app();
+
+        let remote = EntityNet::new(Player::Player2, Entity::from_raw(7).into());
+        let local = app.world.spawn_empty().id();
+        app.world
+            .resource_mut::<EntityIdMapRes>()
+            .register(remote, local);
+
+        send_message(
+            &mut app,
+            Player::Player2,
+            ToPlayers::EnergyUpdate {
+                entity: remote,
+                level: 1234.5.try_into().unwrap(),
+            },
+        );
+        app.update();
+
+        let mut state = SystemState::<EventReader<NetRecvEnergyEvent>>::new(&mut app.world);
+        let mut events = state.get_mut(&mut app.world);
+        let events: Vec<(Entity, f64)> = events
+            .iter()
+            .map(|event| (event.entity(), event.level()))
+            .collect();
+        assert_eq!(events, vec![(local, 1234.5)]);
+    }
+}
@@ -1,20 +1,48 @@
 use parry3d::{
     bounding_volume::Aabb,
     math::{Isometry, Point},
-    query::{intersection_test, PointQuery, Ray, RayCast},
-    shape::{Shape, TriMesh, TriMeshFlags},
+    query::{intersection_test, Ray},
+    shape::{Capsule, Shape, TriMesh, TriMeshFlags},
 };
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone)]
 pub struct ObjectCollider {
     aabb: Aabb,
-    shape: TriMesh,
+    shape: ColliderShape,
+}
+
+/// Solid shape of a collider.
+#[derive(Clone)]
+enum ColliderShape {
+    /// A closed oriented triangle mesh.
+    TriMesh(Box<TriMesh>),
+    /// A capsule, suitable for elongated objects.
+    Capsule(Capsule),
+}
+
+impl ColliderShape {
+    fn as_shape(&self) -> &dyn Shape {
+        match self {
+            Self::TriMesh(mesh) => mesh.as_ref(),
+            Self::Capsule(capsule) => capsule,
+        }
+    }
+
+    /// Returns an arbitrary point on or inside the shape.
+    fn any_point(&self) -> Point<f32> {
+        match self {
+            Self::TriMesh(mesh) => mesh.vertices()[0],
+            Self::Capsule(capsule) => capsule.segment.a,
+        }
+    }
 }
 
 impl ObjectCollider {
-    fn new(aabb: Aabb, shape: TriMesh) -> Self {
-        debug_assert!(shape.pseudo_normals().is_some());
+    fn new(aabb: Aabb, shape: ColliderShape) -> Self {
+        if let ColliderShape::TriMesh(ref mesh) = shape {
+            debug_assert!(mesh.pseudo_normals().is_some());
+        }
         Self { aabb, shape }
     }
 
@@ -23,7 +51,7 @@ impl ObjectCollider {
     }
 
     pub fn cast_ray(&self, position: &Isometry<f32>, ray: &Ray, max_toi: f32) -> Option<f32> {
-        self.shape.cast_ray(position, ray, max_toi, true)
+        self.shape.as_shape().cast_ray(position, ray, max_toi, true)
     }
 
     pub fn intersects(
@@ -41,7 +69,13 @@ impl ObjectCollider {
         if rhs.contains_first_vertex(rhs_position, self, position) {
             return true;
         }
-        intersection_test(position, &self.shape, rhs_position, &rhs.shape).unwrap()
+        intersection_test(
+            position,
+            self.shape.as_shape(),
+            rhs_position,
+            rhs.shape.as_shape(),
+        )
+        .unwrap()
     }
 
     /// Returns true if `self` contains first vertex of `rhs`.
@@ -51,14 +85,28 @@ impl ObjectCollider {
         rhs: &Self,
         rhs_position: &Isometry<f32>,
     ) -> bool {
-        let any_rhs_point = rhs_position.transform_point(&rhs.shape.vertices()[0]);
-        self.shape.contains_point(position, &any_rhs_point)
+        let any_rhs_point = rhs_position.transform_point(&rhs.shape.any_point());
+        self.shape
+            .as_shape()
+            .contains_point(position, &any_rhs_point)
     }
 }
 
 impl From<TriMesh> for ObjectCollider {
     fn from(mesh: TriMesh) -> Self {
-        Self::new(mesh.compute_local_aabb(), mesh)
+        Self::new(
+            mesh.compute_local_aabb(),
+            ColliderShape::TriMesh(Box::new(mesh)),
+        )
+    }
+}
+
+impl From<Capsule> for ObjectCollider {
+    fn from(capsule: Capsule) -> Self {
+        Self::new(
+            capsule.compute_local_aabb(),
+            ColliderShape::Capsule(capsule),
+        )
     }
 }
 
@@ -89,12 +137,35 @@ pub(crate) struct ColliderSerde {
 #[cfg(test)]
 mod tests {
     use parry3d::{
-        math::{Isometry, Vector},
-        shape::{Cuboid, TriMesh, TriMeshFlags},
+        math::{Isometry, Point, Vector},
+        query::Ray,
+        shape::{Capsule, Cuboid, TriMesh, TriMeshFlags},
     };
 
     use crate::ObjectCollider;
 
+    #[test]
+    fn test_capsule() {
+        // A capsule from (-2, 0, 0) to (2, 0, 0) with radius 0.5.
+        let capsule = ObjectCollider::from(Capsule::new_x(2., 0.5));
+        let position = Isometry::translation(0., 1., 0.);
+        let down = Vector::new(0., -1., 0.);
+
+        let body = Ray::new(Point::new(1.5, 10., 0.), down);
+        let toi = capsule.cast_ray(&position, &body, 100.).unwrap();
+        assert!((toi - 8.5).abs() < 1e-4);
+
+        // The ray is within the bounding box but outside the rounded end.
+        let end = Ray::new(Point::new(2.45, 10., 0.45), down);
+        assert!(capsule
+            .aabb()
+            .contains_local_point(&Point::new(2.45, 0., 0.45)));
+        assert!(capsule.cast_ray(&position, &end, 100.).is_none());
+
+        assert!(capsule.intersects(&position, &collider(1.), &Isometry::translation(3., 1., 0.)));
+        assert!(!capsule.intersects(&position, &collider(1.), &Isometry::translation(4., 1., 0.)));
+    }
+
     #[test]
     fn test_intersects() {
         assert!(collider(1.).intersects(
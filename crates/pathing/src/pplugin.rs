@@ -94,11 +94,11 @@ impl UpdateEntityPathEvent {
         Self { entity, target }
     }
 
-    fn entity(&self) -> Entity {
+    pub fn entity(&self) -> Entity {
         self.entity
     }
 
-    fn target(&self) -> PathTarget {
+    pub fn target(&self) -> PathTarget {
         self.target
     }
 }
@@ -23,7 +23,7 @@ pub struct ObjectCounter {
 }
 
 impl ObjectCounter {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             players: AHashMap::new(),
         }
@@ -1,10 +1,14 @@
 use std::marker::PhantomData;
 
-use bevy::ecs::query::{ReadOnlyWorldQuery, WorldQuery};
+use bevy::ecs::query::{Has, ReadOnlyWorldQuery, WorldQuery};
 use bevy::prelude::*;
 use de_audio::spatial::{PlaySpatialAudioEvent, Sound};
 use de_core::gconfig::GameConfig;
-use de_core::{objects::ObjectTypeComponent, player::PlayerComponent, state::AppState};
+use de_core::{
+    objects::{Active, Local, ObjectTypeComponent, Playable},
+    player::PlayerComponent,
+    state::AppState,
+};
 use de_messages::ToPlayers;
 use de_multiplayer::{
     NetEntities, NetEntityCommands, NetRecvDespawnActiveEvent, PeerLeftEvent, ToPlayersEvent,
@@ -29,6 +33,7 @@ impl Plugin for DespawnerPlugin {
                     .after(despawn_active_remote)
                     .before(despawn_active),
                 despawn_active.before(despawn),
+                despawn_dying.before(despawn),
                 despawn,
             )
                 .run_if(in_state(AppState::InGame))
@@ -49,6 +54,13 @@ pub enum DespawnerSet {
     Events,
 }
 
+/// Marker of entities which are despawned during the next frame. Dying
+/// entities are no longer active, playable nor locally simulated and they are
+/// removed from the spatial index, however all their other components are
+/// still available to observers of their death.
+#[derive(Component)]
+pub struct Dying;
+
 #[derive(Event)]
 pub struct DespawnActiveLocalEvent(Entity);
 
@@ -56,6 +68,10 @@ impl DespawnActiveLocalEvent {
     pub fn new(entity: Entity) -> Self {
         Self(entity)
     }
+
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
 }
 
 #[derive(Event)]
@@ -107,15 +123,23 @@ fn despawn_active_peer_left(
 
 fn despawn_active(
     mut counter: ResMut<ObjectCounter>,
-    entities: Query<(&PlayerComponent, &ObjectTypeComponent, &Transform)>,
+    entities: Query<(
+        &PlayerComponent,
+        &ObjectTypeComponent,
+        &Transform,
+        Has<Dying>,
+    )>,
     mut event_reader: EventReader<DespawnActiveEvent>,
     mut event_writer: EventWriter<DespawnEvent>,
     mut play_audio: EventWriter<PlaySpatialAudioEvent>,
 ) {
     for event in event_reader.iter() {
-        let Ok((&player, &object_type, transform)) = entities.get(event.0) else {
+        let Ok((&player, &object_type, transform, dying)) = entities.get(event.0) else {
             panic!("Despawn of non-existing active object requested.");
         };
+        if dying {
+            continue;
+        }
 
         let ObjectType::Active(active_type) = *object_type else {
             panic!("Non-active object cannot be despawned with DespawnActiveEvent.");
@@ -134,10 +158,21 @@ fn despawn_active(
     }
 }
 
-/// Despawn all entities marked for despawning
+/// Mark all entities requested for despawning as dying. They are despawned
+/// during the next frame.
 fn despawn(mut commands: Commands, mut despawning: EventReader<DespawnEvent>) {
     for entity in despawning.iter() {
-        commands.entity(entity.0).despawn_recursive();
+        commands
+            .entity(entity.0)
+            .insert(Dying)
+            .remove::<(Active, Playable, Local)>();
+    }
+}
+
+/// Despawn all entities which were marked as dying during the previous frame.
+fn despawn_dying(mut commands: Commands, dying: Query<Entity, With<Dying>>) {
+    for entity in dying.iter() {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
@@ -349,4 +384,34 @@ mod tests {
         app.update(); // nothing should happen
         trace!("-----------------------------------");
     }
+
+    #[test]
+    fn test_dying() {
+        let mut app = App::new();
+        app.add_event::<DespawnEvent>().add_systems(
+            Update,
+            (despawn_dying.before(despawn), despawn).in_set(DespawnerSet::Despawn),
+        );
+
+        let entity = app
+            .world
+            .spawn((Active, Playable, Local, TestComponent { value: 7 }))
+            .id();
+        app.world.send_event(DespawnEvent(entity));
+        app.update();
+
+        // Dying entities are not playable (e.g. they cannot be selected) but
+        // are still observable.
+        let mut playable = app.world.query_filtered::<Entity, With<Playable>>();
+        assert_eq!(playable.iter(&app.world).count(), 0);
+        let mut observed = app
+            .world
+            .query_filtered::<(Entity, &TestComponent), With<Dying>>();
+        let (observed_entity, data) = observed.single(&app.world);
+        assert_eq!(observed_entity, entity);
+        assert_eq!(data, &TestComponent { value: 7 });
+
+        app.update();
+        assert!(app.world.get_entity(entity).is_none());
+    }
 }
@@ -59,6 +59,30 @@ pub struct DraftBundle {
     ready: DraftReady,
 }
 
+/// Bundle to spawn a construction draft which replaces (upgrades) an existing
+/// building.
+#[derive(Bundle)]
+pub struct UpgradeDraftBundle {
+    draft: DraftBundle,
+    replaces: DraftReplaces,
+}
+
+impl UpgradeDraftBundle {
+    /// # Arguments
+    ///
+    /// * `building_type` - type of the new building.
+    ///
+    /// * `transform` - transform of the replaced building.
+    ///
+    /// * `replaces` - the building to be replaced.
+    pub fn new(building_type: BuildingType, transform: Transform, replaces: Entity) -> Self {
+        Self {
+            draft: DraftBundle::new(building_type, transform),
+            replaces: DraftReplaces(replaces),
+        }
+    }
+}
+
 impl DraftBundle {
     pub fn new(building_type: BuildingType, transform: Transform) -> Self {
         Self {
@@ -74,10 +98,78 @@ impl DraftBundle {
 }
 
 #[derive(Component, Default)]
-pub struct DraftAllowed(bool);
+pub struct DraftAllowed {
+    allowed: bool,
+    reason: Option<DraftBlockReason>,
+}
 
 impl DraftAllowed {
+    pub fn new(allowed: bool) -> Self {
+        Self {
+            allowed,
+            reason: None,
+        }
+    }
+
     pub fn allowed(&self) -> bool {
+        self.allowed
+    }
+
+    /// Returns the reason why the draft cannot be placed. None is returned if
+    /// the draft is allowed or if it was not validated yet.
+    pub fn reason(&self) -> Option<DraftBlockReason> {
+        self.reason
+    }
+}
+
+/// Reason why a draft cannot be placed. When multiple reasons apply, the
+/// first one in the order of the variants below is reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DraftBlockReason {
+    /// The building to be replaced (upgraded) by the draft no longer exists.
+    MissingReplaced,
+    /// The draft is not fully within the map.
+    OutOfMap,
+    /// The draft collides with another solid object.
+    Collision,
+}
+
+impl DraftBlockReason {
+    /// Returns the highest priority reason blocking the draft or None if the
+    /// draft might be placed.
+    ///
+    /// # Arguments
+    ///
+    /// * `replaced_exists` - false if the building replaced by the draft no
+    ///   longer exists.
+    ///
+    /// * `in_map` - true if the draft is fully within the map.
+    ///
+    /// * `collides` - returns true if the draft collides with other objects.
+    ///   It is called only if no other reason applies.
+    fn evaluate(
+        replaced_exists: bool,
+        in_map: bool,
+        collides: impl FnOnce() -> bool,
+    ) -> Option<Self> {
+        if !replaced_exists {
+            Some(Self::MissingReplaced)
+        } else if !in_map {
+            Some(Self::OutOfMap)
+        } else if collides() {
+            Some(Self::Collision)
+        } else {
+            None
+        }
+    }
+}
+
+/// Existing building which is to be replaced by the draft.
+#[derive(Component)]
+pub struct DraftReplaces(Entity);
+
+impl DraftReplaces {
+    pub fn entity(&self) -> Entity {
         self.0
     }
 }
@@ -102,13 +194,25 @@ fn new_draft(
     }
 }
 
+type DraftQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static ObjectTypeComponent,
+        &'static mut DraftAllowed,
+        Option<&'static DraftReplaces>,
+    ),
+>;
+
 fn update_draft(
-    mut drafts: Query<(&Transform, &ObjectTypeComponent, &mut DraftAllowed)>,
+    mut drafts: DraftQuery,
+    buildings: Query<(), With<StaticSolid>>,
     solids: Solids,
     solid_objects: SolidObjects,
     bounds: Res<MapBounds>,
 ) {
-    for (transform, &object_type, mut draft) in drafts.iter_mut() {
+    for (transform, &object_type, mut draft, replaces) in drafts.iter_mut() {
         let collider = QueryCollider::new(
             solid_objects.get(*object_type).collider(),
             Isometry::new(
@@ -122,11 +226,20 @@ fn update_draft(
             let aabb = bounds.aabb();
             Aabb::new(aabb.mins + MAP_OFFSET, aabb.maxs - MAP_OFFSET)
         };
-        let allowed = shrinked_map.contains(&flat_aabb) && !solids.collides(&collider);
-        if allowed != draft.0 {
+        // Footprint of an upgraded building is compatible if it does not
+        // collide with anything but the replaced building.
+        let replaced = replaces.map(|replaces| replaces.entity());
+        let reason = DraftBlockReason::evaluate(
+            replaced.map_or(true, |entity| buildings.contains(entity)),
+            shrinked_map.contains(&flat_aabb),
+            || solids.collides(&collider, replaced),
+        );
+        let allowed = reason.is_none();
+        if allowed != draft.allowed || reason != draft.reason {
             // Access the component mutably only when really needed for optimal
             // Bevy change detection.
-            draft.0 = allowed
+            draft.allowed = allowed;
+            draft.reason = reason;
         }
     }
 }
@@ -228,3 +341,30 @@ fn update_draft_colour(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_reason() {
+        assert_eq!(DraftBlockReason::evaluate(true, true, || false), None);
+        assert_eq!(
+            DraftBlockReason::evaluate(true, true, || true),
+            Some(DraftBlockReason::Collision)
+        );
+        // The draft is both out of the map and colliding.
+        assert_eq!(
+            DraftBlockReason::evaluate(true, false, || true),
+            Some(DraftBlockReason::OutOfMap)
+        );
+        assert_eq!(
+            DraftBlockReason::evaluate(false, false, || true),
+            Some(DraftBlockReason::MissingReplaced)
+        );
+        assert_eq!(
+            DraftBlockReason::evaluate(true, false, || panic!("Collision is not checked.")),
+            Some(DraftBlockReason::OutOfMap)
+        );
+    }
+}
@@ -4,13 +4,17 @@ use bevy::{app::PluginGroupBuilder, prelude::*};
 use counter::CounterPlugin;
 pub use counter::ObjectCounter;
 pub use despawner::{
-    DespawnActiveLocalEvent, DespawnEventsPlugin, DespawnedComponentsEvent, DespawnerSet,
+    DespawnActiveLocalEvent, DespawnEventsPlugin, DespawnedComponentsEvent, DespawnerSet, Dying,
 };
 use draft::DraftPlugin;
-pub use draft::{DraftAllowed, DraftBundle};
+pub use draft::{DraftAllowed, DraftBlockReason, DraftBundle, DraftReplaces, UpgradeDraftBundle};
 use gameend::GameEndPlugin;
+use ownership::OwnershipPlugin;
+pub use ownership::TransferOwnershipEvent;
 use spawner::SpawnerPlugin;
-pub use spawner::{SpawnInactiveEvent, SpawnLocalActiveEvent, SpawnerSet};
+pub use spawner::{
+    AttackOnSpawn, SpawnInactiveEvent, SpawnLocalActiveEvent, SpawnerSet, WaypointsOnSpawn,
+};
 
 use crate::despawner::DespawnerPlugin;
 
@@ -18,6 +22,7 @@ mod counter;
 mod despawner;
 mod draft;
 mod gameend;
+mod ownership;
 mod spawner;
 
 pub struct SpawnerPluginGroup;
@@ -30,5 +35,6 @@ impl PluginGroup for SpawnerPluginGroup {
             .add(DraftPlugin)
             .add(GameEndPlugin)
             .add(DespawnerPlugin)
+            .add(OwnershipPlugin)
     }
 }
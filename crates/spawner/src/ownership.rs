@@ -0,0 +1,307 @@
+use bevy::prelude::*;
+use de_core::{
+    gconfig::{is_multiplayer, GameConfig},
+    objects::{Local, ObjectTypeComponent, Playable},
+    player::PlayerComponent,
+    state::AppState,
+};
+use de_messages::{EntityNet, ToPlayers};
+use de_multiplayer::{NetEntities, NetRecvTransferOwnershipEvent, ToPlayersEvent};
+use de_pathing::PathTarget;
+use de_types::{objects::ObjectType, player::Player};
+
+use crate::{Dying, ObjectCounter, SpawnerSet};
+
+pub(crate) struct OwnershipPlugin;
+
+impl Plugin for OwnershipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransferOwnershipEvent>()
+            .add_event::<OwnershipTransferredEvent>()
+            .add_systems(
+                Update,
+                (
+                    transfer_local.run_if(on_event::<TransferOwnershipEvent>()),
+                    transfer_remote.run_if(on_event::<NetRecvTransferOwnershipEvent>()),
+                    send_transferred
+                        .run_if(is_multiplayer)
+                        .run_if(on_event::<OwnershipTransferredEvent>())
+                        .after(transfer_local),
+                )
+                    .run_if(in_state(AppState::InGame))
+                    .after(SpawnerSet::Spawner),
+            );
+    }
+}
+
+/// Send this event to transfer ownership of locally simulated active objects
+/// to another player.
+///
+/// Objects transferred to a player simulated by another computer are no longer
+/// locally simulated, their simulation is taken over by the computer of the
+/// new owner. Entities which are not locally simulated are ignored.
+#[derive(Event)]
+pub struct TransferOwnershipEvent {
+    entities: Vec<Entity>,
+    player: Player,
+}
+
+impl TransferOwnershipEvent {
+    /// # Arguments
+    ///
+    /// * `entities` - entities to be transferred.
+    ///
+    /// * `player` - the new owner of the entities.
+    pub fn new(entities: Vec<Entity>, player: Player) -> Self {
+        Self { entities, player }
+    }
+}
+
+/// This event is sent after ownership of locally simulated entities is
+/// transferred.
+#[derive(Event)]
+struct OwnershipTransferredEvent {
+    entities: Vec<Entity>,
+    player: Player,
+}
+
+type OwnedQuery<'w, 's, F = ()> =
+    Query<'w, 's, (&'static mut PlayerComponent, &'static ObjectTypeComponent), F>;
+
+fn transfer_local(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut counter: ResMut<ObjectCounter>,
+    mut owned: OwnedQuery<With<Local>>,
+    mut in_events: EventReader<TransferOwnershipEvent>,
+    mut out_events: EventWriter<OwnershipTransferredEvent>,
+) {
+    for event in in_events.iter() {
+        let mut transferred = Vec::with_capacity(event.entities.len());
+
+        for &entity in &event.entities {
+            let Ok((mut owner, &object_type)) = owned.get_mut(entity) else {
+                continue;
+            };
+            if **owner == event.player {
+                continue;
+            }
+
+            change_owner(&mut counter, &mut owner, *object_type, event.player);
+
+            let mut entity_commands = commands.entity(entity);
+            if !config.locals().is_local(event.player) {
+                entity_commands.remove::<(Local, Playable, PathTarget)>();
+            } else if config.locals().is_playable(event.player) || cfg!(feature = "godmode") {
+                entity_commands.insert(Playable);
+            } else {
+                entity_commands.remove::<Playable>();
+            }
+
+            transferred.push(entity);
+        }
+
+        if !transferred.is_empty() {
+            out_events.send(OwnershipTransferredEvent {
+                entities: transferred,
+                player: event.player,
+            });
+        }
+    }
+}
+
+fn transfer_remote(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut counter: ResMut<ObjectCounter>,
+    // Dying entities are no longer local, yet they must not be transferred.
+    mut owned: OwnedQuery<(Without<Local>, Without<Dying>)>,
+    mut events: EventReader<NetRecvTransferOwnershipEvent>,
+) {
+    for event in events.iter() {
+        let Ok((mut owner, &object_type)) = owned.get_mut(event.entity()) else {
+            continue;
+        };
+        if **owner == event.player() {
+            continue;
+        }
+
+        change_owner(&mut counter, &mut owner, *object_type, event.player());
+
+        // Objects transferred to a player simulated by this computer are taken
+        // over by it.
+        if config.locals().is_local(event.player()) {
+            let mut entity_commands = commands.entity(event.entity());
+            entity_commands.insert(Local);
+            if config.locals().is_playable(event.player()) || cfg!(feature = "godmode") {
+                entity_commands.insert(Playable);
+            }
+        }
+    }
+}
+
+fn change_owner(
+    counter: &mut ObjectCounter,
+    owner: &mut PlayerComponent,
+    object_type: ObjectType,
+    player: Player,
+) {
+    let ObjectType::Active(object_type) = object_type else {
+        panic!("Only active objects have an owner.");
+    };
+
+    counter.player_mut(**owner).update(object_type, -1);
+    counter.player_mut(player).update(object_type, 1);
+    *owner = player.into();
+}
+
+fn send_transferred(
+    net_entities: NetEntities,
+    mut in_events: EventReader<OwnershipTransferredEvent>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    for event in in_events.iter() {
+        net_events.send(ToPlayersEvent::new(transfer_message(event, |entity| {
+            net_entities.local_net_id(entity)
+        })));
+    }
+}
+
+fn transfer_message(
+    event: &OwnershipTransferredEvent,
+    net_id: impl Fn(Entity) -> EntityNet,
+) -> ToPlayers {
+    ToPlayers::TransferOwnership {
+        entities: event
+            .entities
+            .iter()
+            .map(|&entity| net_id(entity))
+            .collect(),
+        player: event.player,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use de_core::gconfig::LocalPlayers;
+    use de_types::objects::{ActiveObjectType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_transfer_local() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::new(
+            "/some/path",
+            true,
+            LocalPlayers::from_single(Player::Player1),
+        ))
+        .insert_resource(ObjectCounter::new())
+        .add_event::<TransferOwnershipEvent>()
+        .add_event::<OwnershipTransferredEvent>()
+        .add_systems(Update, transfer_local);
+
+        let object_type = ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker));
+        let spawn = |world: &mut World, local: bool| {
+            let mut entity = world.spawn((
+                PlayerComponent::from(Player::Player1),
+                ObjectTypeComponent::from(object_type),
+                Playable,
+            ));
+            if local {
+                entity.insert(Local);
+            }
+            entity.id()
+        };
+        let entity_a = spawn(&mut app.world, true);
+        let entity_b = spawn(&mut app.world, true);
+        let entity_c = spawn(&mut app.world, false);
+        app.world
+            .resource_mut::<ObjectCounter>()
+            .player_mut(Player::Player1)
+            .update(ActiveObjectType::Unit(UnitType::Attacker), 3);
+
+        app.world.send_event(TransferOwnershipEvent::new(
+            vec![entity_a, entity_b, entity_c],
+            Player::Player2,
+        ));
+        app.update();
+
+        for entity in [entity_a, entity_b] {
+            let entity = app.world.entity(entity);
+            assert_eq!(**entity.get::<PlayerComponent>().unwrap(), Player::Player2);
+            assert!(!entity.contains::<Local>());
+            assert!(!entity.contains::<Playable>());
+        }
+        let entity = app.world.entity(entity_c);
+        assert_eq!(**entity.get::<PlayerComponent>().unwrap(), Player::Player1);
+        assert!(entity.contains::<Playable>());
+
+        let counter = app.world.resource::<ObjectCounter>();
+        assert_eq!(counter.player(Player::Player1).unwrap().unit_count(), 1);
+        assert_eq!(counter.player(Player::Player2).unwrap().unit_count(), 2);
+
+        let mut state = SystemState::<EventReader<OwnershipTransferredEvent>>::new(&mut app.world);
+        let mut events = state.get_mut(&mut app.world);
+        let events: Vec<&OwnershipTransferredEvent> = events.iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entities, vec![entity_a, entity_b]);
+        assert_eq!(events[0].player, Player::Player2);
+
+        let message = transfer_message(events[0], |entity| {
+            EntityNet::new(Player::Player1, entity.into())
+        });
+        let ToPlayers::TransferOwnership { entities, player } = message else {
+            panic!("Unexpected message: {message:?}");
+        };
+        assert_eq!(
+            entities,
+            vec![
+                EntityNet::new(Player::Player1, entity_a.into()),
+                EntityNet::new(Player::Player1, entity_b.into()),
+            ]
+        );
+        assert_eq!(player, Player::Player2);
+    }
+
+    #[test]
+    fn test_transfer_local_player() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::new(
+            "/some/path",
+            true,
+            LocalPlayers::from_max_player(Player::Player1, Player::Player2),
+        ))
+        .insert_resource(ObjectCounter::new())
+        .add_event::<TransferOwnershipEvent>()
+        .add_event::<OwnershipTransferredEvent>()
+        .add_systems(Update, transfer_local);
+
+        let unit_type = ActiveObjectType::Unit(UnitType::Attacker);
+        let entity = app
+            .world
+            .spawn((
+                PlayerComponent::from(Player::Player1),
+                ObjectTypeComponent::from(ObjectType::Active(unit_type)),
+                Local,
+                Playable,
+            ))
+            .id();
+        app.world
+            .resource_mut::<ObjectCounter>()
+            .player_mut(Player::Player1)
+            .update(unit_type, 1);
+
+        app.world
+            .send_event(TransferOwnershipEvent::new(vec![entity], Player::Player2));
+        app.update();
+
+        // The new owner is simulated by this computer, thus the object stays
+        // local but it is no longer playable.
+        let entity = app.world.entity(entity);
+        assert_eq!(**entity.get::<PlayerComponent>().unwrap(), Player::Player2);
+        assert!(entity.contains::<Local>());
+        assert!(!entity.contains::<Playable>());
+    }
+}
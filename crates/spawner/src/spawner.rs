@@ -9,9 +9,9 @@ use de_core::{
     player::PlayerComponent,
     state::AppState,
 };
-use de_energy::Battery;
+use de_energy::{Battery, Throttle};
 use de_messages::ToPlayers;
-use de_multiplayer::{NetEntities, NetRecvSpawnActiveEvent, ToPlayersEvent};
+use de_multiplayer::{NetEntities, NetRecvBuildEvent, NetRecvSpawnActiveEvent, ToPlayersEvent};
 use de_objects::{AssetCollection, InitialHealths, SceneType, Scenes, SolidObjects};
 use de_pathing::{PathTarget, UpdateEntityPathEvent};
 use de_terrain::{CircleMarker, MarkerVisibility, RectangleMarker};
@@ -37,6 +37,9 @@ impl Plugin for SpawnerPlugin {
                     spawn_remote_active
                         .run_if(on_event::<NetRecvSpawnActiveEvent>())
                         .before(spawn_active),
+                    spawn_remote_building
+                        .run_if(on_event::<NetRecvBuildEvent>())
+                        .before(spawn_active),
                     spawn_active.before(spawn),
                     spawn_inactive.before(spawn),
                     spawn,
@@ -59,6 +62,8 @@ pub struct SpawnLocalActiveEvent {
     transform: Transform,
     player: Player,
     path_target: Option<PathTarget>,
+    enemy: Option<Entity>,
+    waypoints: Vec<Vec2>,
 }
 
 impl SpawnLocalActiveEvent {
@@ -77,8 +82,68 @@ impl SpawnLocalActiveEvent {
             transform,
             player,
             path_target,
+            enemy: None,
+            waypoints: Vec::new(),
         }
     }
+
+    /// The spawned object starts attacking an enemy right after it is spawned.
+    /// See [`AttackOnSpawn`].
+    pub fn with_enemy(mut self, enemy: Entity) -> Self {
+        self.enemy = Some(enemy);
+        self
+    }
+
+    /// The spawned object moves through the waypoints (in the given order)
+    /// right after it is spawned. See [`WaypointsOnSpawn`].
+    pub fn with_waypoints(mut self, waypoints: Vec<Vec2>) -> Self {
+        self.waypoints = waypoints;
+        self
+    }
+
+    pub fn object_type(&self) -> ActiveObjectType {
+        self.object_type
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn enemy(&self) -> Option<Entity> {
+        self.enemy
+    }
+
+    pub fn waypoints(&self) -> &[Vec2] {
+        self.waypoints.as_slice()
+    }
+}
+
+/// Enemy to be attacked by a freshly spawned locally simulated object. The
+/// component is inserted during spawning and it is expected to be removed
+/// once the attack is initiated.
+#[derive(Component)]
+pub struct AttackOnSpawn(Entity);
+
+impl AttackOnSpawn {
+    pub fn enemy(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Points to be visited by a freshly spawned locally simulated object. The
+/// component is inserted during spawning and it is expected to be removed
+/// once the points are queued.
+#[derive(Component)]
+pub struct WaypointsOnSpawn(Vec<Vec2>);
+
+impl WaypointsOnSpawn {
+    pub fn waypoints(&self) -> &[Vec2] {
+        self.0.as_slice()
+    }
 }
 
 #[derive(Event)]
@@ -153,6 +218,12 @@ fn spawn_local_active(
         if config.locals().is_playable(event.player) || cfg!(feature = "godmode") {
             entity_commands.insert(Playable);
         }
+        if let Some(enemy) = event.enemy {
+            entity_commands.insert(AttackOnSpawn(enemy));
+        }
+        if !event.waypoints.is_empty() {
+            entity_commands.insert(WaypointsOnSpawn(event.waypoints.clone()));
+        }
 
         let entity = entity_commands.id();
         event_writer.send(SpawnActiveEvent::new(
@@ -167,12 +238,23 @@ fn spawn_local_active(
         }
 
         if config.multiplayer() {
-            net_events.send(ToPlayersEvent::new(ToPlayers::Spawn {
-                entity: net_entities.local_net_id(entity),
-                player: event.player,
-                object_type: event.object_type,
-                transform: event.transform.into(),
-            }));
+            let entity = net_entities.local_net_id(entity);
+            let transform = event.transform.into();
+            let message = match event.object_type {
+                ActiveObjectType::Building(building_type) => ToPlayers::Build {
+                    entity,
+                    player: event.player,
+                    building_type,
+                    transform,
+                },
+                object_type => ToPlayers::Spawn {
+                    entity,
+                    player: event.player,
+                    object_type,
+                    transform,
+                },
+            };
+            net_events.send(ToPlayersEvent::new(message));
         }
     }
 }
@@ -191,6 +273,20 @@ fn spawn_remote_active(
     }
 }
 
+fn spawn_remote_building(
+    mut event_reader: EventReader<NetRecvBuildEvent>,
+    mut event_writer: EventWriter<SpawnActiveEvent>,
+) {
+    for event in event_reader.iter() {
+        event_writer.send(SpawnActiveEvent::new(
+            event.entity(),
+            ActiveObjectType::Building(event.building_type()),
+            event.transform(),
+            event.player(),
+        ));
+    }
+}
+
 fn spawn_active(
     mut commands: Commands,
     mut counter: ResMut<ObjectCounter>,
@@ -230,7 +326,11 @@ fn spawn_active(
             }
             ActiveObjectType::Unit(_) => {
                 let radius = solid.ichnography().radius();
-                entity_commands.insert((MovableSolid, CircleMarker::new(radius)));
+                entity_commands.insert((
+                    MovableSolid,
+                    Throttle::default(),
+                    CircleMarker::new(radius),
+                ));
 
                 audio_events.send(PlaySpatialAudioEvent::new(
                     Sound::Manufacture,
@@ -48,15 +48,28 @@ trait Marker {
 
 /// This component configures a semi-transparent circle drawn on the terrain
 /// surface below the entity.
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 pub struct CircleMarker {
     radius: f32,
+    color: Color,
 }
 
 impl CircleMarker {
-    /// Crates a new circle marker.
+    /// Crates a new (white) circle marker.
     pub fn new(radius: f32) -> Self {
-        Self { radius }
+        Self {
+            radius,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets the color of the marker. Alpha of the color is ignored.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
     }
 }
 
@@ -65,7 +78,7 @@ impl Marker for CircleMarker {
     const UNIFORM_CAPACITY: usize = CIRCLE_CAPACITY;
 
     fn as_shape(&self, position: Vec2) -> Self::Shape {
-        Circle::new(position, self.radius)
+        Circle::new(position, self.radius, self.color)
     }
 
     fn apply_to_material(material: &mut TerrainMaterial, shapes: Vec<Self::Shape>) {
@@ -1,11 +1,11 @@
 use std::{cmp::Ordering, ops::Range};
 
 use bevy::{
-    prelude::{Handle, Image, Material},
+    prelude::{Color, Handle, Image, Material},
     reflect::{TypePath, TypeUuid},
     render::render_resource::{AsBindGroup, ShaderRef, ShaderType},
 };
-use glam::{Mat3, Vec2};
+use glam::{Mat3, Vec2, Vec3};
 
 // * Keep this in sync with terrain.wgsl.
 // * Keep this smaller or equal to de_types::objects::PLAYER_MAX_UNITS.
@@ -55,6 +55,7 @@ pub(crate) struct Circle {
     #[align(16)]
     center: Vec2,
     radius: f32,
+    color: Vec3,
 }
 
 impl Circle {
@@ -64,7 +65,7 @@ impl Circle {
     ///
     /// * If `center` is not finite.
     /// * If radius is non finite or is smaller or equal to zero.
-    pub(crate) fn new(center: Vec2, radius: f32) -> Self {
+    pub(crate) fn new(center: Vec2, radius: f32, color: Color) -> Self {
         if !center.is_finite() {
             panic!("Circle center is not finite: {center:?}");
         }
@@ -75,7 +76,12 @@ impl Circle {
             panic!("Circle radius is smaller or equal to 0: {radius:?}");
         }
 
-        Self { center, radius }
+        let [r, g, b, _] = color.as_linear_rgba_f32();
+        Self {
+            center,
+            radius,
+            color: Vec3::new(r, g, b),
+        }
     }
 
     fn coord(&self, axis: Axis) -> f32 {
@@ -257,10 +263,10 @@ mod tests {
     #[test]
     fn test_kd_tree_build_many() {
         let mut circles = vec![
-            Circle::new(Vec2::new(1., -4.), 1.),
-            Circle::new(Vec2::new(-2., 1.), 1.),
-            Circle::new(Vec2::new(-1.5, -3.), 1.),
-            Circle::new(Vec2::new(2., 1.), 1.),
+            Circle::new(Vec2::new(1., -4.), 1., Color::WHITE),
+            Circle::new(Vec2::new(-2., 1.), 1., Color::WHITE),
+            Circle::new(Vec2::new(-1.5, -3.), 1., Color::WHITE),
+            Circle::new(Vec2::new(2., 1.), 1., Color::WHITE),
         ];
 
         let mut tree = KdTree::empty();
@@ -276,7 +282,7 @@ mod tests {
             assert_eq!(tree.nodes[3].center, Vec2::new(-1.5, -3.));
         }
 
-        circles.push(Circle::new(Vec2::new(-8., 2.), 1.));
+        circles.push(Circle::new(Vec2::new(-8., 2.), 1., Color::WHITE));
         for permutation in (0..circles.len()).permutations(circles.len()) {
             let version: Vec<Circle> = permutation.iter().map(|index| circles[*index]).collect();
             tree.rebuild(version);
@@ -289,7 +295,7 @@ mod tests {
             assert_eq!(tree.nodes[4].center, Vec2::new(-8., 2.));
         }
 
-        circles.push(Circle::new(Vec2::new(1.5, 0.), 1.));
+        circles.push(Circle::new(Vec2::new(1.5, 0.), 1., Color::WHITE));
         for permutation in (0..circles.len()).permutations(circles.len()) {
             let version: Vec<Circle> = permutation.iter().map(|index| circles[*index]).collect();
             tree.rebuild(version);